@@ -0,0 +1,73 @@
+//! 以 age 格式加密导出（可选功能 `age`），生成的文件可用标准 `age -d` 命令解密
+
+use anyhow::{anyhow, Result};
+use std::io::Write;
+
+/// 使用口令加密（age 的 scrypt 口令模式）
+pub fn encrypt_with_passphrase(data: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    let encryptor = age::Encryptor::with_user_passphrase(age::secrecy::SecretString::from(passphrase.to_string()));
+    finish(encryptor, data)
+}
+
+/// 加密给一个 age X25519 接收者（`age1...` 公钥）
+pub fn encrypt_to_recipient(data: &[u8], recipient: &str) -> Result<Vec<u8>> {
+    let recipient = parse_recipient(recipient)?;
+    let encryptor = age::Encryptor::with_recipients(std::iter::once(&recipient as &dyn age::Recipient))
+        .map_err(|e| anyhow!("无法创建 age 加密器: {}", e))?;
+    finish(encryptor, data)
+}
+
+/// 校验 age X25519 接收者公钥
+pub fn parse_recipient(recipient: &str) -> Result<age::x25519::Recipient> {
+    recipient
+        .trim()
+        .parse()
+        .map_err(|e| anyhow!("无效的 age 接收者公钥（应以 age1 开头）: {}", e))
+}
+
+/// 写入数据并完成 age 流（二进制格式）
+fn finish(encryptor: age::Encryptor, data: &[u8]) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    let mut writer = encryptor
+        .wrap_output(&mut out)
+        .map_err(|e| anyhow!("age 加密失败: {}", e))?;
+    writer.write_all(data)?;
+    writer.finish().map_err(|e| anyhow!("age 加密失败: {}", e))?;
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    fn decrypt(data: &[u8], identity: &dyn age::Identity) -> Vec<u8> {
+        let decryptor = age::Decryptor::new(data).unwrap();
+        let mut reader = decryptor.decrypt(std::iter::once(identity)).unwrap();
+        let mut out = Vec::new();
+        reader.read_to_end(&mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn passphrase_round_trip() {
+        let data = b"encrypted abu private key blob";
+        let encrypted = encrypt_with_passphrase(data, "Correct-Horse-Battery-42").unwrap();
+        assert!(encrypted.starts_with(b"age-encryption.org/v1\n"));
+
+        let identity = age::scrypt::Identity::new(age::secrecy::SecretString::from("Correct-Horse-Battery-42".to_string()));
+        assert_eq!(decrypt(&encrypted, &identity), data);
+    }
+
+    #[test]
+    fn recipient_round_trip() {
+        let data = b"encrypted abu private key blob";
+        let identity = age::x25519::Identity::generate();
+        let recipient = identity.to_public().to_string();
+
+        let encrypted = encrypt_to_recipient(data, &format!(" {}\n", recipient)).unwrap();
+        assert_eq!(decrypt(&encrypted, &identity), data);
+
+        assert!(encrypt_to_recipient(data, "not-a-recipient").is_err());
+    }
+}
@@ -0,0 +1,100 @@
+use anyhow::{anyhow, Result};
+
+/// 备份包魔数（位于加密前的明文开头）
+const BUNDLE_MAGIC: &[u8; 4] = b"ABUB";
+
+/// 将多个文件打包为单个字节流
+///
+/// 格式：`ABUB` || 条目数(u16) || 每个条目 [名称长度(u16) || 名称 || 数据长度(u64) || 数据]，
+/// 整数均为大端序。打包结果随后以密码加密保存。
+pub fn pack(entries: &[(String, Vec<u8>)]) -> Result<Vec<u8>> {
+    let count = u16::try_from(entries.len()).map_err(|_| anyhow!("备份文件数量过多"))?;
+
+    let mut out = Vec::new();
+    out.extend_from_slice(BUNDLE_MAGIC);
+    out.extend_from_slice(&count.to_be_bytes());
+    for (name, data) in entries {
+        let name_len = u16::try_from(name.len()).map_err(|_| anyhow!("文件名过长: {}", name))?;
+        out.extend_from_slice(&name_len.to_be_bytes());
+        out.extend_from_slice(name.as_bytes());
+        out.extend_from_slice(&(data.len() as u64).to_be_bytes());
+        out.extend_from_slice(data);
+    }
+
+    Ok(out)
+}
+
+/// 解析 `pack` 生成的字节流，返回 (文件名, 内容) 列表
+pub fn unpack(data: &[u8]) -> Result<Vec<(String, Vec<u8>)>> {
+    let mut reader = Cursor { data, pos: 0 };
+    if reader.take(4)? != BUNDLE_MAGIC {
+        return Err(anyhow!("不是有效的 ABU 备份包"));
+    }
+
+    let count = u16::from_be_bytes(reader.take(2)?.try_into()?);
+    let mut entries = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let name_len = u16::from_be_bytes(reader.take(2)?.try_into()?) as usize;
+        let name = String::from_utf8(reader.take(name_len)?.to_vec())
+            .map_err(|_| anyhow!("备份包中的文件名不是有效的 UTF-8"))?;
+        let data_len = u64::from_be_bytes(reader.take(8)?.try_into()?);
+        let data_len = usize::try_from(data_len).map_err(|_| anyhow!("备份包条目过大"))?;
+        entries.push((name, reader.take(data_len)?.to_vec()));
+    }
+
+    if reader.pos != data.len() {
+        return Err(anyhow!("备份包末尾存在多余数据"));
+    }
+
+    Ok(entries)
+}
+
+/// 简单的只读游标，越界时返回错误而不是 panic
+struct Cursor<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.data.len())
+            .ok_or_else(|| anyhow!("备份包已截断"))?;
+        let slice = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encryption::{self, AeadCipher};
+    use crate::security::{KdfHash, KdfParams};
+
+    #[test]
+    fn encrypted_bundle_round_trips_all_files() {
+        let entries = vec![
+            ("bank_public.asc".to_string(), b"public".to_vec()),
+            ("bank_private.bin".to_string(), vec![0u8, 1, 2, 255]),
+            ("bank_revocation.asc".to_string(), b"revocation".to_vec()),
+            ("bank_metadata.json".to_string(), Vec::new()),
+        ];
+        let kdf = KdfParams { hash: KdfHash::Sha256, iterations: crate::config::MIN_ITERATIONS };
+
+        let packed = pack(&entries).unwrap();
+        let encrypted = encryption::encrypt_with_password(&packed, "bundle password", &kdf, AeadCipher::default()).unwrap();
+        let decrypted = encryption::decrypt_with_password(&encrypted, "bundle password").unwrap();
+
+        assert_eq!(unpack(&decrypted).unwrap(), entries);
+    }
+
+    #[test]
+    fn truncated_bundle_is_rejected() {
+        let packed = pack(&[("a".to_string(), b"data".to_vec())]).unwrap();
+
+        assert!(unpack(&packed[..packed.len() - 1]).is_err());
+    }
+}
@@ -0,0 +1,344 @@
+use crate::config::Config;
+use crate::{commands, encryption, naming, pgp, security, timestamp, userid};
+use anyhow::{anyhow, Result};
+use sequoia_openpgp::armor::Kind;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 未指定 --report 时，报告文件在输出目录中的文件名
+pub const DEFAULT_REPORT_NAME: &str = "batch_report.json";
+
+/// CSV 表头（可省略）
+const CSV_HEADER: &str = "bank_name,email";
+
+/// 输入中的一行：银行名称与邮箱
+struct BatchRow {
+    /// 在输入文件中的行号（从 1 开始）
+    line: usize,
+    bank_name: String,
+    email: String,
+}
+
+/// 单行的处理结果
+#[derive(Serialize)]
+struct RowReport {
+    line: usize,
+    bank_name: String,
+    status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fingerprint: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    outputs: Vec<PathBuf>,
+}
+
+/// 批量生成的汇总报告，供操作员留档审计
+#[derive(Serialize)]
+struct BatchReport {
+    generated_at: String,
+    input: PathBuf,
+    succeeded: usize,
+    failed: usize,
+    rows: Vec<RowReport>,
+}
+
+/// 解析 `bank_name,email` 格式的 CSV；跳过空行、`#` 注释行与表头
+///
+/// 不支持引号转义，银行名称中不能包含逗号。格式错误的行保留为失败行，不中断整批。
+fn parse_rows(text: &str) -> Vec<Result<BatchRow, (usize, String)>> {
+    text.lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .filter(|(_, line)| !line.eq_ignore_ascii_case(CSV_HEADER))
+        .map(|(line, text)| {
+            let fields: Vec<&str> = text.split(',').map(str::trim).collect();
+            match fields.as_slice() {
+                [bank_name, email] if !bank_name.is_empty() => Ok(BatchRow {
+                    line,
+                    bank_name: bank_name.to_string(),
+                    email: email.to_string(),
+                }),
+                _ => Err((line, format!("格式无效，应为 {}: {}", CSV_HEADER, text))),
+            }
+        })
+        .collect()
+}
+
+/// 检查系统时钟偏差（配置了时间源时）；非交互模式不询问，只在 stderr 提示
+pub fn warn_clock_skew(config: &Config) {
+    if let Some(url) = &config.time_source_url {
+        match crate::clock::check(url) {
+            Ok(Some(skew)) => eprintln!("警告：系统时钟与时间源相差约 {} 秒，生成的证书时间可能不正确", skew),
+            Ok(None) => {}
+            Err(e) => eprintln!("无法检查系统时钟: {}", e),
+        }
+    }
+}
+
+/// 按 CSV 批量生成密钥，逐行输出进度，并写出汇总报告
+///
+/// 所有密钥使用同一私钥密码，文件写入配置的输出目录（未配置时为当前目录）。
+/// 单行失败不影响其他行；只要有失败行，写出报告后返回错误。
+pub fn run(config: &Config, input: &Path, password_file: Option<&Path>, report: Option<&Path>) -> Result<()> {
+    security::check_backend()?;
+    warn_clock_skew(config);
+
+    let text = fs::read_to_string(input)
+        .map_err(|e| anyhow!("无法读取批量输入文件 {}: {}", input.display(), e))?;
+    let rows = parse_rows(&text);
+    if rows.is_empty() {
+        return Err(anyhow!("批量输入文件中没有数据行: {}", input.display()));
+    }
+
+    let output_dir = config.output_dir.clone().unwrap_or_else(|| PathBuf::from("."));
+    let report_path = report
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| output_dir.join(DEFAULT_REPORT_NAME));
+    let password = zeroize::Zeroizing::new(commands::read_password(password_file)?);
+    config.password_policy.check(&password).map_err(|e| anyhow!("私钥密码不符合要求: {}", e))?;
+
+    let total = rows.len();
+    let mut reports = Vec::with_capacity(total);
+    for (index, row) in rows.into_iter().enumerate() {
+        let report = match row {
+            Ok(row) => {
+                eprintln!("[{}/{}] 正在生成: {}", index + 1, total, row.bank_name);
+                match generate_row(config, &row, &password, &output_dir) {
+                    Ok((fingerprint, outputs)) => RowReport {
+                        line: row.line,
+                        bank_name: row.bank_name,
+                        status: "ok",
+                        fingerprint: Some(fingerprint),
+                        error: None,
+                        outputs,
+                    },
+                    Err(e) => RowReport {
+                        line: row.line,
+                        bank_name: row.bank_name,
+                        status: "failed",
+                        fingerprint: None,
+                        error: Some(e.to_string()),
+                        outputs: Vec::new(),
+                    },
+                }
+            }
+            Err((line, error)) => RowReport {
+                line,
+                bank_name: String::new(),
+                status: "failed",
+                fingerprint: None,
+                error: Some(error),
+                outputs: Vec::new(),
+            },
+        };
+        match &report.error {
+            Some(error) => eprintln!("[{}/{}] 第 {} 行失败: {}", index + 1, total, report.line, error),
+            None => eprintln!("[{}/{}] 完成", index + 1, total),
+        }
+        reports.push(report);
+    }
+
+    let failed = reports.iter().filter(|r| r.error.is_some()).count();
+    let summary = BatchReport {
+        generated_at: timestamp::Timestamp::now(config.utc).rfc3339(),
+        input: input.to_path_buf(),
+        succeeded: total - failed,
+        failed,
+        rows: reports,
+    };
+    fs::write(&report_path, serde_json::to_string_pretty(&summary)?)
+        .map_err(|e| anyhow!("无法写出报告 {}: {}", report_path.display(), e))?;
+    eprintln!(
+        "批量生成结束：成功 {}，失败 {}。报告已保存到: {}",
+        summary.succeeded,
+        failed,
+        report_path.display(),
+    );
+
+    if failed > 0 {
+        return Err(anyhow!("{} 行生成失败，详见报告", failed));
+    }
+    Ok(())
+}
+
+/// 生成单行的密钥并写出公钥、加密私钥与吊销证书，返回指纹与输出路径
+fn generate_row(config: &Config, row: &BatchRow, password: &str, output_dir: &Path) -> Result<(String, Vec<PathBuf>)> {
+    let mut user_ids = userid::UserIdSet::new()
+        .with_allowed_domains(&config.allowed_email_domains)
+        .with_comment(config.test_key.then_some(pgp::TEST_KEY_COMMENT));
+    user_ids.add(&row.bank_name, &row.email)?;
+
+    let options = security::KeyOptions {
+        curve: if config.minimal_cert { security::KeyCurve::Rsa3072 } else { config.default_curve },
+        primary_curve: config.primary_curve,
+        validity: security::validity_days(config.key_expiry_days),
+        notations: config.notations.clone(),
+        profile: config.profile,
+        minimal: config.minimal_cert,
+        signing_subkey: true,
+        encryption_subkey: true,
+        signing_validity: security::validity_days(config.signing_subkey_expiry_days),
+        encryption_validity: security::validity_days(config.encryption_subkey_expiry_days),
+        revocation_keys: commands::load_designated_revokers(config.designated_revoker.as_deref())?,
+    };
+    generate_and_save(config, &user_ids, &row.bank_name, &row.email, &options, password, output_dir)
+}
+
+/// 按给定参数生成密钥，写出公钥、加密私钥与吊销证书（非交互），返回指纹与输出路径
+///
+/// `bank_name` 与 `email` 仅用于按命名模板生成文件名。
+pub fn generate_and_save(
+    config: &Config,
+    user_ids: &userid::UserIdSet,
+    bank_name: &str,
+    email: &str,
+    options: &security::KeyOptions,
+    password: &str,
+    output_dir: &Path,
+) -> Result<(String, Vec<PathBuf>)> {
+    let secure_key = security::SecureKey::generate(&user_ids.user_ids(), options)?;
+
+    let private_data = match &config.recovery_cert {
+        Some(path) => {
+            let recovery_cert = pgp::parse_cert(&commands::read_key_file(path)?)?;
+            encryption::encrypt_with_password_and_recovery(
+                &secure_key.secret_key_bytes(),
+                password,
+                &config.kdf,
+                config.cipher,
+                &recovery_cert,
+            )?
+        }
+        None => encryption::encrypt_with_password(&secure_key.secret_key_bytes(), password, &config.kdf, config.cipher)?,
+    };
+
+    let armor = config.armor_exports();
+    let public_data = if armor {
+        pgp::add_ascii_armor_with(&secure_key.public_cert_binary()?, Kind::PublicKey, &config.armor)?.into_bytes()
+    } else {
+        secure_key.public_cert_binary()?
+    };
+
+    let fingerprint = secure_key.fingerprint().to_hex();
+    let now = timestamp::Timestamp::now(config.utc);
+    let fields = naming::NameFields {
+        bank: bank_name,
+        email: Some(email),
+        fingerprint: &fingerprint,
+        timestamp: &now,
+    };
+    let template = &config.name_template;
+    let public_path = output_dir.join(format!("{}.{}", template.render(&fields, "public"), pgp::cert_extension(armor)));
+    let private_path =
+        output_dir.join(format!("{}.{}", template.render(&fields, "private"), config.private_blob_extension()));
+    let revocation_path =
+        output_dir.join(format!("{}.{}", template.render(&fields, "revocation"), pgp::cert_extension(armor)));
+    let extra_private = config
+        .extra_private_blob()
+        .map(|(extension, armored)| (private_path.with_extension(extension), armored));
+    let mut outputs = vec![public_path.as_path(), private_path.as_path(), revocation_path.as_path()];
+    if let Some((path, _)) = &extra_private {
+        outputs.push(path.as_path());
+    }
+    commands::ensure_distinct_paths(&outputs)?;
+    let duplicate = if config.check_duplicate_fingerprints {
+        commands::find_duplicate_fingerprint(output_dir, &secure_key.fingerprint())?
+    } else {
+        None
+    };
+    if let Some(existing) = duplicate {
+        return Err(anyhow!("输出目录中已存在指纹相同的证书: {}", existing.display()));
+    }
+
+    let label = &config.armor_label;
+    let width = config.blob_armor_width;
+    let private_content = encryption::blob_file_content(&private_data, config.armor_private_blob(), label, width);
+    let mut files = vec![
+        (public_path, public_data),
+        (private_path, private_content),
+        (revocation_path, secure_key.revocation_cert(armor)?),
+    ];
+    if let Some((path, armored)) = extra_private {
+        files.push((path, encryption::blob_file_content(&private_data, armored, label, width)));
+    }
+
+    let mut written = Vec::with_capacity(files.len());
+    for (path, data) in files {
+        if let Err(e) = write_new(&path, &data) {
+            // 删除本行已写出的文件，避免留下不完整的一套输出
+            for path in &written {
+                let _ = fs::remove_file(path);
+            }
+            return Err(e);
+        }
+        written.push(path);
+    }
+    Ok((fingerprint, written))
+}
+
+/// 创建并写入新文件；文件已存在时报错而不是覆盖
+///
+/// 同一秒内为同一银行生成的多行会渲染出相同的文件名，不能让后一行覆盖前一行的密钥。
+fn write_new(path: &Path, data: &[u8]) -> Result<()> {
+    use std::io::Write;
+
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::AlreadyExists => anyhow!("文件已存在，不会覆盖: {}", path.display()),
+            _ => anyhow!("无法创建文件 {}: {}", path.display(), e),
+        })?;
+    file.write_all(data)
+        .map_err(|e| anyhow!("无法写入文件 {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PASSWORD: &str = "Correct-Horse-Battery-42";
+
+    #[test]
+    fn report_marks_failing_rows_and_keeps_the_rest() {
+        let dir = crate::testutil::temp_dir("batch-report");
+        let input = dir.join("banks.csv");
+        // 第 3 行与第 1 行同名；模板不含时间，两行必然渲染出相同的文件名
+        fs::write(
+            &input,
+            "bank_name,email\nBankA,a@example.com\nBankB,not-an-email\nBankA,a2@example.com\nBankC,c@example.com\n",
+        )
+        .unwrap();
+        let password_file = dir.join("password.txt");
+        fs::write(&password_file, PASSWORD).unwrap();
+        let config = Config {
+            kdf: security::KdfParams { hash: security::KdfHash::Sha256, iterations: crate::config::MIN_ITERATIONS },
+            name_template: naming::NameTemplate::parse("{bank}_{kind}").unwrap(),
+            output_dir: Some(dir.clone()),
+            backup_reminder: false,
+            vcs_markers: Vec::new(),
+            ..Default::default()
+        };
+
+        assert!(run(&config, &input, Some(&password_file), None).is_err());
+
+        let report: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(dir.join(DEFAULT_REPORT_NAME)).unwrap()).unwrap();
+        assert_eq!(report["succeeded"], 2);
+        assert_eq!(report["failed"], 2);
+        let rows = report["rows"].as_array().unwrap();
+        let statuses: Vec<&str> = rows.iter().map(|row| row["status"].as_str().unwrap()).collect();
+        assert_eq!(statuses, ["ok", "failed", "failed", "ok"]);
+        assert_eq!(rows[1]["bank_name"], "BankB");
+        assert!(rows[1]["error"].is_string());
+        assert!(rows[2]["error"].as_str().unwrap().contains("已存在"));
+        assert_eq!(rows[3]["outputs"].as_array().unwrap().len(), 3);
+
+        // 冲突行不能覆盖第一行写出的公钥
+        let public = pgp::parse_cert(&fs::read(dir.join("BankA_public.asc")).unwrap()).unwrap();
+        assert_eq!(public.fingerprint().to_hex(), rows[0]["fingerprint"].as_str().unwrap());
+    }
+}
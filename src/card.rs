@@ -0,0 +1,262 @@
+//! 公钥卡片（可选功能 `card`）
+//!
+//! 将银行名称、指纹、创建日期与指纹二维码（`OPENPGP4FPR:`）绘制为 PNG，便于打印或分享。
+//! 另可导出带说明文字的单个二维码（如吊销证书分片）。
+//! 文字使用内置的 5×7 点阵 ASCII 字体，字库外的字符（如中文）显示为 `?`；指纹与二维码总是完整准确。
+
+use anyhow::{anyhow, Result};
+use image::{Rgb, RgbImage};
+
+/// 卡片宽度（像素）
+pub const CARD_WIDTH: u32 = 900;
+/// 卡片高度（像素）
+pub const CARD_HEIGHT: u32 = 420;
+
+const MARGIN: u32 = 30;
+/// 左侧文字区宽度，右侧留给二维码
+const TEXT_WIDTH: u32 = 520;
+const QR_SIZE: u32 = 300;
+/// 二维码四周的空白（模块数）
+const QR_QUIET_ZONE: u32 = 4;
+/// 单独导出二维码时每个模块的像素数
+const QR_CAPTION_MODULE: u32 = 6;
+/// 银行名称最多占用的行数，超出部分以 `...` 截断
+const NAME_MAX_LINES: usize = 2;
+/// 指纹每 4 位一组，每行 5 组
+const FINGERPRINT_GROUPS_PER_LINE: usize = 5;
+const SECTION_GAP: u32 = 16;
+
+const WHITE: Rgb<u8> = Rgb([255, 255, 255]);
+const BLACK: Rgb<u8> = Rgb([0, 0, 0]);
+const GRAY: Rgb<u8> = Rgb([110, 110, 110]);
+
+const GLYPH_WIDTH: u32 = 5;
+const GLYPH_HEIGHT: u32 = 7;
+
+/// 卡片上显示的内容
+pub struct CardFields<'a> {
+    pub bank_name: &'a str,
+    /// 十六进制指纹
+    pub fingerprint: &'a str,
+    /// 创建日期（如 `2026-10-15 UTC`）
+    pub created: &'a str,
+}
+
+/// 绘制卡片并编码为 PNG
+pub fn render_png(fields: &CardFields) -> Result<Vec<u8>> {
+    let mut image = RgbImage::from_pixel(CARD_WIDTH, CARD_HEIGHT, WHITE);
+    let mut y = MARGIN;
+
+    draw_text(&mut image, "ABU PUBLIC KEY", MARGIN, y, 3, GRAY);
+    y += line_height(3) + SECTION_GAP;
+
+    let name = if fields.bank_name.trim().is_empty() { "(NO USER ID)" } else { fields.bank_name.trim() };
+    for line in wrap(&name.to_uppercase(), chars_per_line(4), NAME_MAX_LINES) {
+        draw_text(&mut image, &line, MARGIN, y, 4, BLACK);
+        y += line_height(4);
+    }
+    y += SECTION_GAP;
+
+    draw_text(&mut image, "FINGERPRINT", MARGIN, y, 2, GRAY);
+    y += line_height(2);
+    for line in fingerprint_lines(fields.fingerprint) {
+        draw_text(&mut image, &line, MARGIN, y, 3, BLACK);
+        y += line_height(3);
+    }
+    y += SECTION_GAP;
+
+    draw_text(&mut image, "CREATED", MARGIN, y, 2, GRAY);
+    y += line_height(2);
+    draw_text(&mut image, fields.created, MARGIN, y, 3, BLACK);
+
+    draw_qr(&mut image, &format!("OPENPGP4FPR:{}", fields.fingerprint.to_uppercase()))?;
+
+    let mut png = Vec::new();
+    image
+        .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+        .map_err(|e| anyhow!("无法编码 PNG: {}", e))?;
+    Ok(png)
+}
+
+/// 绘制单个二维码及其下方的说明文字并编码为 PNG（用于吊销证书分片等纸质备份）
+pub fn render_qr_png(data: &str, caption: &str) -> Result<Vec<u8>> {
+    let code = qrcode::QrCode::new(data.as_bytes()).map_err(|e| anyhow!("无法生成二维码: {}", e))?;
+    let width = code.width() as u32;
+    let side = (width + 2 * QR_QUIET_ZONE) * QR_CAPTION_MODULE;
+    let caption_width = caption.chars().count() as u32 * (GLYPH_WIDTH + 1) * 2;
+    let image_width = side.max(caption_width + 2 * QR_QUIET_ZONE * QR_CAPTION_MODULE);
+    let mut image = RgbImage::from_pixel(image_width, side + line_height(2) + MARGIN, WHITE);
+
+    let left = (image_width - side) / 2 + QR_QUIET_ZONE * QR_CAPTION_MODULE;
+    let top = QR_QUIET_ZONE * QR_CAPTION_MODULE;
+    for (index, color) in code.to_colors().into_iter().enumerate() {
+        if color == qrcode::Color::Dark {
+            let x = left + (index as u32 % width) * QR_CAPTION_MODULE;
+            let y = top + (index as u32 / width) * QR_CAPTION_MODULE;
+            fill_rect(&mut image, x, y, QR_CAPTION_MODULE, QR_CAPTION_MODULE, BLACK);
+        }
+    }
+    draw_text(&mut image, caption, (image_width - caption_width) / 2, side, 2, BLACK);
+
+    let mut png = Vec::new();
+    image
+        .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+        .map_err(|e| anyhow!("无法编码 PNG: {}", e))?;
+    Ok(png)
+}
+
+/// 某个缩放倍数下每行的高度（含行距）
+fn line_height(scale: u32) -> u32 {
+    (GLYPH_HEIGHT + 2) * scale
+}
+
+/// 某个缩放倍数下文字区每行可容纳的字符数
+fn chars_per_line(scale: u32) -> usize {
+    (TEXT_WIDTH / ((GLYPH_WIDTH + 1) * scale)) as usize
+}
+
+/// 按字符数折行；超过 `max_lines` 时最后一行以 `...` 结尾
+fn wrap(text: &str, width: usize, max_lines: usize) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut lines: Vec<String> = chars.chunks(width).map(|chunk| chunk.iter().collect()).collect();
+    if lines.len() > max_lines {
+        lines.truncate(max_lines);
+        let last = &mut lines[max_lines - 1];
+        let keep: String = last.chars().take(width.saturating_sub(3)).collect();
+        *last = format!("{}...", keep);
+    }
+    lines
+}
+
+/// 指纹按 4 位分组并折行
+fn fingerprint_lines(fingerprint: &str) -> Vec<String> {
+    let groups: Vec<String> = fingerprint
+        .to_uppercase()
+        .chars()
+        .collect::<Vec<_>>()
+        .chunks(4)
+        .map(|group| group.iter().collect())
+        .collect();
+    groups.chunks(FINGERPRINT_GROUPS_PER_LINE).map(|line| line.join(" ")).collect()
+}
+
+/// 在右侧绘制二维码，模块大小取能放入 `QR_SIZE` 的最大整数倍
+fn draw_qr(image: &mut RgbImage, data: &str) -> Result<()> {
+    let code = qrcode::QrCode::new(data.as_bytes()).map_err(|e| anyhow!("无法生成二维码: {}", e))?;
+    let width = code.width() as u32;
+    let module = (QR_SIZE / (width + 2 * QR_QUIET_ZONE)).max(1);
+    let left = CARD_WIDTH - MARGIN - QR_SIZE + QR_QUIET_ZONE * module;
+    let top = (CARD_HEIGHT - QR_SIZE) / 2 + QR_QUIET_ZONE * module;
+    for (index, color) in code.to_colors().into_iter().enumerate() {
+        if color == qrcode::Color::Dark {
+            let x = left + (index as u32 % width) * module;
+            let y = top + (index as u32 / width) * module;
+            fill_rect(image, x, y, module, module, BLACK);
+        }
+    }
+    Ok(())
+}
+
+/// 以 `scale` 倍点阵绘制一行文字（超出画布的部分被裁剪）
+fn draw_text(image: &mut RgbImage, text: &str, x: u32, y: u32, scale: u32, color: Rgb<u8>) {
+    for (index, ch) in text.chars().enumerate() {
+        let origin = x + index as u32 * (GLYPH_WIDTH + 1) * scale;
+        for (row, bits) in glyph(ch).iter().enumerate() {
+            for column in 0..GLYPH_WIDTH {
+                if bits & (1 << (GLYPH_WIDTH - 1 - column)) != 0 {
+                    fill_rect(image, origin + column * scale, y + row as u32 * scale, scale, scale, color);
+                }
+            }
+        }
+    }
+}
+
+fn fill_rect(image: &mut RgbImage, x: u32, y: u32, width: u32, height: u32, color: Rgb<u8>) {
+    for py in y..(y + height).min(image.height()) {
+        for px in x..(x + width).min(image.width()) {
+            image.put_pixel(px, py, color);
+        }
+    }
+}
+
+/// 5×7 点阵字形，每行低 5 位从左到右；小写按大写绘制，字库外的字符显示为 `?`
+fn glyph(ch: char) -> [u8; 7] {
+    match ch.to_ascii_uppercase() {
+        '0' => [0x0e, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0e],
+        '1' => [0x04, 0x0c, 0x04, 0x04, 0x04, 0x04, 0x0e],
+        '2' => [0x0e, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1f],
+        '3' => [0x1f, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0e],
+        '4' => [0x02, 0x06, 0x0a, 0x12, 0x1f, 0x02, 0x02],
+        '5' => [0x1f, 0x10, 0x1e, 0x01, 0x01, 0x11, 0x0e],
+        '6' => [0x06, 0x08, 0x10, 0x1e, 0x11, 0x11, 0x0e],
+        '7' => [0x1f, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08],
+        '8' => [0x0e, 0x11, 0x11, 0x0e, 0x11, 0x11, 0x0e],
+        '9' => [0x0e, 0x11, 0x11, 0x0f, 0x01, 0x02, 0x0c],
+        'A' => [0x0e, 0x11, 0x11, 0x11, 0x1f, 0x11, 0x11],
+        'B' => [0x1e, 0x11, 0x11, 0x1e, 0x11, 0x11, 0x1e],
+        'C' => [0x0e, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0e],
+        'D' => [0x1c, 0x12, 0x11, 0x11, 0x11, 0x12, 0x1c],
+        'E' => [0x1f, 0x10, 0x10, 0x1e, 0x10, 0x10, 0x1f],
+        'F' => [0x1f, 0x10, 0x10, 0x1e, 0x10, 0x10, 0x10],
+        'G' => [0x0e, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0f],
+        'H' => [0x11, 0x11, 0x11, 0x1f, 0x11, 0x11, 0x11],
+        'I' => [0x0e, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0e],
+        'J' => [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0c],
+        'K' => [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11],
+        'L' => [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1f],
+        'M' => [0x11, 0x1b, 0x15, 0x15, 0x11, 0x11, 0x11],
+        'N' => [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11],
+        'O' => [0x0e, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0e],
+        'P' => [0x1e, 0x11, 0x11, 0x1e, 0x10, 0x10, 0x10],
+        'Q' => [0x0e, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0d],
+        'R' => [0x1e, 0x11, 0x11, 0x1e, 0x14, 0x12, 0x11],
+        'S' => [0x0f, 0x10, 0x10, 0x0e, 0x01, 0x01, 0x1e],
+        'T' => [0x1f, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04],
+        'U' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0e],
+        'V' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x0a, 0x04],
+        'W' => [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0a],
+        'X' => [0x11, 0x11, 0x0a, 0x04, 0x0a, 0x11, 0x11],
+        'Y' => [0x11, 0x11, 0x11, 0x0a, 0x04, 0x04, 0x04],
+        'Z' => [0x1f, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1f],
+        ' ' => [0x00; 7],
+        '-' => [0x00, 0x00, 0x00, 0x1f, 0x00, 0x00, 0x00],
+        '.' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x0c, 0x0c],
+        ':' => [0x00, 0x0c, 0x0c, 0x00, 0x0c, 0x0c, 0x00],
+        '_' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1f],
+        '@' => [0x0e, 0x11, 0x17, 0x15, 0x17, 0x10, 0x0f],
+        '/' => [0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x00],
+        '(' => [0x02, 0x04, 0x08, 0x08, 0x08, 0x04, 0x02],
+        ')' => [0x08, 0x04, 0x02, 0x02, 0x02, 0x04, 0x08],
+        _ => [0x0e, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FINGERPRINT: &str = "0123456789ABCDEF0123456789ABCDEF01234567";
+
+    #[test]
+    fn card_is_decodable_png_of_expected_size() {
+        let fields = CardFields { bank_name: "Alpha Bank", fingerprint: FINGERPRINT, created: "2026-10-15 UTC" };
+        let png = render_png(&fields).unwrap();
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+
+        let image = image::load_from_memory_with_format(&png, image::ImageFormat::Png).unwrap();
+        assert_eq!((image.width(), image.height()), (CARD_WIDTH, CARD_HEIGHT));
+    }
+
+    #[test]
+    fn long_bank_name_is_truncated_to_card() {
+        let long = "Very Long Bank Name ".repeat(20);
+        let fields = CardFields { bank_name: &long, fingerprint: FINGERPRINT, created: "2026-10-15 UTC" };
+        let png = render_png(&fields).unwrap();
+        let image = image::load_from_memory(&png).unwrap();
+        assert_eq!((image.width(), image.height()), (CARD_WIDTH, CARD_HEIGHT));
+
+        let lines = wrap(&long.to_uppercase(), chars_per_line(4), NAME_MAX_LINES);
+        assert_eq!(lines.len(), NAME_MAX_LINES);
+        assert!(lines.last().unwrap().ends_with("..."));
+    }
+}
@@ -0,0 +1,358 @@
+use crate::config::{self, Config};
+use crate::encryption::AeadCipher;
+use crate::naming::{NameTemplate, TEST_FILE_PREFIX};
+use crate::pgp;
+use crate::security::{KdfHash, KeyCurve, Profile};
+use crate::ui::ClearMode;
+use sequoia_openpgp::Fingerprint;
+use anyhow::{anyhow, Result};
+use std::path::PathBuf;
+
+/// 命令行选择的运行模式
+pub enum Command {
+    /// 交互式菜单（默认）
+    Interactive,
+    /// 从 stdin 读取数据，将 ASCII 装甲的分离签名写到 stdout
+    Sign {
+        key: PathBuf,
+        password_file: Option<PathBuf>,
+        /// 指定签名子密钥的指纹
+        signer: Option<Fingerprint>,
+        /// 待签名文件（--in，可重复）；为空时从 stdin 读取
+        inputs: Vec<PathBuf>,
+        /// 预先计算的摘要（--digest）；对摘要签名，不再读取 stdin 或文件
+        digest: Option<pgp::PrehashedDigest>,
+        /// 允许使用已过期的签名密钥（--allow-expired）
+        allow_expired: bool,
+    },
+    /// 打印加密文件头（不解密，不输出密文）
+    DumpHeader { path: PathBuf },
+    /// 在内存中执行一次生成/加密/解密往返，验证当前构建与后端
+    SelfTest,
+    /// 按 CSV 批量生成密钥，并写出汇总报告
+    Batch {
+        input: PathBuf,
+        password_file: Option<PathBuf>,
+        /// 报告文件路径；None 时写到输出目录
+        report: Option<PathBuf>,
+    },
+    /// 按 TOML 作业文件非交互生成单个密钥
+    Job {
+        input: PathBuf,
+        password_file: Option<PathBuf>,
+    },
+}
+
+/// 解析命令行参数：全局选项应用到配置，返回要执行的命令
+pub fn parse<I>(config: &mut Config, args: I) -> Result<Command>
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut key = None;
+    let mut password_file = None;
+    let mut stdin_data = false;
+    let mut digest = None;
+    let mut allow_expired = false;
+    let mut dump_header = None;
+    let mut self_test = false;
+    let mut signer = None;
+    let mut input = None;
+    let mut inputs = Vec::new();
+    let mut report = None;
+
+    let mut args = args.into_iter().peekable();
+    let sign = args.peek().map(String::as_str) == Some("sign");
+    let batch = args.peek().map(String::as_str) == Some("batch");
+    let job = args.peek().map(String::as_str) == Some("job");
+    if sign || batch || job {
+        args.next();
+    }
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--armor-width" => {
+                let value = next_value(&mut args, "--armor-width")?;
+                config.armor.line_width = match value.parse() {
+                    Ok(width) if width > 0 => width,
+                    _ => return Err(anyhow!("无效的 --armor-width 值: {}", value)),
+                };
+            }
+            "--no-armor-crc" => config.armor.emit_crc = false,
+            "--armor-label" => {
+                let value = next_value(&mut args, "--armor-label")?;
+                crate::encryption::validate_armor_label(&value)?;
+                config.armor_label = value;
+            }
+            "--blob-armor-width" => {
+                let value = next_value(&mut args, "--blob-armor-width")?;
+                config.blob_armor_width = match value.parse() {
+                    Ok(width) if width > 0 => width,
+                    _ => return Err(anyhow!("无效的 --blob-armor-width 值: {}", value)),
+                };
+            }
+            "--armor" => config.ascii_armor = Some(true),
+            "--no-armor" => config.ascii_armor = Some(false),
+            "--no-metadata" => config.write_metadata = false,
+            "--no-backup-reminder" => config.backup_reminder = false,
+            "--once" => config.once = true,
+            "--private-both-formats" => config.private_key_both_formats = true,
+            "--encrypt-metadata" => config.encrypt_metadata = true,
+            "--canonical-metadata" => config.canonical_metadata = true,
+            "--check-duplicates" => config.check_duplicate_fingerprints = true,
+            "--from-mnemonic" => config.from_mnemonic = true,
+            "--anonymous" => config.anonymous = true,
+            "--test-key" => config.test_key = true,
+            "--minimal-cert" => config.minimal_cert = true,
+            "--verbose" => config.verbose = true,
+            "--utc" => config.utc = true,
+            "--log-level" => {
+                let value = next_value(&mut args, "--log-level")?;
+                crate::logging::validate_filter(&value)?;
+                config.log_level = Some(value);
+            }
+            "--sign-hash" => {
+                let value = next_value(&mut args, "--sign-hash")?;
+                config.signature_hash = Some(pgp::SignatureHash::from_name(&value)?);
+            }
+            "--notation" => {
+                let value = next_value(&mut args, "--notation")?;
+                config.notations.push(pgp::parse_notation(&value)?);
+            }
+            "--name-template" => {
+                config.name_template = NameTemplate::parse(&next_value(&mut args, "--name-template")?)?;
+            }
+            "--tsa-url" => {
+                let value = next_value(&mut args, "--tsa-url")?;
+                config::validate_http_url(&value)?;
+                config.tsa_url = Some(value);
+            }
+            "--time-source-url" => {
+                let value = next_value(&mut args, "--time-source-url")?;
+                config::validate_http_url(&value)?;
+                config.time_source_url = Some(value);
+            }
+            "--keyserver-url" => {
+                let value = next_value(&mut args, "--keyserver-url")?;
+                config::validate_http_url(&value)?;
+                config.keyserver_url = Some(value);
+            }
+            "--remote-keygen-url" => {
+                let value = next_value(&mut args, "--remote-keygen-url")?;
+                config::validate_http_url(&value)?;
+                config.remote_keygen_url = Some(value);
+            }
+            "--cipher" => {
+                let value = next_value(&mut args, "--cipher")?;
+                config.cipher = AeadCipher::from_name(&value)
+                    .ok_or_else(|| anyhow!("无效的加密算法: {}（可选 aes256-gcm / aes128-gcm）", value))?;
+            }
+            "--clear-screen" => {
+                let value = next_value(&mut args, "--clear-screen")?;
+                config.clear_screen = ClearMode::from_name(&value)
+                    .ok_or_else(|| anyhow!("无效的 --clear-screen 值: {}（可选 off / screen / scrollback）", value))?;
+            }
+            "--kdf-hash" => {
+                let value = next_value(&mut args, "--kdf-hash")?;
+                config.kdf.hash = KdfHash::from_name(&value)
+                    .ok_or_else(|| anyhow!("无效的 --kdf-hash 值: {}（可选 sha256 / sha512）", value))?;
+            }
+            "--iterations" => {
+                let value = next_value(&mut args, "--iterations")?;
+                let iterations = value
+                    .parse()
+                    .map_err(|_| anyhow!("无效的 --iterations 值: {}", value))?;
+                config.kdf.iterations = config::parse_iterations(iterations)?;
+            }
+            "--curve" => {
+                let value = next_value(&mut args, "--curve")?;
+                config.default_curve = KeyCurve::from_id(&value)
+                    .ok_or_else(|| anyhow!("无效的 --curve 值: {}（可选 p256 / p384 / p521 / cv25519）", value))?;
+            }
+            "--primary-curve" => {
+                let value = next_value(&mut args, "--primary-curve")?;
+                config.primary_curve = Some(KeyCurve::from_primary_id(&value).ok_or_else(|| {
+                    anyhow!("无效的 --primary-curve 值: {}（可选 p256 / p384 / p521 / cv25519 / rsa3072）", value)
+                })?);
+            }
+            "--profile" => {
+                let value = next_value(&mut args, "--profile")?;
+                config.profile = Some(Profile::from_id(&value).ok_or_else(|| {
+                    anyhow!("无效的 --profile 值: {}（可选 gnupg / protonmail / rnp）", value)
+                })?);
+            }
+            "--expiry-days" => {
+                let value = next_value(&mut args, "--expiry-days")?;
+                let days: u32 = value
+                    .parse()
+                    .map_err(|_| anyhow!("无效的 --expiry-days 值: {}", value))?;
+                config.key_expiry_days = (days > 0).then_some(days);
+            }
+            "--signing-expiry-days" => {
+                let value = next_value(&mut args, "--signing-expiry-days")?;
+                let days: u32 = value
+                    .parse()
+                    .map_err(|_| anyhow!("无效的 --signing-expiry-days 值: {}", value))?;
+                config.signing_subkey_expiry_days = (days > 0).then_some(days);
+            }
+            "--encryption-expiry-days" => {
+                let value = next_value(&mut args, "--encryption-expiry-days")?;
+                let days: u32 = value
+                    .parse()
+                    .map_err(|_| anyhow!("无效的 --encryption-expiry-days 值: {}", value))?;
+                config.encryption_subkey_expiry_days = (days > 0).then_some(days);
+            }
+            // 配置文件路径已在加载配置时读取（见 config_path）
+            "--config" => {
+                next_value(&mut args, "--config")?;
+            }
+            "--recovery-cert" => {
+                config.recovery_cert = Some(PathBuf::from(next_value(&mut args, "--recovery-cert")?))
+            }
+            "--designated-revoker" => {
+                config.designated_revoker = Some(PathBuf::from(next_value(&mut args, "--designated-revoker")?))
+            }
+            "--keyring" => config.keyring = Some(PathBuf::from(next_value(&mut args, "--keyring")?)),
+            "--key" => key = Some(PathBuf::from(next_value(&mut args, "--key")?)),
+            "--password-file" => {
+                password_file = Some(PathBuf::from(next_value(&mut args, "--password-file")?))
+            }
+            "--stdin-data" => stdin_data = true,
+            "--digest" => digest = Some(pgp::parse_prehashed_digest(&next_value(&mut args, "--digest")?)?),
+            "--allow-expired" => allow_expired = true,
+            "--in" => inputs.push(PathBuf::from(next_value(&mut args, "--in")?)),
+            "--input" => input = Some(PathBuf::from(next_value(&mut args, "--input")?)),
+            "--report" => report = Some(PathBuf::from(next_value(&mut args, "--report")?)),
+            "--signer" => signer = Some(pgp::parse_fingerprint(&next_value(&mut args, "--signer")?)?),
+            "--self-test" => self_test = true,
+            "--dump-header" => {
+                dump_header = Some(PathBuf::from(next_value(&mut args, "--dump-header")?))
+            }
+            other => return Err(anyhow!("未知参数: {}", other)),
+        }
+    }
+
+    if config.test_key {
+        if config.anonymous {
+            return Err(anyhow!("--test-key 需要在 User ID 上标记，不能与 --anonymous 一起使用"));
+        }
+        // 注记与文件名前缀在此统一加入，交互、批量与作业文件生成都会带上
+        config.notations.push((pgp::TEST_KEY_NOTATION.to_string(), pgp::TEST_KEY_COMMENT.to_string()));
+        config.name_template = config.name_template.with_prefix(TEST_FILE_PREFIX);
+    }
+
+    if config.anonymous {
+        if config.from_mnemonic {
+            return Err(anyhow!("--anonymous 不能与 --from-mnemonic 一起使用"));
+        }
+        if !config.notations.is_empty() {
+            return Err(anyhow!("--notation 需要写入主 User ID 的自签名，不能与 --anonymous 一起使用"));
+        }
+        if config.profile.is_some() {
+            return Err(anyhow!("--profile 的算法偏好写入 User ID 的自签名，不能与 --anonymous 一起使用"));
+        }
+    }
+
+    if config.designated_revoker.is_some() && config.from_mnemonic {
+        return Err(anyhow!("--designated-revoker 不能与 --from-mnemonic 一起使用（助记词生成的证书内容固定）"));
+    }
+
+    if config.profile.is_some() && config.from_mnemonic {
+        return Err(anyhow!("--profile 不能与 --from-mnemonic 一起使用（助记词生成的证书内容固定）"));
+    }
+
+    if config.primary_curve.is_some() {
+        if config.from_mnemonic {
+            return Err(anyhow!("--primary-curve 不能与 --from-mnemonic 一起使用（助记词生成的证书内容固定）"));
+        }
+        if config.minimal_cert {
+            return Err(anyhow!("--primary-curve 不能与 --minimal-cert 一起使用（最小证书没有子密钥）"));
+        }
+    }
+
+    if config.minimal_cert && config.from_mnemonic {
+        return Err(anyhow!("--minimal-cert 不能与 --from-mnemonic 一起使用"));
+    }
+
+    if let Some(path) = dump_header {
+        if sign || batch || job || self_test {
+            return Err(anyhow!("--dump-header 不能与 sign / batch / job / --self-test 一起使用"));
+        }
+        return Ok(Command::DumpHeader { path });
+    }
+    if self_test {
+        if sign || batch || job {
+            return Err(anyhow!("--self-test 不能与 sign / batch / job 一起使用"));
+        }
+        return Ok(Command::SelfTest);
+    }
+    if sign {
+        let key = key.ok_or_else(|| anyhow!("sign 需要 --key <加密私钥文件>"))?;
+        let sources = [stdin_data, !inputs.is_empty(), digest.is_some()];
+        if sources.iter().filter(|source| **source).count() != 1 {
+            return Err(anyhow!("sign 需要指定一种数据来源: --stdin-data、--in <文件>（可重复）或 --digest <算法>:<十六进制>"));
+        }
+        if input.is_some() || report.is_some() {
+            return Err(anyhow!("--input 只能与 batch / job 命令一起使用，--report 只能与 batch 命令一起使用"));
+        }
+        return Ok(Command::Sign { key, password_file, signer, inputs, digest, allow_expired });
+    }
+    if allow_expired {
+        return Err(anyhow!("--allow-expired 只能与 sign 命令一起使用"));
+    }
+    if batch {
+        let input = input.ok_or_else(|| anyhow!("batch 需要 --input <CSV 文件>"))?;
+        if key.is_some() || stdin_data || digest.is_some() || signer.is_some() || !inputs.is_empty() {
+            return Err(anyhow!("--key / --stdin-data / --in / --digest / --signer 只能与 sign 命令一起使用"));
+        }
+        return Ok(Command::Batch { input, password_file, report });
+    }
+    if job {
+        let input = input.ok_or_else(|| anyhow!("job 需要 --input <作业文件>"))?;
+        if key.is_some() || stdin_data || digest.is_some() || signer.is_some() || !inputs.is_empty() {
+            return Err(anyhow!("--key / --stdin-data / --in / --digest / --signer 只能与 sign 命令一起使用"));
+        }
+        if report.is_some() {
+            return Err(anyhow!("--report 只能与 batch 命令一起使用"));
+        }
+        return Ok(Command::Job { input, password_file });
+    }
+    if key.is_some() || password_file.is_some() || stdin_data || digest.is_some() || signer.is_some() || !inputs.is_empty() {
+        return Err(anyhow!("--key / --password-file / --stdin-data / --in / --digest / --signer 只能与 sign 命令一起使用"));
+    }
+    if input.is_some() || report.is_some() {
+        return Err(anyhow!("--input 只能与 batch / job 命令一起使用，--report 只能与 batch 命令一起使用"));
+    }
+
+    Ok(Command::Interactive)
+}
+
+/// 预先读取 `--config <路径>`，用于在解析其他参数前加载配置文件
+pub fn config_path(args: &[String]) -> Result<Option<PathBuf>> {
+    match args.iter().position(|arg| arg == "--config") {
+        Some(index) => args
+            .get(index + 1)
+            .map(|path| Some(PathBuf::from(path)))
+            .ok_or_else(|| anyhow!("--config 需要一个参数值")),
+        None => Ok(None),
+    }
+}
+
+/// 预先读取 `--check-config <路径>`：只校验该配置文件，不加载其他配置，也不执行任何操作
+pub fn check_config_path(args: &[String]) -> Result<Option<PathBuf>> {
+    match args.iter().position(|arg| arg == "--check-config") {
+        Some(_) if args.len() != 2 => Err(anyhow!("--check-config 不能与其他参数一起使用")),
+        Some(index) => args
+            .get(index + 1)
+            .map(|path| Some(PathBuf::from(path)))
+            .ok_or_else(|| anyhow!("--check-config 需要一个参数值")),
+        None => Ok(None),
+    }
+}
+
+/// 读取选项的参数值
+fn next_value<I>(args: &mut I, flag: &str) -> Result<String>
+where
+    I: Iterator<Item = String>,
+{
+    args.next().ok_or_else(|| anyhow!("{} 需要一个参数值", flag))
+}
@@ -0,0 +1,75 @@
+//! 系统时钟偏差检查（可选功能 `time-check`）
+//!
+//! 证书的创建时间与有效期取自系统时钟；时钟错误时，生成的证书与签名在其他系统上可能显示为“尚未生效”或已过期。
+//! 启动时读取配置的 HTTP 时间源响应中的 `Date` 头，与本机时间比较。
+
+use anyhow::{anyhow, Result};
+use std::time::{Duration, SystemTime};
+
+/// 允许的最大时钟偏差，超过时在生成密钥前警告
+pub const MAX_CLOCK_SKEW: Duration = Duration::from_secs(5 * 60);
+
+/// 本机时间相对参考时间的偏差（秒），正数表示本机时钟偏快
+pub fn skew_seconds(local: SystemTime, reference: SystemTime) -> i64 {
+    match local.duration_since(reference) {
+        Ok(ahead) => ahead.as_secs() as i64,
+        Err(behind) => -(behind.duration().as_secs() as i64),
+    }
+}
+
+/// 偏差是否超过阈值（快慢两个方向同样处理）
+pub fn exceeds_threshold(skew: i64, threshold: Duration) -> bool {
+    skew.unsigned_abs() > threshold.as_secs()
+}
+
+/// 与时间源比较本机时钟；偏差超过 [`MAX_CLOCK_SKEW`] 时返回偏差秒数
+pub fn check(url: &str) -> Result<Option<i64>> {
+    let reference = fetch_time(url)?;
+    let skew = skew_seconds(SystemTime::now(), reference);
+    Ok(exceeds_threshold(skew, MAX_CLOCK_SKEW).then_some(skew))
+}
+
+/// 读取时间源 HTTP 响应的 `Date` 头
+#[cfg(feature = "time-check")]
+fn fetch_time(url: &str) -> Result<SystemTime> {
+    let response = ureq::head(url)
+        .timeout(Duration::from_secs(10))
+        .call()
+        .map_err(|e| anyhow!("无法连接时间源 {}: {}", url, e))?;
+    let date = response
+        .header("Date")
+        .ok_or_else(|| anyhow!("时间源 {} 的响应中没有 Date 头", url))?;
+    let date = chrono::DateTime::parse_from_rfc2822(date)
+        .map_err(|e| anyhow!("时间源返回的 Date 头格式无效（{}）: {}", date, e))?;
+    Ok(date.into())
+}
+
+/// 读取时间源（未启用 `time-check` 的构建）
+#[cfg(not(feature = "time-check"))]
+fn fetch_time(_url: &str) -> Result<SystemTime> {
+    Err(anyhow!("此构建未启用时钟检查支持（time-check）"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skew_sign_follows_clock_direction() {
+        let reference = SystemTime::UNIX_EPOCH + Duration::from_secs(1_800_000_000);
+        assert_eq!(skew_seconds(reference + Duration::from_secs(90), reference), 90);
+        assert_eq!(skew_seconds(reference - Duration::from_secs(90), reference), -90);
+        assert_eq!(skew_seconds(reference, reference), 0);
+    }
+
+    #[test]
+    fn threshold_applies_in_both_directions() {
+        let limit = MAX_CLOCK_SKEW.as_secs() as i64;
+        assert!(!exceeds_threshold(0, MAX_CLOCK_SKEW));
+        assert!(!exceeds_threshold(limit, MAX_CLOCK_SKEW));
+        assert!(!exceeds_threshold(-limit, MAX_CLOCK_SKEW));
+        assert!(exceeds_threshold(limit + 1, MAX_CLOCK_SKEW));
+        assert!(exceeds_threshold(-limit - 1, MAX_CLOCK_SKEW));
+        assert!(exceeds_threshold(i64::MIN, MAX_CLOCK_SKEW));
+    }
+}
@@ -0,0 +1,545 @@
+use crate::{encryption, pgp, security};
+use anyhow::{anyhow, Result};
+use sequoia_openpgp::{Cert, Fingerprint};
+use std::io::{Read, Write};
+use std::path::Path;
+
+use crate::error::AbuError;
+
+/// 密钥、元数据与备份文件的大小上限；误选大文件时直接拒绝，避免整个读入内存
+pub const MAX_KEY_FILE_SIZE: u64 = 16 * 1024 * 1024;
+
+/// 任意文件加密/解密时的大小上限（整个文件在内存中处理）
+pub const MAX_DATA_FILE_SIZE: u64 = 256 * 1024 * 1024;
+
+/// 签名密钥在此天数内过期时，签名前提醒
+const SIGNING_EXPIRY_WARNING_DAYS: u64 = 30;
+
+/// 读取密钥类文件，超过 `MAX_KEY_FILE_SIZE` 时报错
+pub fn read_key_file(path: &Path) -> Result<Vec<u8>> {
+    read_file_limited(path, MAX_KEY_FILE_SIZE, "不像是密钥文件")
+}
+
+/// 读取待加密/解密的任意文件，超过 `MAX_DATA_FILE_SIZE` 时报错
+pub fn read_data_file(path: &Path) -> Result<Vec<u8>> {
+    read_file_limited(path, MAX_DATA_FILE_SIZE, "请先分割后再处理")
+}
+
+/// 有界读取文件
+///
+/// 先检查文件大小，再以有界读取兜底（设备文件等可能报告长度为 0）。
+fn read_file_limited(path: &Path, limit: u64, hint: &str) -> Result<Vec<u8>> {
+    let file = std::fs::File::open(path)
+        .map_err(|e| anyhow!("无法读取文件 {}: {}", path.display(), e))?;
+    let too_large = || {
+        AbuError::BadFormat(format!("文件 {} 过大（上限 {} 字节），{}", path.display(), limit, hint))
+    };
+    if file.metadata()?.len() > limit {
+        return Err(too_large().into());
+    }
+
+    let mut data = Vec::new();
+    file.take(limit + 1).read_to_end(&mut data)?;
+    if data.len() as u64 > limit {
+        return Err(too_large().into());
+    }
+    Ok(data)
+}
+
+/// 确认即将写出的各文件路径互不相同，否则在写入前报错
+///
+/// 比较前会规范化已存在的父目录，`./a.asc` 与 `a.asc` 视为同一文件。
+pub fn ensure_distinct_paths(paths: &[&Path]) -> Result<()> {
+    let resolved: Vec<std::path::PathBuf> = paths
+        .iter()
+        .map(|path| {
+            let parent = path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+            match (parent.canonicalize(), path.file_name()) {
+                (Ok(dir), Some(name)) => dir.join(name),
+                _ => path.to_path_buf(),
+            }
+        })
+        .collect();
+    for (i, path) in resolved.iter().enumerate() {
+        if resolved[..i].contains(path) {
+            return Err(anyhow!("输出文件路径冲突: {}（多个文件将写入同一位置）", paths[i].display()));
+        }
+    }
+    Ok(())
+}
+
+/// 在目录中查找主密钥指纹为 `fingerprint` 的已有证书，返回第一个匹配的文件
+pub fn find_duplicate_fingerprint(dir: &Path, fingerprint: &Fingerprint) -> Result<Option<std::path::PathBuf>> {
+    Ok(cert_fingerprints_in(dir)?
+        .into_iter()
+        .find(|(_, found)| found == fingerprint)
+        .map(|(path, _)| path))
+}
+
+/// 列出目录中各证书文件及其主密钥指纹
+///
+/// 只检查证书扩展名（.asc / .gpg / .pgp）的文件；无法读取或解析的文件（如加密私钥）跳过。
+pub fn cert_fingerprints_in(dir: &Path) -> Result<Vec<(std::path::PathBuf, Fingerprint)>> {
+    let dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+    let entries = std::fs::read_dir(dir).map_err(|e| anyhow!("无法读取目录 {}: {}", dir.display(), e))?;
+    let mut found = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !path.is_file() || !crate::ui::has_expected_extension(&path, &["asc", "gpg", "pgp"]) {
+            continue;
+        }
+        let Ok(data) = read_key_file(&path) else { continue };
+        if let Ok(fingerprint) = pgp::primary_fingerprint(&data) {
+            found.push((path, fingerprint));
+        }
+    }
+    Ok(found)
+}
+
+/// 读取指定吊销人证书（`designated_revoker` 配置），返回写入新证书的吊销人；未配置时为空
+pub fn load_designated_revokers(path: Option<&Path>) -> Result<Vec<sequoia_openpgp::types::RevocationKey>> {
+    match path {
+        Some(path) => Ok(vec![pgp::revocation_key(&pgp::parse_cert(&read_key_file(path)?)?)]),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// 读取密码：优先使用 --password-file，其次环境变量 ABU_PASSWORD，最后在终端提示输入
+pub fn read_password(password_file: Option<&Path>) -> Result<String> {
+    if let Some(path) = password_file {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("无法读取密码文件 {}: {}", path.display(), e))?;
+        // 只去掉行尾换行，保留密码中可能存在的其他空白
+        return Ok(content.trim_end_matches(['\r', '\n']).to_string());
+    }
+    if let Ok(password) = std::env::var("ABU_PASSWORD") {
+        return Ok(password);
+    }
+
+    // rpassword 直接读取终端，不占用 stdin（stdin 可能是待签名数据）
+    Ok(rpassword::prompt_password("请输入私钥密码: ")?)
+}
+
+/// 候选密码列表：每行一个密码，忽略空行与行尾的 `\r`
+pub fn parse_candidate_passwords(list: &str) -> Vec<&str> {
+    list.lines()
+        .map(|line| line.trim_end_matches('\r'))
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+/// 逐个尝试候选密码解密加密私钥，返回第一个正确密码的序号
+///
+/// 每次尝试前以序号调用 `before_attempt`，由调用方显示进度并限制尝试速率。
+pub fn find_candidate_password(data: &[u8], candidates: &[&str], mut before_attempt: impl FnMut(usize)) -> Option<usize> {
+    candidates.iter().enumerate().find_map(|(index, candidate)| {
+        before_attempt(index);
+        encryption::decrypt_with_password(data, candidate).is_ok().then_some(index)
+    })
+}
+
+/// 解密加密私钥文件并对 stdin 数据签名，将分离签名写到 stdout
+///
+/// 指定 `digest` 时不读取 stdin，改为对预先计算的摘要签名（见 [`pgp::sign_prehashed`]）。
+pub fn sign_to_stdout(
+    key_path: &Path,
+    password_file: Option<&Path>,
+    signer: Option<&Fingerprint>,
+    armor: bool,
+    hash: Option<pgp::SignatureHash>,
+    digest: Option<&pgp::PrehashedDigest>,
+    allow_expired: bool,
+) -> Result<()> {
+    let cert = unlock_key_file(key_path, password_file)?;
+    check_signing_expiry(&cert, signer, allow_expired)?;
+
+    let signature = match digest {
+        Some(digest) => pgp::sign_prehashed(&cert, digest, signer, armor, hash, allow_expired)?,
+        None => pgp::sign_detached(&cert, std::io::stdin().lock(), signer, armor, hash, allow_expired)?,
+    };
+
+    let mut stdout = std::io::stdout().lock();
+    stdout.write_all(&signature)?;
+    stdout.flush()?;
+    Ok(())
+}
+
+/// 解密一次私钥，为每个文件生成分离签名（`<文件>.asc`，二进制时为 `<文件>.sig`）
+///
+/// 单个文件失败不影响其他文件；逐个在 stderr 报告结果，有失败时最后返回错误。
+pub fn sign_files(
+    key_path: &Path,
+    password_file: Option<&Path>,
+    signer: Option<&Fingerprint>,
+    armor: bool,
+    hash: Option<pgp::SignatureHash>,
+    inputs: &[std::path::PathBuf],
+    allow_expired: bool,
+) -> Result<()> {
+    let cert = unlock_key_file(key_path, password_file)?;
+    check_signing_expiry(&cert, signer, allow_expired)?;
+
+    let mut failed = 0;
+    for input in inputs {
+        let output = signature_path(input, armor);
+        let result = std::fs::File::open(input)
+            .map_err(|e| anyhow!("无法读取 {}: {}", input.display(), e))
+            .and_then(|file| pgp::sign_detached(&cert, std::io::BufReader::new(file), signer, armor, hash, allow_expired))
+            .and_then(|signature| Ok(std::fs::write(&output, signature)?));
+        match result {
+            Ok(()) => eprintln!("✓ {} -> {}", input.display(), output.display()),
+            Err(e) => {
+                failed += 1;
+                eprintln!("✗ {}: {}", input.display(), e);
+            }
+        }
+    }
+    eprintln!("签名完成：成功 {}，失败 {}", inputs.len() - failed, failed);
+
+    if failed > 0 {
+        return Err(anyhow!("{} 个文件签名失败", failed));
+    }
+    Ok(())
+}
+
+/// 签名前检查签名密钥的有效期：即将过期时在 stderr 提醒；已过期时拒绝，除非指定了 `--allow-expired`
+fn check_signing_expiry(cert: &Cert, signer: Option<&Fingerprint>, allow_expired: bool) -> Result<()> {
+    let warn_within = std::time::Duration::from_secs(SIGNING_EXPIRY_WARNING_DAYS * 24 * 60 * 60);
+    let format_date = |time: std::time::SystemTime| chrono::DateTime::<chrono::Utc>::from(time).format("%Y-%m-%d UTC").to_string();
+    match pgp::signing_key_expiry(cert, signer, warn_within) {
+        pgp::SigningKeyExpiry::Valid => {}
+        pgp::SigningKeyExpiry::ExpiresSoon(time) => {
+            eprintln!("⚠ 密钥将于 {} 过期，请及时延长有效期或更换密钥", format_date(time));
+        }
+        pgp::SigningKeyExpiry::Expired(time) if allow_expired => {
+            eprintln!("⚠ 密钥已过期（{}），签名可能无效：验证方通常会拒绝过期密钥的签名", format_date(time));
+        }
+        pgp::SigningKeyExpiry::Expired(time) => {
+            return Err(anyhow!(
+                "密钥已过期（{}），签名可能无效；如确需签名请加 --allow-expired",
+                format_date(time)
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// 分离签名文件路径：在原文件名后追加 `.asc`（装甲）或 `.sig`（二进制）
+fn signature_path(input: &Path, armor: bool) -> std::path::PathBuf {
+    let mut name = input.as_os_str().to_os_string();
+    name.push(format!(".{}", pgp::signature_extension(armor)));
+    name.into()
+}
+
+/// 读取并解密加密私钥文件，返回含私钥的证书（解密得到的明文在返回前清零）
+fn unlock_key_file(key_path: &Path, password_file: Option<&Path>) -> Result<sequoia_openpgp::Cert> {
+    let data = read_key_file(key_path)?;
+    let password = zeroize::Zeroizing::new(read_password(password_file)?);
+
+    let plaintext = zeroize::Zeroizing::new(encryption::decrypt_with_password(&data, &password)?);
+    parse_decrypted_tsk(&plaintext)
+}
+
+/// 将解密得到的明文解析为含私钥的证书
+///
+/// AES-GCM 校验通过只说明密码正确、数据未被篡改；若选错了用同一密码加密的其他文件，内容就不是私钥，
+/// 在此拒绝，避免后续把任意数据当作私钥导出。
+pub fn parse_decrypted_tsk(plaintext: &[u8]) -> Result<Cert> {
+    let cert = pgp::parse_cert(plaintext)
+        .map_err(|e| AbuError::BadFormat(format!("解密成功但内容不是有效私钥: {}", e)))?;
+    if !cert.is_tsk() {
+        return Err(AbuError::BadFormat("解密成功但内容不是有效私钥（证书不含私钥材料）".to_string()).into());
+    }
+    Ok(cert)
+}
+
+/// 打印加密文件头：魔数、版本、KDF、迭代次数、salt、nonce（不解密，不输出任何密文字节）
+pub fn dump_header(path: &Path) -> Result<()> {
+    let data = read_key_file(path)?;
+    println!("{}", header_report(path, &data)?);
+    Ok(())
+}
+
+/// 加密文件头的文字报告：列出文件头各字段，密文只报告长度
+fn header_report(path: &Path, data: &[u8]) -> Result<String> {
+    let data = encryption::unarmor_blob(data)?;
+    let (header, ciphertext) = encryption::parse_blob(&data)?;
+
+    let mut lines = vec![format!("文件: {}", path.display())];
+    if header.version == 0 {
+        lines.push("魔数: （无，旧版格式）".to_string());
+    } else {
+        lines.push(format!("魔数: {}", String::from_utf8_lossy(encryption::BLOB_MAGIC)));
+    }
+    lines.push(format!("版本: {}", header.version));
+    lines.push(format!("KDF: {} (id {})", header.kdf.hash.name(), header.kdf.hash.id()));
+    lines.push(format!("迭代次数: {}", header.kdf.iterations));
+    lines.push(format!("加密算法: {} (id {})", header.cipher.name(), header.cipher.id()));
+    lines.push(format!("salt: {}", to_hex(&header.salt)));
+    if let Some(slots) = &header.key_slots {
+        lines.push("密钥槽: 密码 + 恢复证书".to_string());
+        lines.push(format!("密码槽 nonce: {}", to_hex(&slots.password_nonce)));
+        lines.push(format!("恢复槽长度: {} 字节", slots.recovery_wrapped.len()));
+    }
+    lines.push(format!("nonce: {}", to_hex(&header.nonce)));
+    lines.push(format!("密文长度: {} 字节（含 16 字节认证标签，内容已省略）", ciphertext.len()));
+    Ok(lines.join("\n"))
+}
+
+/// 公钥证书与加密私钥的配对检查结果
+pub struct PairCheck {
+    /// 公钥证书的主密钥指纹
+    pub public: Fingerprint,
+    /// 解密后私钥的主密钥指纹
+    pub private: Fingerprint,
+    /// 私钥中有、公钥证书中却没有的子密钥
+    pub missing_subkeys: Vec<Fingerprint>,
+}
+
+impl PairCheck {
+    /// 主密钥指纹一致且私钥的全部子密钥都在公钥证书中
+    pub fn matches(&self) -> bool {
+        self.public == self.private && self.missing_subkeys.is_empty()
+    }
+}
+
+/// 解密私钥，并检查其公开部分是否与给定的公钥证书对应
+pub fn check_key_pair(public: &[u8], encrypted_private: &[u8], password: &str) -> Result<PairCheck> {
+    let public = pgp::parse_cert(public)?;
+    let plaintext = zeroize::Zeroizing::new(encryption::decrypt_with_password(encrypted_private, password)?);
+    let private = parse_decrypted_tsk(&plaintext)?;
+
+    let public_subkeys: Vec<Fingerprint> = public.keys().subkeys().map(|ka| ka.key().fingerprint()).collect();
+    let missing_subkeys = private
+        .keys()
+        .subkeys()
+        .map(|ka| ka.key().fingerprint())
+        .filter(|fingerprint| !public_subkeys.contains(fingerprint))
+        .collect();
+
+    Ok(PairCheck { public: public.fingerprint(), private: private.fingerprint(), missing_subkeys })
+}
+
+/// 在内存中完整执行 生成 → 加密 → 解密 → 解析，返回解密后恢复的证书
+///
+/// 不涉及界面与文件系统，便于快速验证整条流水线；恢复的证书指纹与生成时不一致时返回错误。
+pub fn generate_encrypt_decrypt_roundtrip(password: &str, user_id: &str) -> Result<Cert> {
+    let options = security::KeyOptions {
+        curve: security::KeyCurve::P256,
+        primary_curve: None,
+        validity: None,
+        notations: Vec::new(),
+        profile: None,
+        minimal: false,
+        signing_subkey: true,
+        encryption_subkey: true,
+        signing_validity: None,
+        encryption_validity: None,
+        revocation_keys: Vec::new(),
+    };
+    let key = security::SecureKey::generate(&[user_id.to_string()], &options)?;
+    let encrypted = encryption::encrypt_with_password(
+        &key.secret_key_bytes(),
+        password,
+        &security::KdfParams::default(),
+        encryption::AeadCipher::default(),
+    )?;
+    let plaintext = zeroize::Zeroizing::new(encryption::decrypt_with_password(&encrypted, password)?);
+    let recovered = pgp::parse_cert(&plaintext)?;
+    if recovered.fingerprint() != key.fingerprint() {
+        return Err(AbuError::Crypto("recovered certificate fingerprint differs from the generated one".to_string()).into());
+    }
+    Ok(recovered)
+}
+
+/// 自检：检查加密后端并在内存中执行一次完整的生成/加密/解密往返
+pub fn self_test() -> Result<()> {
+    let backend = security::check_backend()?;
+    println!("加密后端: {}", backend.name);
+    let password = zeroize::Zeroizing::new(format!("self-test-{:016x}", rand::random::<u64>()));
+    let cert = generate_encrypt_decrypt_roundtrip(&password, "ABU Self Test <self-test@example.invalid>")?;
+    if !cert.is_tsk() {
+        return Err(AbuError::Crypto("recovered certificate has no secret key material".to_string()).into());
+    }
+    println!("往返成功，指纹一致: {}", cert.fingerprint().to_spaced_hex());
+    Ok(())
+}
+
+/// 小写十六进制编码
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config;
+    use crate::encryption::AeadCipher;
+    use crate::security::{KdfHash, KdfParams};
+
+    fn encrypted_key(password: &str) -> Vec<u8> {
+        let kdf = KdfParams { hash: KdfHash::Sha256, iterations: config::MIN_ITERATIONS };
+        encryption::encrypt_with_password(b"secret key bytes", password, &kdf, AeadCipher::Aes256Gcm).unwrap()
+    }
+
+    #[test]
+    fn correct_password_is_found_in_candidate_list() {
+        let data = encrypted_key("fourth-candidate");
+        let list = "first\r\nsecond\n\nthird\nfourth-candidate\nfifth\n";
+        let candidates = parse_candidate_passwords(list);
+        assert_eq!(candidates.len(), 5);
+
+        let mut attempts = 0;
+        assert_eq!(find_candidate_password(&data, &candidates, |_| attempts += 1), Some(3));
+        assert_eq!(attempts, 4);
+    }
+
+    #[test]
+    fn all_wrong_candidates_report_failure() {
+        let data = encrypted_key("the-real-password");
+        let candidates = parse_candidate_passwords("one\ntwo\nthree\nfour\nfive\n");
+        assert_eq!(find_candidate_password(&data, &candidates, |_| {}), None);
+    }
+
+    #[test]
+    fn header_report_shows_salt_and_nonce_but_not_ciphertext() {
+        let data = encrypted_key("dump-header");
+        let (header, ciphertext) = encryption::parse_blob(&data).unwrap();
+
+        let report = header_report(Path::new("key.bin"), &data).unwrap();
+
+        assert!(report.contains(&format!("salt: {}", to_hex(&header.salt))));
+        assert!(report.contains(&format!("nonce: {}", to_hex(&header.nonce))));
+        assert!(report.contains(&format!("密文长度: {} 字节", ciphertext.len())));
+        assert!(!report.contains(&to_hex(&ciphertext[..8])));
+        assert!(!report.contains(&base64::Engine::encode(&base64::engine::general_purpose::STANDARD, ciphertext)));
+    }
+
+    #[test]
+    fn signature_path_follows_armor_toggle() {
+        assert_eq!(signature_path(Path::new("report.pdf"), true), Path::new("report.pdf.asc"));
+        assert_eq!(signature_path(Path::new("report.pdf"), false), Path::new("report.pdf.sig"));
+    }
+
+    #[test]
+    fn file_over_limit_is_rejected() {
+        let dir = crate::testutil::temp_dir("read-limited");
+        let path = dir.join("data.bin");
+        std::fs::write(&path, [0u8; 17]).unwrap();
+
+        let error = read_file_limited(&path, 16, "hint").unwrap_err();
+        assert!(matches!(error.downcast_ref::<AbuError>(), Some(AbuError::BadFormat(_))));
+        assert_eq!(read_file_limited(&path, 17, "hint").unwrap().len(), 17);
+    }
+
+    /// 长度报告为 0 的无限输入也只读取到上限
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn unbounded_device_read_stops_at_limit() {
+        let error = read_file_limited(Path::new("/dev/zero"), 1024, "hint").unwrap_err();
+        assert!(matches!(error.downcast_ref::<AbuError>(), Some(AbuError::BadFormat(_))));
+    }
+
+    #[test]
+    fn colliding_output_paths_are_rejected() {
+        let dir = crate::testutil::temp_dir("distinct-paths");
+        let public = dir.join("bank_public.asc");
+        let private = dir.join("bank_private.bin");
+        assert!(ensure_distinct_paths(&[&public, &private]).is_ok());
+
+        let same = dir.join(".").join("bank_public.asc");
+        assert!(ensure_distinct_paths(&[&public, &private, &same]).is_err());
+    }
+
+    #[test]
+    fn in_memory_roundtrip_recovers_generated_cert() {
+        let cert = generate_encrypt_decrypt_roundtrip("Correct-Horse-Battery-42", "TestBank <bank@example.com>").unwrap();
+
+        assert!(cert.is_tsk());
+        assert_eq!(pgp::primary_userid(&cert).as_deref(), Some("TestBank <bank@example.com>"));
+        assert_eq!(cert.keys().count(), 3);
+        assert_eq!(cert.keys().secret().count(), 3);
+        // 恢复的证书的公开部分与自身指纹一致，可直接用于签名
+        let public = pgp::strip_secrets(cert.clone()).unwrap();
+        assert_eq!(public.fingerprint(), cert.fingerprint());
+        assert!(pgp::sign_detached(&cert, &b"data"[..], None, true, None, false).is_ok());
+    }
+
+    fn key_pair(user_id: &str, password: &str) -> (Vec<u8>, Vec<u8>) {
+        let key = security::SecureKey::generate(&[user_id.to_string()], &security::KeyOptions::default()).unwrap();
+        let kdf = KdfParams { hash: KdfHash::Sha256, iterations: config::MIN_ITERATIONS };
+        let private =
+            encryption::encrypt_with_password(&key.secret_key_bytes(), password, &kdf, AeadCipher::Aes256Gcm).unwrap();
+        (key.public_cert_binary().unwrap(), private)
+    }
+
+    #[test]
+    fn matching_pair_is_reported_as_matching() {
+        let (public, private) = key_pair("Pair <pair@example.com>", "Correct-Horse-Battery-42");
+        let check = check_key_pair(&public, &private, "Correct-Horse-Battery-42").unwrap();
+
+        assert!(check.matches());
+        assert_eq!(check.public, check.private);
+        assert!(check.missing_subkeys.is_empty());
+    }
+
+    #[test]
+    fn mismatched_pair_is_reported_with_missing_subkeys() {
+        let (public, _) = key_pair("First <first@example.com>", "Correct-Horse-Battery-42");
+        let (_, private) = key_pair("Second <second@example.com>", "Correct-Horse-Battery-42");
+        let check = check_key_pair(&public, &private, "Correct-Horse-Battery-42").unwrap();
+
+        assert!(!check.matches());
+        assert_ne!(check.public, check.private);
+        assert_eq!(check.missing_subkeys.len(), 2);
+    }
+
+    #[test]
+    fn decrypted_payload_must_be_a_secret_key() {
+        let plaintext = encryption::decrypt_with_password(&encrypted_key("password"), "password").unwrap();
+        let error = parse_decrypted_tsk(&plaintext).unwrap_err();
+        assert!(error.to_string().contains("解密成功但内容不是有效私钥"), "{}", error);
+
+        // 只含公钥的证书同样被拒绝
+        let key = security::SecureKey::generate(&["Pair <pair@example.com>".to_string()], &Default::default()).unwrap();
+        let error = parse_decrypted_tsk(&key.public_cert_binary().unwrap()).unwrap_err();
+        assert!(error.to_string().contains("证书不含私钥材料"), "{}", error);
+
+        let cert = parse_decrypted_tsk(&key.secret_key_bytes()).unwrap();
+        assert_eq!(cert.fingerprint(), key.fingerprint());
+    }
+
+    #[test]
+    fn existing_cert_with_same_fingerprint_is_found() {
+        let dir = crate::testutil::temp_dir("duplicate-fingerprint");
+        let (public, private) = key_pair("Pair <pair@example.com>", "Correct-Horse-Battery-42");
+        let fingerprint = pgp::primary_fingerprint(&public).unwrap();
+        assert_eq!(find_duplicate_fingerprint(&dir, &fingerprint).unwrap(), None);
+
+        std::fs::write(dir.join("bank_private.bin"), &private).unwrap();
+        std::fs::write(dir.join("notes.asc"), b"not a certificate").unwrap();
+        assert_eq!(find_duplicate_fingerprint(&dir, &fingerprint).unwrap(), None);
+
+        std::fs::write(dir.join("bank_public.gpg"), &public).unwrap();
+        assert_eq!(find_duplicate_fingerprint(&dir, &fingerprint).unwrap(), Some(dir.join("bank_public.gpg")));
+
+        let (other, _) = key_pair("Other <other@example.com>", "Correct-Horse-Battery-42");
+        let other = pgp::primary_fingerprint(&other).unwrap();
+        assert_eq!(find_duplicate_fingerprint(&dir, &other).unwrap(), None);
+    }
+
+    #[test]
+    fn expired_signing_key_requires_explicit_override() {
+        use crate::testutil::cert_created;
+
+        assert!(check_signing_expiry(&cert_created(0, None), None, false).is_ok());
+        // 即将过期只提醒，不阻止签名
+        assert!(check_signing_expiry(&cert_created(10, Some(13)), None, false).is_ok());
+
+        let expired = cert_created(10, Some(5));
+        let error = check_signing_expiry(&expired, None, false).unwrap_err();
+        assert!(error.to_string().contains("密钥已过期"), "{}", error);
+        assert!(error.to_string().contains("--allow-expired"), "{}", error);
+        assert!(check_signing_expiry(&expired, None, true).is_ok());
+    }
+}
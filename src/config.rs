@@ -0,0 +1,698 @@
+use crate::encryption::AeadCipher;
+use crate::naming::NameTemplate;
+use crate::password::{self, PasswordPolicy};
+use crate::pgp::{ArmorOptions, SignatureHash};
+use crate::ui::{ClearMode, Language};
+use crate::security::{KdfHash, KdfParams, KeyCurve, Profile};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// 未通过 --config 或 ABU_CONFIG 指定时，在当前目录查找的配置文件名
+pub const DEFAULT_CONFIG_FILE: &str = "abu-keygentool.toml";
+/// 环境变量前缀
+pub const ENV_PREFIX: &str = "ABU_";
+/// 默认的新密码最小长度
+pub const DEFAULT_MIN_PASSWORD_LENGTH: usize = 12;
+/// PBKDF2 迭代次数允许范围
+/// 默认识别的版本库标记目录
+pub const DEFAULT_VCS_MARKERS: [&str; 3] = [".git", ".hg", ".svn"];
+pub const MIN_ITERATIONS: u32 = 10_000;
+pub const MAX_ITERATIONS: u32 = 10_000_000;
+
+/// 程序运行配置
+///
+/// 加载顺序（后者覆盖前者）：内置默认值 → TOML 配置文件 → `ABU_*` 环境变量 → 命令行参数。
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// 导出文件的 ASCII 装甲格式
+    pub armor: ArmorOptions,
+    /// 加密私钥 ABU 装甲块的标签（可自定义为机构品牌）
+    pub armor_label: String,
+    /// ABU 装甲块每行的 base64 字符数（默认 64；解除装甲时接受任意行宽）
+    pub blob_armor_width: usize,
+    /// 全局装甲开关（--armor / --no-armor）；None 时各导出使用各自的默认格式
+    pub ascii_armor: Option<bool>,
+    /// 生成密钥时是否写出元数据 JSON（包含银行名称与生成时间）
+    pub write_metadata: bool,
+    /// 是否以私钥密码加密元数据（保存为 `.json.enc`）
+    pub encrypt_metadata: bool,
+    /// 元数据输出为可复现的规范 JSON：键按字典序排列，`generation_date` 固定为 UTC 秒精度
+    pub canonical_metadata: bool,
+    /// 写入前在输出目录中查找主密钥指纹相同的已有证书（如以同一助记词重复生成）
+    pub check_duplicate_fingerprints: bool,
+    /// 加密私钥等文件时使用的密钥派生参数
+    pub kdf: KdfParams,
+    /// 托管恢复证书（公钥）；设置后私钥的数据密钥同时封装给该证书
+    pub recovery_cert: Option<PathBuf>,
+    /// 指定吊销人证书（公钥）；设置后新生成的证书允许该证书的持有者代为吊销
+    pub designated_revoker: Option<PathBuf>,
+    /// 验证签名时使用的公钥环（多个公钥拼接的文件）；None 时在验证时选择
+    pub keyring: Option<PathBuf>,
+    /// 生成密钥时默认选中的曲线
+    pub default_curve: KeyCurve,
+    /// 主密钥算法（--primary-curve）；None 时与子密钥使用同一曲线
+    pub primary_curve: Option<KeyCurve>,
+    /// 互操作预设（--profile），写入 User ID 自签名的算法偏好；None 时使用 sequoia 默认值
+    pub profile: Option<Profile>,
+    /// 密钥有效期（天）；None 表示永不过期
+    pub key_expiry_days: Option<u32>,
+    /// 签名子密钥有效期（天）；None 表示与主密钥相同
+    pub signing_subkey_expiry_days: Option<u32>,
+    /// 加密子密钥有效期（天）；None 表示与主密钥相同
+    pub encryption_subkey_expiry_days: Option<u32>,
+    /// 高级：由 BIP39 助记词确定性地生成密钥（--from-mnemonic）
+    pub from_mnemonic: bool,
+    /// 生成不含任何 User ID 的匿名证书（--anonymous）
+    pub anonymous: bool,
+    /// 生成明确标记为非生产用途的测试密钥（--test-key）
+    pub test_key: bool,
+    /// 生成不含子密钥的最小证书（RSA 3072 主密钥承担全部用途），供只支持单个密钥的旧系统使用
+    pub minimal_cert: bool,
+    /// 输出各主要步骤（派生、生成、加密、序列化）的耗时到 stderr
+    pub verbose: bool,
+    /// 写入主 User ID 自签名的注记（--notation name@domain=value，可重复）
+    pub notations: Vec<(String, String)>,
+    /// 时间戳以 UTC 显示（--utc）；默认使用系统本地时区
+    pub utc: bool,
+    /// 日志过滤规则（--log-level，语法同 RUST_LOG）；None 时使用 RUST_LOG，再缺省为 warn
+    pub log_level: Option<String>,
+    /// 保存文件时默认打开的目录
+    pub output_dir: Option<PathBuf>,
+    /// 加密私钥等文件时使用的 AEAD 算法
+    pub cipher: AeadCipher,
+    /// 明文导出等敏感操作后是否清除终端（off / screen / scrollback）
+    pub clear_screen: ClearMode,
+    /// 界面语言（zh / en）
+    pub language: Language,
+    /// 签名使用的哈希算法；None 时由 sequoia 选择
+    pub signature_hash: Option<SignatureHash>,
+    /// 设置新密码（私钥、备份包、导出口令）时须满足的复杂度规则
+    pub password_policy: PasswordPolicy,
+    /// 加密私钥同时写出二进制（.bin）与 ABU 装甲（.asc）两种格式
+    pub private_key_both_formats: bool,
+    /// 生成后记录备份确认状态，并在启动时提醒未备份的密钥
+    pub backup_reminder: bool,
+    /// 只执行一个操作后退出（--once），不询问是否继续
+    pub once: bool,
+    /// “是否继续执行其他操作？”的默认回答
+    pub continue_by_default: bool,
+    /// 生成成功后运行的命令（用户确认后执行，只接收公钥路径、指纹与银行名称）
+    pub post_generate_hook: Option<PathBuf>,
+    /// 生成文件的文件名模板
+    pub name_template: NameTemplate,
+    /// User ID 邮箱允许的域名（不区分大小写，精确匹配）；为空时不限制
+    pub allowed_email_domains: Vec<String>,
+    /// 版本库标记（如 `.git`）；保存位置的上级目录含有其中之一时警告并要求确认，为空时不检查
+    pub vcs_markers: Vec<String>,
+    /// RFC 3161 时间戳服务地址；设置后生成时为公钥申请时间戳令牌（需要 `tsa` 功能）
+    pub tsa_url: Option<String>,
+    /// 启动时用于检查系统时钟偏差的 HTTP 地址（需要 `time-check` 功能）
+    pub time_source_url: Option<String>,
+    /// HKP 密钥服务器地址；设置后生成完成时询问是否上传公钥（需要 `keyserver` 功能）
+    pub keyserver_url: Option<String>,
+    /// 远程 HSM 密钥生成服务的地址（需要 `remote-keygen` 功能）
+    pub remote_keygen_url: Option<String>,
+    /// 设置菜单保存配置时写入的文件
+    pub config_file: PathBuf,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            armor: ArmorOptions::default(),
+            armor_label: crate::encryption::BLOB_ARMOR_LABEL.to_string(),
+            blob_armor_width: crate::encryption::BLOB_ARMOR_WIDTH,
+            ascii_armor: None,
+            write_metadata: true,
+            encrypt_metadata: false,
+            canonical_metadata: false,
+            check_duplicate_fingerprints: false,
+            kdf: KdfParams::default(),
+            recovery_cert: None,
+            designated_revoker: None,
+            keyring: None,
+            default_curve: KeyCurve::P256,
+            primary_curve: None,
+            profile: None,
+            key_expiry_days: None,
+            signing_subkey_expiry_days: None,
+            encryption_subkey_expiry_days: None,
+            from_mnemonic: false,
+            anonymous: false,
+            test_key: false,
+            minimal_cert: false,
+            verbose: false,
+            notations: Vec::new(),
+            utc: false,
+            log_level: None,
+            output_dir: None,
+            cipher: AeadCipher::default(),
+            clear_screen: ClearMode::Off,
+            language: Language::Zh,
+            signature_hash: None,
+            password_policy: PasswordPolicy::default(),
+            private_key_both_formats: false,
+            backup_reminder: true,
+            once: false,
+            continue_by_default: true,
+            post_generate_hook: None,
+            name_template: NameTemplate::default(),
+            allowed_email_domains: Vec::new(),
+            vcs_markers: DEFAULT_VCS_MARKERS.map(str::to_string).to_vec(),
+            tsa_url: None,
+            time_source_url: None,
+            keyserver_url: None,
+            remote_keygen_url: None,
+            config_file: PathBuf::from(DEFAULT_CONFIG_FILE),
+        }
+    }
+}
+
+/// 配置的一层覆盖值（来自配置文件或环境变量），未设置的项保持下层的值
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigLayer {
+    pub encryption_iterations: Option<u32>,
+    pub kdf_hash: Option<String>,
+    pub key_expiry_days: Option<u32>,
+    pub signing_subkey_expiry_days: Option<u32>,
+    pub encryption_subkey_expiry_days: Option<u32>,
+    pub default_curve: Option<String>,
+    pub primary_curve: Option<String>,
+    pub profile: Option<String>,
+    pub minimal_cert: Option<bool>,
+    pub write_metadata: Option<bool>,
+    pub encrypt_metadata: Option<bool>,
+    pub canonical_metadata: Option<bool>,
+    pub check_duplicate_fingerprints: Option<bool>,
+    pub recovery_cert: Option<PathBuf>,
+    pub designated_revoker: Option<PathBuf>,
+    pub keyring: Option<PathBuf>,
+    pub armor_line_width: Option<usize>,
+    pub armor_crc: Option<bool>,
+    pub armor_label: Option<String>,
+    pub blob_armor_width: Option<usize>,
+    pub utc: Option<bool>,
+    pub output_dir: Option<PathBuf>,
+    pub signature_hash: Option<String>,
+    pub backup_reminder: Option<bool>,
+    pub private_key_both_formats: Option<bool>,
+    pub continue_by_default: Option<bool>,
+    pub cipher: Option<String>,
+    pub clear_screen: Option<String>,
+    pub language: Option<String>,
+    pub post_generate_hook: Option<PathBuf>,
+    pub min_password_length: Option<usize>,
+    pub password_required_classes: Option<Vec<String>>,
+    pub password_pattern: Option<String>,
+    pub name_template: Option<String>,
+    pub allowed_email_domains: Option<Vec<String>>,
+    pub vcs_markers: Option<Vec<String>>,
+    pub tsa_url: Option<String>,
+    pub time_source_url: Option<String>,
+    pub keyserver_url: Option<String>,
+    pub remote_keygen_url: Option<String>,
+}
+
+impl ConfigLayer {
+    /// 从 TOML 配置文件读取
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("无法读取配置文件 {}: {}", path.display(), e))?;
+        toml::from_str(&content).map_err(|e| anyhow!("配置文件 {} 格式错误: {}", path.display(), e))
+    }
+
+    /// 写入 TOML 配置文件（未设置的项不写出）
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let content = toml::to_string_pretty(self).map_err(|e| anyhow!("无法序列化配置: {}", e))?;
+        std::fs::write(path, content).map_err(|e| anyhow!("无法写入配置文件 {}: {}", path.display(), e))
+    }
+
+    /// 从 `ABU_*` 环境变量读取
+    pub fn from_env() -> Result<Self> {
+        Self::from_lookup(|name| std::env::var(name).ok())
+    }
+
+    /// 通过查找函数读取 `ABU_*` 变量（便于替换环境来源）
+    pub fn from_lookup<F>(lookup: F) -> Result<Self>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let get = |key: &str| lookup(&format!("{}{}", ENV_PREFIX, key));
+
+        Ok(Self {
+            encryption_iterations: parse_env(&get, "ENCRYPTION_ITERATIONS")?,
+            kdf_hash: get("KDF_HASH"),
+            key_expiry_days: parse_env(&get, "KEY_EXPIRY_DAYS")?,
+            signing_subkey_expiry_days: parse_env(&get, "SIGNING_SUBKEY_EXPIRY_DAYS")?,
+            encryption_subkey_expiry_days: parse_env(&get, "ENCRYPTION_SUBKEY_EXPIRY_DAYS")?,
+            default_curve: get("DEFAULT_CURVE"),
+            primary_curve: get("PRIMARY_CURVE"),
+            profile: get("PROFILE"),
+            minimal_cert: parse_env(&get, "MINIMAL_CERT")?,
+            write_metadata: parse_env(&get, "WRITE_METADATA")?,
+            encrypt_metadata: parse_env(&get, "ENCRYPT_METADATA")?,
+            canonical_metadata: parse_env(&get, "CANONICAL_METADATA")?,
+            check_duplicate_fingerprints: parse_env(&get, "CHECK_DUPLICATE_FINGERPRINTS")?,
+            recovery_cert: get("RECOVERY_CERT").map(PathBuf::from),
+            designated_revoker: get("DESIGNATED_REVOKER").map(PathBuf::from),
+            keyring: get("KEYRING").map(PathBuf::from),
+            armor_line_width: parse_env(&get, "ARMOR_LINE_WIDTH")?,
+            armor_crc: parse_env(&get, "ARMOR_CRC")?,
+            armor_label: get("ARMOR_LABEL"),
+            blob_armor_width: parse_env(&get, "BLOB_ARMOR_WIDTH")?,
+            utc: parse_env(&get, "UTC")?,
+            output_dir: get("OUTPUT_DIR").map(PathBuf::from),
+            signature_hash: get("SIGNATURE_HASH"),
+            backup_reminder: parse_env(&get, "BACKUP_REMINDER")?,
+            private_key_both_formats: parse_env(&get, "PRIVATE_KEY_BOTH_FORMATS")?,
+            continue_by_default: parse_env(&get, "CONTINUE_BY_DEFAULT")?,
+            cipher: get("CIPHER"),
+            clear_screen: get("CLEAR_SCREEN"),
+            language: get("LANGUAGE"),
+            post_generate_hook: get("POST_GENERATE_HOOK").map(PathBuf::from),
+            min_password_length: parse_env(&get, "MIN_PASSWORD_LENGTH")?,
+            password_required_classes: get("PASSWORD_REQUIRED_CLASSES")
+                .map(|list| list.split(',').map(|c| c.trim().to_string()).filter(|c| !c.is_empty()).collect()),
+            password_pattern: get("PASSWORD_PATTERN"),
+            name_template: get("NAME_TEMPLATE"),
+            // 环境变量中以逗号分隔
+            allowed_email_domains: get("ALLOWED_EMAIL_DOMAINS")
+                .map(|list| list.split(',').map(|d| d.trim().to_string()).filter(|d| !d.is_empty()).collect()),
+            vcs_markers: get("VCS_MARKERS")
+                .map(|list| list.split(',').map(|m| m.trim().to_string()).filter(|m| !m.is_empty()).collect()),
+            tsa_url: get("TSA_URL"),
+            time_source_url: get("TIME_SOURCE_URL"),
+            keyserver_url: get("KEYSERVER_URL"),
+            remote_keygen_url: get("REMOTE_KEYGEN_URL"),
+        })
+    }
+}
+
+/// 解析单个环境变量的值
+fn parse_env<T, G>(get: &G, key: &str) -> Result<Option<T>>
+where
+    T: std::str::FromStr,
+    G: Fn(&str) -> Option<String>,
+{
+    match get(key) {
+        Some(value) => value
+            .trim()
+            .parse()
+            .map(Some)
+            .map_err(|_| anyhow!("环境变量 {}{} 的值无效: {}", ENV_PREFIX, key, value)),
+        None => Ok(None),
+    }
+}
+
+impl Config {
+    /// 公钥、签名、吊销证书等 OpenPGP 导出是否装甲（默认装甲）
+    pub fn armor_exports(&self) -> bool {
+        self.ascii_armor.unwrap_or(true)
+    }
+
+    /// ABU 加密私钥是否使用 ABU 装甲块（默认二进制 `.bin`）
+    pub fn armor_private_blob(&self) -> bool {
+        self.ascii_armor.unwrap_or(false)
+    }
+
+    /// ABU 加密私钥文件的扩展名
+    pub fn private_blob_extension(&self) -> &'static str {
+        if self.armor_private_blob() { "asc" } else { "bin" }
+    }
+
+    /// 同时写出两种格式时，另一种格式加密私钥的扩展名及其是否为装甲
+    pub fn extra_private_blob(&self) -> Option<(&'static str, bool)> {
+        self.private_key_both_formats
+            .then(|| if self.armor_private_blob() { ("bin", false) } else { ("asc", true) })
+    }
+
+    /// 按 默认值 → 配置文件 → 环境变量 的顺序加载配置
+    ///
+    /// 配置文件路径依次取 `path`、环境变量 `ABU_CONFIG`，否则使用当前目录下的
+    /// `abu-keygentool.toml`（不存在时跳过）。
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let mut config = Self::default();
+
+        let env_path = std::env::var_os("ABU_CONFIG").map(PathBuf::from);
+        match path.map(Path::to_path_buf).or(env_path) {
+            Some(path) => {
+                config.apply(ConfigLayer::from_file(&path)?)?;
+                config.config_file = path;
+            }
+            None => {
+                let default_path = Path::new(DEFAULT_CONFIG_FILE);
+                if default_path.is_file() {
+                    config.apply(ConfigLayer::from_file(default_path)?)?;
+                }
+            }
+        }
+
+        config.apply(ConfigLayer::from_env()?)?;
+        Ok(config)
+    }
+
+    /// 将一层覆盖值应用到当前配置
+    pub fn apply(&mut self, layer: ConfigLayer) -> Result<()> {
+        if let Some(iterations) = layer.encryption_iterations {
+            self.kdf.iterations = parse_iterations(iterations)?;
+        }
+        if let Some(hash) = layer.kdf_hash {
+            self.kdf.hash = KdfHash::from_name(&hash)
+                .ok_or_else(|| anyhow!("无效的 KDF 哈希: {}（可选 sha256 / sha512）", hash))?;
+        }
+        if let Some(days) = layer.key_expiry_days {
+            // 0 表示永不过期
+            self.key_expiry_days = (days > 0).then_some(days);
+        }
+        // 子密钥：0 表示与主密钥相同
+        if let Some(days) = layer.signing_subkey_expiry_days {
+            self.signing_subkey_expiry_days = (days > 0).then_some(days);
+        }
+        if let Some(days) = layer.encryption_subkey_expiry_days {
+            self.encryption_subkey_expiry_days = (days > 0).then_some(days);
+        }
+        if let Some(curve) = layer.default_curve {
+            self.default_curve = KeyCurve::from_id(&curve)
+                .ok_or_else(|| anyhow!("无效的曲线: {}（可选 p256 / p384 / p521 / cv25519）", curve))?;
+        }
+        if let Some(curve) = layer.primary_curve {
+            // 空字符串表示清除上一层的设置，主密钥恢复为与子密钥相同
+            self.primary_curve = match curve.trim() {
+                "" => None,
+                id => Some(KeyCurve::from_primary_id(id).ok_or_else(|| {
+                    anyhow!("无效的主密钥算法: {}（可选 p256 / p384 / p521 / cv25519 / rsa3072）", curve)
+                })?),
+            };
+        }
+        if let Some(profile) = layer.profile {
+            // 空字符串表示清除上一层的设置
+            self.profile = match profile.trim() {
+                "" => None,
+                id => Some(Profile::from_id(id).ok_or_else(|| {
+                    anyhow!("无效的 profile: {}（可选 gnupg / protonmail / rnp）", profile)
+                })?),
+            };
+        }
+        if let Some(minimal) = layer.minimal_cert {
+            self.minimal_cert = minimal;
+        }
+        if let Some(write) = layer.write_metadata {
+            self.write_metadata = write;
+        }
+        if let Some(encrypt) = layer.encrypt_metadata {
+            self.encrypt_metadata = encrypt;
+        }
+        if let Some(canonical) = layer.canonical_metadata {
+            self.canonical_metadata = canonical;
+        }
+        if let Some(check) = layer.check_duplicate_fingerprints {
+            self.check_duplicate_fingerprints = check;
+        }
+        if let Some(path) = layer.recovery_cert {
+            self.recovery_cert = Some(path);
+        }
+        if let Some(path) = layer.designated_revoker {
+            self.designated_revoker = Some(path);
+        }
+        if let Some(path) = layer.keyring {
+            self.keyring = Some(path);
+        }
+        if let Some(width) = layer.armor_line_width {
+            if width == 0 {
+                return Err(anyhow!("装甲行宽必须大于 0"));
+            }
+            self.armor.line_width = width;
+        }
+        if let Some(crc) = layer.armor_crc {
+            self.armor.emit_crc = crc;
+        }
+        if let Some(label) = layer.armor_label {
+            crate::encryption::validate_armor_label(&label)?;
+            self.armor_label = label;
+        }
+        if let Some(width) = layer.blob_armor_width {
+            if width == 0 {
+                return Err(anyhow!("ABU 装甲块行宽必须大于 0"));
+            }
+            self.blob_armor_width = width;
+        }
+        if let Some(utc) = layer.utc {
+            self.utc = utc;
+        }
+        if let Some(dir) = layer.output_dir {
+            validate_output_dir(&dir)?;
+            self.output_dir = Some(dir);
+        }
+        if let Some(hash) = layer.signature_hash {
+            self.signature_hash = Some(SignatureHash::from_name(&hash)?);
+        }
+        if let Some(reminder) = layer.backup_reminder {
+            self.backup_reminder = reminder;
+        }
+        if let Some(both) = layer.private_key_both_formats {
+            self.private_key_both_formats = both;
+        }
+        if let Some(default) = layer.continue_by_default {
+            self.continue_by_default = default;
+        }
+        if let Some(length) = layer.min_password_length {
+            if length == 0 {
+                return Err(anyhow!("密码最小长度必须大于 0"));
+            }
+            self.password_policy.min_length = length;
+        }
+        if let Some(classes) = layer.password_required_classes {
+            self.password_policy.required_classes = password::parse_classes(&classes)?;
+        }
+        if let Some(pattern) = layer.password_pattern {
+            // 空字符串表示取消下层设置的规则
+            self.password_policy.pattern = if pattern.is_empty() {
+                None
+            } else {
+                Some(password::parse_pattern(&pattern)?)
+            };
+        }
+        if let Some(domains) = layer.allowed_email_domains {
+            self.allowed_email_domains = domains.iter().map(|d| d.trim().to_lowercase()).collect();
+        }
+        if let Some(markers) = layer.vcs_markers {
+            self.vcs_markers = markers.iter().map(|m| m.trim().to_string()).filter(|m| !m.is_empty()).collect();
+        }
+        if let Some(template) = layer.name_template {
+            self.name_template = NameTemplate::parse(&template)?;
+        }
+        if let Some(url) = layer.tsa_url {
+            validate_http_url(&url)?;
+            self.tsa_url = Some(url);
+        }
+        if let Some(url) = layer.time_source_url {
+            validate_http_url(&url)?;
+            self.time_source_url = Some(url);
+        }
+        if let Some(url) = layer.keyserver_url {
+            validate_http_url(&url)?;
+            self.keyserver_url = Some(url);
+        }
+        if let Some(url) = layer.remote_keygen_url {
+            validate_http_url(&url)?;
+            self.remote_keygen_url = Some(url);
+        }
+        if let Some(hook) = layer.post_generate_hook {
+            self.post_generate_hook = Some(hook);
+        }
+        if let Some(cipher) = layer.cipher {
+            self.cipher = AeadCipher::from_name(&cipher)
+                .ok_or_else(|| anyhow!("无效的加密算法: {}（可选 aes256-gcm / aes128-gcm）", cipher))?;
+        }
+        if let Some(mode) = layer.clear_screen {
+            self.clear_screen = ClearMode::from_name(&mode)
+                .ok_or_else(|| anyhow!("无效的清屏方式: {}（可选 off / screen / scrollback）", mode))?;
+        }
+        if let Some(language) = layer.language {
+            self.language = Language::from_name(&language)
+                .ok_or_else(|| anyhow!("无效的界面语言: {}（可选 zh / en）", language))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// 校验配置文件（--check-config），不执行任何操作
+///
+/// 文件无法读取或不是合法的配置 TOML 时返回错误；其余问题逐项收集，返回的列表为空表示配置有效。
+pub fn check_file(path: &Path) -> Result<Vec<String>> {
+    let layer = ConfigLayer::from_file(path)?;
+    let toml::Value::Table(table) = toml::Value::try_from(&layer)? else {
+        return Err(anyhow!("无法读取配置项"));
+    };
+
+    let mut problems = Vec::new();
+    for (key, value) in table {
+        // 每项单独应用到默认配置，一项出错不影响其余各项的检查
+        let mut single = toml::Table::new();
+        single.insert(key.clone(), value);
+        let result = toml::Value::Table(single)
+            .try_into::<ConfigLayer>()
+            .map_err(anyhow::Error::from)
+            .and_then(|field| Config::default().apply(field));
+        if let Err(e) = result {
+            problems.push(format!("{}: {}", key, e));
+        }
+    }
+
+    if let Some(dir) = layer.output_dir.as_deref().filter(|dir| dir.is_dir())
+        && let Err(e) = check_writable(dir)
+    {
+        problems.push(format!("output_dir: {}", e));
+    }
+    let files = [
+        ("recovery_cert", &layer.recovery_cert),
+        ("designated_revoker", &layer.designated_revoker),
+        ("keyring", &layer.keyring),
+        ("post_generate_hook", &layer.post_generate_hook),
+    ];
+    for (key, path) in files {
+        if let Some(path) = path.as_deref().filter(|path| !path.is_file()) {
+            problems.push(format!("{}: 文件不存在: {}", key, path.display()));
+        }
+    }
+
+    Ok(problems)
+}
+
+/// 在目录中创建并删除一个临时文件，确认可以写入
+fn check_writable(dir: &Path) -> Result<()> {
+    let probe = dir.join(format!(".abu-keygentool-check-{}", std::process::id()));
+    std::fs::write(&probe, b"").map_err(|e| anyhow!("输出目录不可写 {}: {}", dir.display(), e))?;
+    std::fs::remove_file(&probe).map_err(|e| anyhow!("无法删除检查文件 {}: {}", probe.display(), e))
+}
+
+/// 解析设置菜单中输入的有效期天数（非负整数，0 表示永不过期）
+pub fn parse_expiry_days(text: &str) -> Result<u32> {
+    text.trim()
+        .parse()
+        .map_err(|_| anyhow!("有效期必须是非负整数天数（0 表示永不过期）: {}", text.trim()))
+}
+
+/// 校验输出目录存在且为目录
+pub fn validate_output_dir(dir: &Path) -> Result<()> {
+    if dir.is_dir() {
+        Ok(())
+    } else {
+        Err(anyhow!("输出目录不存在或不是目录: {}", dir.display()))
+    }
+}
+
+/// 校验网络服务地址（只接受 http/https）
+pub fn validate_http_url(url: &str) -> Result<()> {
+    if url.starts_with("https://") || url.starts_with("http://") {
+        Ok(())
+    } else {
+        Err(anyhow!("地址必须以 http:// 或 https:// 开头: {}", url))
+    }
+}
+
+/// 校验 PBKDF2 迭代次数是否在允许范围内
+pub fn parse_iterations(iterations: u32) -> Result<u32> {
+    if (MIN_ITERATIONS..=MAX_ITERATIONS).contains(&iterations) {
+        Ok(iterations)
+    } else {
+        Err(anyhow!(
+            "迭代次数 {} 超出允许范围 {}..={}",
+            iterations,
+            MIN_ITERATIONS,
+            MAX_ITERATIONS
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn env_overrides_file_which_overrides_default() {
+        let file: ConfigLayer = toml::from_str("encryption_iterations = 200000\nkey_expiry_days = 365\n").unwrap();
+        let env = ConfigLayer::from_lookup(|name| (name == "ABU_ENCRYPTION_ITERATIONS").then(|| "300000".to_string())).unwrap();
+
+        let mut config = Config::default();
+        let default_curve = config.default_curve;
+        config.apply(file).unwrap();
+        config.apply(env).unwrap();
+
+        assert_eq!(config.kdf.iterations, 300_000);
+        assert_eq!(config.key_expiry_days, Some(365));
+        assert_eq!(config.default_curve, default_curve);
+    }
+
+    #[test]
+    fn invalid_env_value_is_reported() {
+        let error = ConfigLayer::from_lookup(|name| (name == "ABU_KEY_EXPIRY_DAYS").then(|| "soon".to_string()))
+            .err()
+            .unwrap();
+        assert!(error.to_string().contains("ABU_KEY_EXPIRY_DAYS"));
+    }
+
+    #[test]
+    fn armor_toggle_selects_export_formats() {
+        let armored = Config { ascii_armor: Some(true), ..Config::default() };
+        assert!(armored.armor_exports());
+        assert_eq!(armored.private_blob_extension(), "asc");
+
+        let binary = Config { ascii_armor: Some(false), ..Config::default() };
+        assert!(!binary.armor_exports());
+        assert_eq!(binary.private_blob_extension(), "bin");
+
+        // 未指定时证书与签名装甲，私钥为二进制
+        let default = Config::default();
+        assert!(default.armor_exports());
+        assert_eq!(default.private_blob_extension(), "bin");
+    }
+
+    #[test]
+    fn settings_fields_are_validated() {
+        assert!(parse_iterations(MIN_ITERATIONS).is_ok());
+        assert!(parse_iterations(MAX_ITERATIONS).is_ok());
+        assert!(parse_iterations(MIN_ITERATIONS - 1).is_err());
+        assert!(parse_iterations(MAX_ITERATIONS + 1).is_err());
+
+        assert_eq!(parse_expiry_days(" 365 ").unwrap(), 365);
+        assert_eq!(parse_expiry_days("0").unwrap(), 0);
+        assert!(parse_expiry_days("-1").is_err());
+        assert!(parse_expiry_days("one year").is_err());
+
+        let dir = crate::testutil::temp_dir("settings-output-dir");
+        assert!(validate_output_dir(&dir).is_ok());
+        assert!(validate_output_dir(&dir.join("missing")).is_err());
+
+        assert_eq!(Language::from_name("EN"), Some(Language::En));
+        assert_eq!(Language::from_name("zh-CN"), Some(Language::Zh));
+        assert_eq!(Language::from_name("fr"), None);
+        for language in Language::ALL {
+            assert_eq!(Language::from_name(language.id()), Some(language));
+        }
+    }
+
+    #[test]
+    fn invalid_language_is_rejected() {
+        let layer: ConfigLayer = toml::from_str("language = \"klingon\"\n").unwrap();
+        assert!(Config::default().apply(layer).is_err());
+    }
+
+    #[test]
+    fn check_file_reports_invalid_curve_and_accepts_the_rest() {
+        let dir = crate::testutil::temp_dir("check-config");
+        let path = dir.join("abu-keygen.toml");
+        std::fs::write(&path, "default_curve = \"p999\"\nkey_expiry_days = 365\ncipher = \"aes256-gcm\"\n").unwrap();
+
+        let problems = check_file(&path).unwrap();
+        assert_eq!(problems.len(), 1, "{:?}", problems);
+        assert!(problems[0].starts_with("default_curve:"), "{}", problems[0]);
+        assert!(problems[0].contains("p999"), "{}", problems[0]);
+
+        std::fs::write(&path, "default_curve = \"p384\"\nkey_expiry_days = 365\n").unwrap();
+        assert!(check_file(&path).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,312 @@
+use base64::Engine;
+
+use crate::encryption::{self, BLOB_MAGIC, BLOB_VERSION, BLOB_VERSION_KEY_SLOTS_CIPHER};
+use crate::pgp;
+
+/// GCM 认证标签长度
+const TAG_LEN: usize = 16;
+
+/// 截断/损坏诊断结果（仅诊断，不修复数据）
+pub struct Diagnosis {
+    /// 识别出的文件类型
+    pub kind: String,
+    /// 发现的问题；为空表示结构完整
+    pub problems: Vec<String>,
+    /// 补充说明（不代表文件有问题）
+    pub notes: Vec<String>,
+}
+
+impl Diagnosis {
+    fn new(kind: &str) -> Self {
+        Self { kind: kind.to_string(), problems: Vec::new(), notes: Vec::new() }
+    }
+}
+
+/// 诊断密钥文件是否被截断：加密私钥（二进制或 ABU 装甲）与 OpenPGP 装甲文件
+pub fn diagnose(data: &[u8]) -> Diagnosis {
+    if data.is_empty() {
+        let mut diagnosis = Diagnosis::new("空文件");
+        diagnosis.problems.push("文件为 0 字节，写入在开始前即中断".to_string());
+        return diagnosis;
+    }
+
+    if let Ok(text) = std::str::from_utf8(data) {
+        // Windows 编辑器保存的文件可能带 BOM，解析时会被忽略，诊断时同样跳过
+        let trimmed = text.trim_start_matches('\u{feff}').trim_start();
+        if trimmed.starts_with("-----BEGIN PGP") {
+            return diagnose_pgp_armor(trimmed);
+        }
+        if encryption::is_armored_blob(trimmed) {
+            return diagnose_abu_armor(trimmed);
+        }
+    }
+
+    if data.starts_with(BLOB_MAGIC) {
+        let mut diagnosis = Diagnosis::new("加密私钥（带文件头）");
+        diagnose_blob(data, &mut diagnosis);
+        return diagnosis;
+    }
+
+    if pgp::parse_cert(data).is_ok() {
+        return Diagnosis::new("二进制 OpenPGP 证书");
+    }
+
+    // 无魔数：按旧版无文件头格式（salt || nonce || 密文）检查
+    let mut diagnosis = Diagnosis::new("加密私钥（旧版无文件头格式，或未知文件）");
+    diagnose_blob(data, &mut diagnosis);
+    diagnosis
+}
+
+/// 按字段顺序检查文件长度，定位截断位置
+struct FieldWalker {
+    len: usize,
+    pos: usize,
+}
+
+impl FieldWalker {
+    fn field(&mut self, size: usize, name: &str) -> Result<(), String> {
+        let end = self.pos + size;
+        if end > self.len {
+            return Err(format!(
+                "文件在第 {} 字节处结束：{}（偏移 {}..{}）不完整，缺少 {} 字节",
+                self.len,
+                name,
+                self.pos,
+                end,
+                end - self.len,
+            ));
+        }
+        self.pos = end;
+        Ok(())
+    }
+}
+
+/// 检查加密私钥的文件头与认证标签
+fn diagnose_blob(data: &[u8], diagnosis: &mut Diagnosis) {
+    if let Err(problem) = walk_blob(data, diagnosis) {
+        diagnosis.problems.push(problem);
+    }
+}
+
+fn walk_blob(data: &[u8], diagnosis: &mut Diagnosis) -> Result<(), String> {
+    let mut walker = FieldWalker { len: data.len(), pos: 0 };
+
+    let version = if data.starts_with(BLOB_MAGIC) {
+        walker.field(BLOB_MAGIC.len(), "魔数")?;
+        walker.field(1, "版本")?;
+        let version = data[walker.pos - 1];
+        if !(BLOB_VERSION..=BLOB_VERSION_KEY_SLOTS_CIPHER).contains(&version) {
+            return Err(format!("不支持的版本号 {}，文件头可能已损坏", version));
+        }
+        walker.field(1, "KDF 标识")?;
+        walker.field(4, "迭代次数")?;
+        if encryption::version_has_cipher(version) {
+            walker.field(1, "加密算法标识")?;
+        }
+        version
+    } else {
+        0
+    };
+
+    walker.field(16, "salt")?;
+    if encryption::version_has_key_slots(version) {
+        walker.field(12, "密码槽 nonce")?;
+        walker.field(48, "密码槽")?;
+        walker.field(4, "恢复槽长度")?;
+        let start = walker.pos - 4;
+        let recovery_len = u32::from_be_bytes([data[start], data[start + 1], data[start + 2], data[start + 3]]);
+        walker.field(recovery_len as usize, "恢复槽")?;
+    }
+    walker.field(12, "nonce")?;
+
+    let remaining = data.len() - walker.pos;
+    if remaining < TAG_LEN {
+        return Err(format!(
+            "密文与认证标签不完整：仅剩 {} 字节，至少需要 {} 字节认证标签，缺少 {} 字节",
+            remaining,
+            TAG_LEN,
+            TAG_LEN - remaining,
+        ));
+    }
+    if remaining == TAG_LEN {
+        return Err("只剩认证标签长度的数据，密文正文缺失，文件很可能被截断".to_string());
+    }
+
+    diagnosis.notes.push(format!("文件头完整，密文（含 {} 字节认证标签）共 {} 字节", TAG_LEN, remaining));
+    diagnosis
+        .notes
+        .push("密文长度无法单独校验；若密码正确仍解密失败，文件可能在密文中途被截断".to_string());
+    Ok(())
+}
+
+/// 检查 ABU 装甲块，并对能解出的数据继续检查文件头
+fn diagnose_abu_armor(text: &str) -> Diagnosis {
+    let mut diagnosis = Diagnosis::new("加密私钥（ABU 装甲）");
+    let has_end = text.lines().any(|line| line.trim().starts_with("-----END "));
+    if !has_end {
+        diagnosis.problems.push("缺少 -----END ...----- 行，装甲块不完整".to_string());
+    }
+
+    let mut body = armor_body(text);
+    if !has_end {
+        // 截断的装甲：只解码完整的 base64 分组，以便继续定位
+        body.truncate(body.len() - body.len() % 4);
+    }
+    match base64::engine::general_purpose::STANDARD.decode(&body) {
+        Ok(decoded) => diagnose_blob(&decoded, &mut diagnosis),
+        Err(e) => diagnosis.problems.push(format!("装甲正文 base64 无效: {}", e)),
+    }
+    diagnosis
+}
+
+/// 检查 OpenPGP 装甲：END 行、CRC 校验行与正文
+fn diagnose_pgp_armor(text: &str) -> Diagnosis {
+    let begin = text.lines().next().unwrap_or_default().trim();
+    let label = begin
+        .trim_start_matches("-----BEGIN ")
+        .trim_end_matches("-----")
+        .to_string();
+    let mut diagnosis = Diagnosis::new(&format!("OpenPGP 装甲（{}）", label));
+
+    let end = text.lines().map(str::trim).find(|line| line.starts_with("-----END "));
+    let crc_line = text
+        .lines()
+        .map(str::trim)
+        .find(|line| line.starts_with('=') && line.len() == 5);
+
+    match end {
+        None => {
+            diagnosis.problems.push(format!("缺少 -----END {}----- 行，装甲不完整", label));
+            if crc_line.is_none() {
+                diagnosis.problems.push("同时缺少 CRC 校验行，文件很可能在正文中被截断".to_string());
+            }
+        }
+        Some(end) if end != format!("-----END {}-----", label) => {
+            diagnosis.problems.push(format!("END 行与 BEGIN 行不匹配: {}", end));
+        }
+        Some(_) => {
+            if crc_line.is_none() {
+                diagnosis.notes.push("没有 CRC 校验行（标准允许省略）".to_string());
+            }
+        }
+    }
+
+    let body = armor_body(text);
+    let decoded = match base64::engine::general_purpose::STANDARD.decode(&body) {
+        Ok(decoded) => decoded,
+        Err(e) => {
+            diagnosis.problems.push(format!("正文 base64 无效（共 {} 个字符）: {}", body.len(), e));
+            return diagnosis;
+        }
+    };
+
+    if let Some(crc_line) = crc_line {
+        let expected = base64::engine::general_purpose::STANDARD
+            .decode(&crc_line[1..])
+            .ok()
+            .filter(|crc| crc.len() == 3)
+            .map(|crc| u32::from_be_bytes([0, crc[0], crc[1], crc[2]]));
+        match expected {
+            Some(expected) if expected != pgp::crc24(&decoded) => {
+                diagnosis.problems.push("CRC-24 校验不匹配，正文已损坏或不完整".to_string());
+            }
+            Some(_) => {}
+            None => diagnosis.problems.push(format!("CRC 校验行格式无效: {}", crc_line)),
+        }
+    }
+
+    if diagnosis.problems.is_empty()
+        && let Err(e) = pgp::parse_cert(&decoded)
+    {
+        diagnosis.problems.push(format!("装甲完整，但其中的 OpenPGP 数据无法解析: {}", e));
+    }
+    diagnosis
+}
+
+/// 提取装甲正文：跳过 BEGIN/END 行、头部字段与 CRC 行
+fn armor_body(text: &str) -> String {
+    text.lines()
+        .map(str::trim)
+        .skip(1)
+        .take_while(|line| !line.starts_with("-----END "))
+        .filter(|line| !line.is_empty() && !line.contains(':') && !line.starts_with('='))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encryption::AeadCipher;
+    use crate::security::{KdfHash, KdfParams, KeyOptions, SecureKey};
+
+    /// v1 文件头长度：魔数(4) + 版本(1) + KDF 标识(1) + 迭代次数(4)
+    const V1_PREFIX: usize = 10;
+
+    fn blob() -> Vec<u8> {
+        let kdf = KdfParams { hash: KdfHash::Sha256, iterations: crate::config::MIN_ITERATIONS };
+        encryption::encrypt_with_password(b"secret key bytes", "password", &kdf, AeadCipher::Aes256Gcm).unwrap()
+    }
+
+    fn armored_cert() -> String {
+        let key = SecureKey::generate(&["Bank <bank@example.com>".to_string()], &KeyOptions::default()).unwrap();
+        key.public_cert_armored().as_str().to_string()
+    }
+
+    #[test]
+    fn complete_blob_has_no_problems() {
+        assert!(diagnose(&blob()).problems.is_empty());
+    }
+
+    #[test]
+    fn empty_file_is_reported() {
+        let diagnosis = diagnose(b"");
+        assert_eq!(diagnosis.kind, "空文件");
+        assert_eq!(diagnosis.problems.len(), 1);
+    }
+
+    #[test]
+    fn truncated_header_names_missing_field() {
+        let data = blob();
+        let diagnosis = diagnose(&data[..V1_PREFIX + 6]);
+        assert_eq!(diagnosis.problems.len(), 1);
+        assert!(diagnosis.problems[0].contains("salt"), "{:?}", diagnosis.problems);
+        assert!(diagnosis.problems[0].contains("缺少 10 字节"), "{:?}", diagnosis.problems);
+    }
+
+    #[test]
+    fn truncated_tag_is_reported() {
+        let data = blob();
+        let diagnosis = diagnose(&data[..V1_PREFIX + 16 + 12 + 5]);
+        assert_eq!(diagnosis.problems.len(), 1);
+        assert!(diagnosis.problems[0].contains("认证标签"), "{:?}", diagnosis.problems);
+    }
+
+    #[test]
+    fn truncated_abu_armor_is_reported() {
+        let armored = encryption::armor_blob(&blob(), encryption::BLOB_ARMOR_LABEL, 64);
+        let cut = &armored[..armored.find("-----END ").unwrap()];
+        let diagnosis = diagnose(cut.as_bytes());
+        assert!(diagnosis.problems.iter().any(|problem| problem.contains("缺少 -----END")), "{:?}", diagnosis.problems);
+    }
+
+    #[test]
+    fn complete_public_armor_has_no_problems() {
+        assert!(diagnose(armored_cert().as_bytes()).problems.is_empty());
+    }
+
+    #[test]
+    fn public_armor_missing_footer_is_reported() {
+        let armored = armored_cert();
+        let cut = &armored[..armored.find("-----END ").unwrap()];
+        let diagnosis = diagnose(cut.as_bytes());
+        assert!(diagnosis.problems.iter().any(|problem| problem.contains("缺少 -----END PGP PUBLIC KEY BLOCK-----")));
+    }
+
+    #[test]
+    fn public_armor_truncated_in_body_is_reported() {
+        let armored = armored_cert();
+        let cut = &armored[..armored.len() / 2];
+        let diagnosis = diagnose(cut.as_bytes());
+        assert!(diagnosis.problems.iter().any(|problem| problem.contains("CRC")), "{:?}", diagnosis.problems);
+    }
+}
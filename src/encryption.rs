@@ -1,25 +1,85 @@
 use aes_gcm::{
     aead::{Aead, KeyInit},
-    Aes256Gcm, Key, Nonce,
+    Aes128Gcm, Aes256Gcm, Key, Nonce,
 };
 use rand::RngCore;
+use crate::config;
+use crate::error::AbuError;
+use crate::security::{KdfHash, KdfParams, SecureKey};
+use sequoia_openpgp::Cert;
+use zeroize::Zeroizing;
 
-/// 使用AES-GCM加密数据
+/// 内容加密使用的 AEAD 算法（认证标签固定为完整的 16 字节）
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AeadCipher {
+    /// AES-256-GCM（默认）
+    #[default]
+    Aes256Gcm,
+    /// AES-128-GCM，供要求 128 位密钥的系统互通使用
+    Aes128Gcm,
+}
+
+impl AeadCipher {
+    /// 写入文件头的算法标识
+    pub fn id(self) -> u8 {
+        match self {
+            AeadCipher::Aes256Gcm => 1,
+            AeadCipher::Aes128Gcm => 2,
+        }
+    }
+
+    /// 从文件头中的算法标识解析
+    pub fn from_id(id: u8) -> Result<Self, anyhow::Error> {
+        match id {
+            1 => Ok(AeadCipher::Aes256Gcm),
+            2 => Ok(AeadCipher::Aes128Gcm),
+            other => Err(anyhow::anyhow!("unknown cipher id: {}", other)),
+        }
+    }
+
+    /// 从配置中的名称解析（aes256-gcm / aes128-gcm，不区分大小写）
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "aes256-gcm" | "aes-256-gcm" => Some(AeadCipher::Aes256Gcm),
+            "aes128-gcm" | "aes-128-gcm" => Some(AeadCipher::Aes128Gcm),
+            _ => None,
+        }
+    }
+
+    /// 显示名称
+    pub fn name(self) -> &'static str {
+        match self {
+            AeadCipher::Aes256Gcm => "AES-256-GCM",
+            AeadCipher::Aes128Gcm => "AES-128-GCM",
+        }
+    }
+
+    /// 弱于默认算法时的警告文本
+    pub fn weakness_warning(self) -> Option<&'static str> {
+        match self {
+            AeadCipher::Aes256Gcm => None,
+            AeadCipher::Aes128Gcm => Some("AES-128-GCM 的安全余量低于默认的 AES-256-GCM，仅在对方系统要求时使用"),
+        }
+    }
+}
+
+/// 使用AES-GCM加密数据（AES-128 时使用密钥的前 16 字节）
 pub fn aes_gcm_encrypt(
     plaintext: &[u8],
     key: &[u8; 32],
+    cipher: AeadCipher,
 ) -> Result<(Vec<u8>, [u8; 12]), anyhow::Error> {
-    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
-    
     // 生成随机nonce
     let mut nonce_bytes = [0u8; 12];
     let mut rng = rand::rngs::OsRng;
     rng.fill_bytes(&mut nonce_bytes);
     let nonce = Nonce::from_slice(&nonce_bytes);
     
-    let ciphertext = cipher
-        .encrypt(nonce, plaintext)
-        .map_err(|e| anyhow::anyhow!("Encryption failed: {:?}", e))?;
+    let ciphertext = match cipher {
+        AeadCipher::Aes256Gcm => Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key)).encrypt(nonce, plaintext),
+        AeadCipher::Aes128Gcm => Aes128Gcm::new(Key::<Aes128Gcm>::from_slice(&key[..16])).encrypt(nonce, plaintext),
+    }
+    .map_err(|e| anyhow::anyhow!("Encryption failed: {:?}", e))?;
     
     Ok((ciphertext, nonce_bytes))
 }
@@ -30,15 +90,533 @@ pub fn aes_gcm_decrypt(
     ciphertext: &[u8],
     key: &[u8; 32],
     nonce: &[u8; 12],
+    cipher: AeadCipher,
 ) -> Result<Vec<u8>, anyhow::Error> {
-    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
     let nonce = Nonce::from_slice(nonce);
     
-    let plaintext = cipher
-        .decrypt(nonce, ciphertext)
-        .map_err(|e| anyhow::anyhow!("Decryption failed: {:?}", e))?;
+    let plaintext = match cipher {
+        AeadCipher::Aes256Gcm => Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key)).decrypt(nonce, ciphertext),
+        AeadCipher::Aes128Gcm => Aes128Gcm::new(Key::<Aes128Gcm>::from_slice(&key[..16])).decrypt(nonce, ciphertext),
+    }
+    .map_err(|e| anyhow::anyhow!("Decryption failed: {:?}", e))?;
     
     Ok(plaintext)
 }
 
-// SecureBuffer removed (unused). Add back if secure buffer semantics are needed.
\ No newline at end of file
+/// 加密文件头魔数
+pub const BLOB_MAGIC: &[u8; 4] = b"ABUK";
+/// 仅密码保护的加密文件格式版本
+pub const BLOB_VERSION: u8 = 1;
+/// 带密钥槽（密码 + 恢复公钥）的加密文件格式版本
+pub const BLOB_VERSION_KEY_SLOTS: u8 = 2;
+/// v1 加上 AEAD 算法标识的版本（仅在使用非默认算法时写出）
+pub const BLOB_VERSION_CIPHER: u8 = 3;
+/// v2 加上 AEAD 算法标识的版本（仅在使用非默认算法时写出）
+pub const BLOB_VERSION_KEY_SLOTS_CIPHER: u8 = 4;
+
+/// 该版本是否在 iterations 之后带有 AEAD 算法标识
+pub fn version_has_cipher(version: u8) -> bool {
+    version == BLOB_VERSION_CIPHER || version == BLOB_VERSION_KEY_SLOTS_CIPHER
+}
+
+/// 该版本是否带有密钥槽
+pub fn version_has_key_slots(version: u8) -> bool {
+    version == BLOB_VERSION_KEY_SLOTS || version == BLOB_VERSION_KEY_SLOTS_CIPHER
+}
+
+/// 数据密钥的两个封装槽：密码槽与恢复公钥槽
+///
+/// 内容使用随机数据密钥加密；数据密钥分别以密码派生密钥（AES-GCM）和
+/// 恢复证书的加密子密钥（OpenPGP 消息）封装，任一路径均可解出。
+pub struct KeySlots {
+    /// 密码槽 AES-GCM nonce
+    pub password_nonce: [u8; 12],
+    /// 密码槽：AES-GCM 封装的数据密钥（32 字节密钥 + 16 字节标签）
+    pub password_wrapped: Vec<u8>,
+    /// 恢复槽：加密给恢复证书的 OpenPGP 消息
+    pub recovery_wrapped: Vec<u8>,
+}
+
+/// 加密文件头
+///
+/// v1 格式：`ABUK` || version(1) || kdf_id(1) || iterations(u32 BE) || salt(16) || nonce(12) || ciphertext。
+/// v2 格式在 salt 与 nonce 之间插入密钥槽：
+/// password_nonce(12) || password_wrapped(48) || recovery_len(u32 BE) || recovery_wrapped。
+/// v3 / v4 分别在 v1 / v2 的 iterations 之后插入 cipher_id(1)；未带该字段时为 AES-256-GCM。
+/// 不带魔数的旧文件按 salt(16) || nonce(12) || ciphertext 解析，使用默认 KDF 参数。
+pub struct BlobHeader {
+    /// 格式版本；旧版无文件头格式为 0
+    pub version: u8,
+    pub kdf: KdfParams,
+    pub cipher: AeadCipher,
+    pub salt: [u8; 16],
+    /// v2 格式的密钥槽
+    pub key_slots: Option<KeySlots>,
+    pub nonce: [u8; 12],
+}
+
+/// 解析加密文件头，返回文件头与密文
+pub fn parse_blob(data: &[u8]) -> Result<(BlobHeader, &[u8]), anyhow::Error> {
+    let mut reader = BlobReader { data, pos: 0 };
+
+    let (version, kdf, cipher) = if data.starts_with(BLOB_MAGIC) {
+        reader.take(4, "魔数")?;
+        let version = reader.take(1, "版本")?[0];
+        if !(BLOB_VERSION..=BLOB_VERSION_KEY_SLOTS_CIPHER).contains(&version) {
+            return Err(AbuError::BadFormat(format!("不支持的加密文件版本: {}", version)).into());
+        }
+        let hash = KdfHash::from_id(reader.take(1, "KDF 标识")?[0])
+            .map_err(|e| AbuError::BadFormat(e.to_string()))?;
+        let iterations = u32::from_be_bytes(reader.take(4, "迭代次数")?.try_into()?);
+        // 迭代次数来自文件，超出范围时拒绝，避免损坏或恶意文件导致极慢的密钥派生
+        if !(config::MIN_ITERATIONS..=config::MAX_ITERATIONS).contains(&iterations) {
+            return Err(AbuError::BadFormat(format!(
+                "迭代次数 {} 超出允许范围 {}..={}",
+                iterations,
+                config::MIN_ITERATIONS,
+                config::MAX_ITERATIONS
+            ))
+            .into());
+        }
+        let cipher = if version_has_cipher(version) {
+            AeadCipher::from_id(reader.take(1, "加密算法标识")?[0])
+                .map_err(|e| AbuError::BadFormat(e.to_string()))?
+        } else {
+            AeadCipher::Aes256Gcm
+        };
+        (version, KdfParams { hash, iterations }, cipher)
+    } else {
+        (0, KdfParams::default(), AeadCipher::Aes256Gcm)
+    };
+
+    let salt: [u8; 16] = reader.take(16, "salt")?.try_into()?;
+
+    let key_slots = if version_has_key_slots(version) {
+        let password_nonce: [u8; 12] = reader.take(12, "密码槽 nonce")?.try_into()?;
+        let password_wrapped = reader.take(48, "密码槽")?.to_vec();
+        let recovery_len = u32::from_be_bytes(reader.take(4, "恢复槽长度")?.try_into()?) as usize;
+        let recovery_wrapped = reader.take(recovery_len, "恢复槽")?.to_vec();
+        Some(KeySlots { password_nonce, password_wrapped, recovery_wrapped })
+    } else {
+        None
+    };
+
+    let nonce: [u8; 12] = reader.take(12, "nonce")?.try_into()?;
+    let ciphertext = reader.rest();
+    if ciphertext.len() < 16 {
+        return Err(AbuError::BadFormat("文件太短，无法包含 salt/nonce/密文".to_string()).into());
+    }
+
+    Ok((BlobHeader { version, kdf, cipher, salt, key_slots, nonce }, ciphertext))
+}
+
+/// 解析文件头用的只读游标，越界时返回说明缺失字段的错误
+struct BlobReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> BlobReader<'a> {
+    fn take(&mut self, len: usize, field: &str) -> Result<&'a [u8], anyhow::Error> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.data.len())
+            .ok_or_else(|| AbuError::BadFormat(format!("文件太短，{} 不完整", field)))?;
+        let slice = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn rest(&self) -> &'a [u8] {
+        &self.data[self.pos..]
+    }
+}
+
+/// 写入通用的文件头前缀：魔数、版本、KDF 标识、迭代次数，以及非默认算法时的算法标识
+///
+/// 默认的 AES-256-GCM 仍写出 v1 / v2，旧版本程序可继续读取。
+fn write_header_prefix(out: &mut Vec<u8>, key_slots: bool, kdf: &KdfParams, cipher: AeadCipher) {
+    let with_cipher = cipher != AeadCipher::Aes256Gcm;
+    let version = match (key_slots, with_cipher) {
+        (false, false) => BLOB_VERSION,
+        (true, false) => BLOB_VERSION_KEY_SLOTS,
+        (false, true) => BLOB_VERSION_CIPHER,
+        (true, true) => BLOB_VERSION_KEY_SLOTS_CIPHER,
+    };
+    out.extend_from_slice(BLOB_MAGIC);
+    out.push(version);
+    out.push(kdf.hash.id());
+    out.extend_from_slice(&kdf.iterations.to_be_bytes());
+    if with_cipher {
+        out.push(cipher.id());
+    }
+}
+
+/// 生成随机 salt
+fn random_salt() -> [u8; 16] {
+    let mut salt = [0u8; 16];
+    let mut rng = rand::rngs::OsRng;
+    rng.fill_bytes(&mut salt);
+    salt
+}
+
+/// 使用密码加密数据，输出带文件头的加密数据
+pub fn encrypt_with_password(
+    plaintext: &[u8],
+    password: &str,
+    kdf: &KdfParams,
+    cipher: AeadCipher,
+) -> Result<Vec<u8>, anyhow::Error> {
+    // 生成盐值
+    let salt = random_salt();
+
+    // 派生加密密钥
+    let encryption_key = SecureKey::derive_encryption_key(password, &salt, kdf)?;
+
+    // 加密数据
+    let (ciphertext, nonce) = aes_gcm_encrypt(plaintext, &encryption_key, cipher)?;
+
+    // 组合数据：文件头 + 盐 + nonce + 密文
+    let mut encrypted_data = Vec::new();
+    write_header_prefix(&mut encrypted_data, false, kdf, cipher);
+    encrypted_data.extend_from_slice(&salt);
+    encrypted_data.extend_from_slice(&nonce);
+    encrypted_data.extend_from_slice(&ciphertext);
+
+    Ok(encrypted_data)
+}
+
+/// 使用密码加密数据，并额外将数据密钥封装给恢复证书（v2 格式）
+pub fn encrypt_with_password_and_recovery(
+    plaintext: &[u8],
+    password: &str,
+    kdf: &KdfParams,
+    cipher: AeadCipher,
+    recovery_cert: &Cert,
+) -> Result<Vec<u8>, anyhow::Error> {
+    // 随机数据密钥，用于加密内容
+    let mut data_key = Zeroizing::new([0u8; 32]);
+    rand::rngs::OsRng.fill_bytes(&mut data_key[..]);
+    let (ciphertext, nonce) = aes_gcm_encrypt(plaintext, &data_key, cipher)?;
+
+    // 密码槽
+    let salt = random_salt();
+    let password_key = Zeroizing::new(SecureKey::derive_encryption_key(password, &salt, kdf)?);
+    let (password_wrapped, password_nonce) = aes_gcm_encrypt(&data_key[..], &password_key, cipher)?;
+
+    // 恢复槽
+    let recovery_wrapped = crate::pgp::encrypt_to_cert(recovery_cert, &data_key[..])?;
+    let recovery_len = u32::try_from(recovery_wrapped.len())
+        .map_err(|_| anyhow::anyhow!("恢复槽过大"))?;
+
+    let mut encrypted_data = Vec::new();
+    write_header_prefix(&mut encrypted_data, true, kdf, cipher);
+    encrypted_data.extend_from_slice(&salt);
+    encrypted_data.extend_from_slice(&password_nonce);
+    encrypted_data.extend_from_slice(&password_wrapped);
+    encrypted_data.extend_from_slice(&recovery_len.to_be_bytes());
+    encrypted_data.extend_from_slice(&recovery_wrapped);
+    encrypted_data.extend_from_slice(&nonce);
+    encrypted_data.extend_from_slice(&ciphertext);
+
+    Ok(encrypted_data)
+}
+
+/// 使用密码解密数据，KDF 参数从文件头读取（接受二进制或 ABU 装甲格式）
+pub fn decrypt_with_password(data: &[u8], password: &str) -> Result<Vec<u8>, anyhow::Error> {
+    let data = unarmor_blob(data)?;
+    let (header, ciphertext) = parse_blob(&data)?;
+
+    // 派生密钥并解密
+    let key = Zeroizing::new(SecureKey::derive_encryption_key(password, &header.salt, &header.kdf)?);
+
+    // GCM 认证失败即视为密码错误（或数据被篡改）
+    match &header.key_slots {
+        Some(slots) => {
+            let wrapped = aes_gcm_decrypt(&slots.password_wrapped, &key, &slots.password_nonce, header.cipher)
+                .map_err(|_| AbuError::WrongPassword)?;
+            let data_key = unwrap_data_key(&wrapped)?;
+            aes_gcm_decrypt(ciphertext, &data_key, &header.nonce, header.cipher)
+                .map_err(|_| AbuError::WrongPassword.into())
+        }
+        None => aes_gcm_decrypt(ciphertext, &key, &header.nonce, header.cipher)
+            .map_err(|_| AbuError::WrongPassword.into()),
+    }
+}
+
+/// 使用恢复证书的私钥解密 v2 格式数据（无需用户密码）
+pub fn decrypt_with_recovery_key(data: &[u8], recovery_tsk: &Cert) -> Result<Vec<u8>, anyhow::Error> {
+    let data = unarmor_blob(data)?;
+    let (header, ciphertext) = parse_blob(&data)?;
+    let slots = header
+        .key_slots
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("该文件没有恢复密钥槽，只能使用密码解密"))?;
+
+    let data_key = unwrap_data_key(&crate::pgp::decrypt_with_cert(recovery_tsk, &slots.recovery_wrapped)?)?;
+    aes_gcm_decrypt(ciphertext, &data_key, &header.nonce, header.cipher)
+}
+
+/// 校验解出的数据密钥长度
+fn unwrap_data_key(bytes: &[u8]) -> Result<Zeroizing<[u8; 32]>, anyhow::Error> {
+    let key: [u8; 32] = bytes
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("密钥槽内容长度无效"))?;
+    Ok(Zeroizing::new(key))
+}
+
+/// ABU 加密私钥装甲块的默认标签
+pub const BLOB_ARMOR_LABEL: &str = "ABU ENCRYPTED PRIVATE KEY";
+/// 自定义装甲标签的最大长度
+const MAX_ARMOR_LABEL_LEN: usize = 64;
+/// ABU 装甲块默认的 base64 行宽；解除装甲时接受任意行宽
+pub const BLOB_ARMOR_WIDTH: usize = 64;
+
+/// 校验自定义装甲标签：大写字母、数字与空格，且不能冒充 OpenPGP 装甲
+pub fn validate_armor_label(label: &str) -> Result<(), anyhow::Error> {
+    let valid_chars = label
+        .chars()
+        .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == ' ');
+    if label.trim().is_empty() || label.trim() != label || label.len() > MAX_ARMOR_LABEL_LEN || !valid_chars {
+        return Err(anyhow::anyhow!(
+            "无效的装甲标签: {:?}（仅限大写字母、数字与空格，最长 {} 个字符）",
+            label,
+            MAX_ARMOR_LABEL_LEN,
+        ));
+    }
+    if label.starts_with("PGP") {
+        return Err(anyhow::anyhow!("装甲标签不能以 PGP 开头，以免与 OpenPGP 装甲混淆: {}", label));
+    }
+    Ok(())
+}
+
+/// 将加密数据封装为 ABU 自定义 ASCII 装甲块；`label` 仅为品牌展示，解除装甲时以内部魔数识别
+///
+/// `width` 为每行 base64 字符数，必须大于 0。
+pub fn armor_blob(data: &[u8], label: &str, width: usize) -> String {
+    use base64::Engine;
+
+    let body = base64::engine::general_purpose::STANDARD.encode(data);
+    let mut out = format!("-----BEGIN {}-----\n", label);
+    for line in body.as_bytes().chunks(width) {
+        // base64 输出必为 ASCII，按字节切分安全
+        out.push_str(&String::from_utf8_lossy(line));
+        out.push('\n');
+    }
+    out.push_str(&format!("-----END {}-----\n", label));
+    out
+}
+
+/// 加密私钥文件的内容：`armored` 时为 ABU 装甲块，否则为原始二进制
+pub fn blob_file_content(data: &[u8], armored: bool, label: &str, width: usize) -> Vec<u8> {
+    if armored {
+        armor_blob(data, label, width).into_bytes()
+    } else {
+        data.to_vec()
+    }
+}
+
+/// 数据是否为 ABU 装甲块；允许开头的 UTF-8 BOM
+///
+/// 标签可由组织自定义，因此不按标签判断，而是解码正文开头检查 ABU 魔数：
+/// OpenPGP 装甲、SPKI `PUBLIC KEY` 等其他 PEM 块不会被误认。
+pub fn is_armored_blob(text: &str) -> bool {
+    use base64::Engine;
+
+    let text = text.trim_start_matches('\u{feff}').trim_start();
+    if !text.starts_with("-----BEGIN ") {
+        return false;
+    }
+    // 8 个 base64 字符解出 6 字节，足以覆盖 4 字节魔数；行宽可能小于 8，跨行拼接
+    let prefix: String = text
+        .lines()
+        .skip(1)
+        .map(str::trim)
+        .take_while(|line| !line.starts_with("-----END "))
+        .flat_map(str::chars)
+        .take(8)
+        .collect();
+    base64::engine::general_purpose::STANDARD
+        .decode(prefix)
+        .is_ok_and(|prefix| prefix.starts_with(BLOB_MAGIC))
+}
+
+/// 若数据是 ABU 装甲块则解除装甲，否则原样返回（二进制与装甲格式均可解密）
+///
+/// 接受任意标签（组织可自定义品牌标签），以解出数据开头的 ABU 魔数为准。
+pub fn unarmor_blob(data: &[u8]) -> Result<std::borrow::Cow<'_, [u8]>, anyhow::Error> {
+    use base64::Engine;
+
+    let text = match std::str::from_utf8(data) {
+        Ok(text) if is_armored_blob(text) => text,
+        _ => return Ok(std::borrow::Cow::Borrowed(data)),
+    };
+    // Windows 编辑器保存的 BOM；CRLF 换行由 lines() 与 trim 处理
+    let text = text.trim_start_matches('\u{feff}');
+
+    let body: String = text
+        .lines()
+        .map(str::trim)
+        .skip_while(|line| !line.starts_with("-----BEGIN "))
+        .skip(1)
+        .take_while(|line| !line.starts_with("-----END "))
+        .collect();
+    if !text.contains("-----END ") {
+        return Err(AbuError::BadFormat("ABU 装甲块缺少 END 行".to_string()).into());
+    }
+
+    let decoded = base64::engine::general_purpose::STANDARD
+        .decode(body)
+        .map_err(|e| AbuError::BadFormat(format!("ABU 装甲块 base64 无效: {}", e)))?;
+    if !decoded.starts_with(BLOB_MAGIC) {
+        return Err(AbuError::BadFormat("装甲块内容不是 ABU 加密数据（缺少魔数）".to_string()).into());
+    }
+    Ok(std::borrow::Cow::Owned(decoded))
+}
+
+// SecureBuffer removed (unused). Add back if secure buffer semantics are needed.
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PASSWORD: &str = "Correct-Horse-Battery-42";
+
+    fn params(hash: KdfHash) -> KdfParams {
+        KdfParams { hash, iterations: config::MIN_ITERATIONS }
+    }
+
+    #[test]
+    fn round_trip_with_both_kdf_hashes() {
+        for hash in [KdfHash::Sha256, KdfHash::Sha512] {
+            let blob = encrypt_with_password(b"secret key bytes", PASSWORD, &params(hash), AeadCipher::Aes256Gcm).unwrap();
+            let (header, _) = parse_blob(&blob).unwrap();
+            assert_eq!(header.kdf.hash, hash);
+            assert_eq!(decrypt_with_password(&blob, PASSWORD).unwrap(), b"secret key bytes");
+        }
+    }
+
+    #[test]
+    fn mismatched_kdf_hash_fails_decryption() {
+        let mut blob =
+            encrypt_with_password(b"secret key bytes", PASSWORD, &params(KdfHash::Sha256), AeadCipher::Aes256Gcm).unwrap();
+        // 文件头：魔数(4) || 版本(1) || KDF 标识(1)
+        blob[5] = KdfHash::Sha512.id();
+        assert!(decrypt_with_password(&blob, PASSWORD).is_err());
+    }
+
+    #[test]
+    fn out_of_range_iterations_are_rejected() {
+        let blob =
+            encrypt_with_password(b"secret key bytes", PASSWORD, &params(KdfHash::Sha256), AeadCipher::Aes256Gcm).unwrap();
+        for iterations in [1, config::MIN_ITERATIONS - 1, config::MAX_ITERATIONS + 1, u32::MAX] {
+            let mut tampered = blob.clone();
+            tampered[6..10].copy_from_slice(&iterations.to_be_bytes());
+            let error = parse_blob(&tampered).err().unwrap();
+            assert!(matches!(error.downcast_ref::<AbuError>(), Some(AbuError::BadFormat(_))));
+        }
+    }
+
+    #[test]
+    fn password_and_recovery_paths_recover_same_plaintext() {
+        let options = crate::security::KeyOptions::default();
+        let recovery = SecureKey::generate(&["Recovery Agent <recovery@example.com>".to_string()], &options).unwrap();
+        let recovery_tsk = crate::pgp::parse_cert(&recovery.secret_key_bytes()).unwrap();
+        let recovery_public = crate::pgp::strip_secrets(recovery_tsk.clone()).unwrap();
+
+        let blob = encrypt_with_password_and_recovery(
+            b"secret key bytes",
+            PASSWORD,
+            &params(KdfHash::Sha256),
+            AeadCipher::Aes256Gcm,
+            &recovery_public,
+        )
+        .unwrap();
+
+        assert!(parse_blob(&blob).unwrap().0.key_slots.is_some());
+        let by_password = decrypt_with_password(&blob, PASSWORD).unwrap();
+        let by_recovery = decrypt_with_recovery_key(&blob, &recovery_tsk).unwrap();
+        assert_eq!(by_password, b"secret key bytes");
+        assert_eq!(by_recovery, by_password);
+    }
+
+    #[test]
+    fn round_trip_with_both_ciphers() {
+        for cipher in [AeadCipher::Aes256Gcm, AeadCipher::Aes128Gcm] {
+            let blob = encrypt_with_password(b"secret key bytes", PASSWORD, &params(KdfHash::Sha256), cipher).unwrap();
+            let (header, _) = parse_blob(&blob).unwrap();
+            assert_eq!(header.cipher, cipher);
+            assert_eq!(header.version == BLOB_VERSION_CIPHER, cipher != AeadCipher::Aes256Gcm);
+            assert_eq!(decrypt_with_password(&blob, PASSWORD).unwrap(), b"secret key bytes");
+        }
+    }
+
+    #[test]
+    fn mismatched_cipher_id_fails_decryption() {
+        let mut blob =
+            encrypt_with_password(b"secret key bytes", PASSWORD, &params(KdfHash::Sha256), AeadCipher::Aes128Gcm).unwrap();
+        // v3 文件头：魔数(4) || 版本(1) || KDF 标识(1) || 迭代次数(4) || 算法标识(1)
+        assert_eq!(blob[10], AeadCipher::Aes128Gcm.id());
+        blob[10] = AeadCipher::Aes256Gcm.id();
+        assert!(decrypt_with_password(&blob, PASSWORD).is_err());
+
+        blob[10] = 0xff;
+        let error = parse_blob(&blob).err().unwrap();
+        assert!(matches!(error.downcast_ref::<AbuError>(), Some(AbuError::BadFormat(_))));
+    }
+
+    #[test]
+    fn only_weaker_cipher_has_warning() {
+        assert!(AeadCipher::Aes256Gcm.weakness_warning().is_none());
+        assert!(AeadCipher::Aes128Gcm.weakness_warning().is_some());
+    }
+
+    #[test]
+    fn custom_label_round_trips_by_magic() {
+        let blob =
+            encrypt_with_password(b"secret key bytes", PASSWORD, &params(KdfHash::Sha256), AeadCipher::Aes256Gcm).unwrap();
+        for width in [4, BLOB_ARMOR_WIDTH] {
+            let armored = armor_blob(&blob, "ACME BANK ENCRYPTED KEY", width);
+            assert!(armored.starts_with("-----BEGIN ACME BANK ENCRYPTED KEY-----\n"));
+            assert!(is_armored_blob(&armored));
+            assert_eq!(unarmor_blob(armored.as_bytes()).unwrap().as_ref(), blob.as_slice());
+            assert_eq!(decrypt_with_password(armored.as_bytes(), PASSWORD).unwrap(), b"secret key bytes");
+        }
+
+        // 标签只是展示：同样的标签包着非 ABU 数据时不被当作 ABU 装甲块
+        let foreign = armor_blob(b"not an abu blob at all", "ACME BANK ENCRYPTED KEY", BLOB_ARMOR_WIDTH);
+        assert!(!is_armored_blob(&foreign));
+        assert!(decrypt_with_password(foreign.as_bytes(), PASSWORD).is_err());
+    }
+
+    #[test]
+    fn other_pem_blocks_are_not_abu_armor() {
+        let spki = "-----BEGIN PUBLIC KEY-----\n\
+                    MFkwEwYHKoZIzj0CAQYIKoZIzj0DAQcDQgAE\n\
+                    -----END PUBLIC KEY-----\n";
+        assert!(!is_armored_blob(spki));
+        assert_eq!(unarmor_blob(spki.as_bytes()).unwrap().as_ref(), spki.as_bytes());
+        assert!(!is_armored_blob("-----BEGIN PGP PUBLIC KEY BLOCK-----\n\nxjMEZ\n-----END PGP PUBLIC KEY BLOCK-----\n"));
+    }
+
+    #[test]
+    fn armor_widths_64_and_200_dearmor_to_identical_bytes() {
+        let blob = encrypt_with_password(b"secret key bytes", PASSWORD, &params(KdfHash::Sha256), AeadCipher::Aes256Gcm)
+            .unwrap();
+        let narrow = armor_blob(&blob, BLOB_ARMOR_LABEL, 64);
+        let wide = armor_blob(&blob, BLOB_ARMOR_LABEL, 200);
+
+        let body_lines =
+            |text: &str| text.lines().filter(|line| !line.starts_with("-----")).map(str::len).collect::<Vec<_>>();
+        assert!(body_lines(&narrow).iter().all(|len| *len <= 64));
+        assert!(body_lines(&narrow).len() > 1);
+        assert_eq!(body_lines(&wide).len(), 1);
+        assert!(body_lines(&wide)[0] > 64 && body_lines(&wide)[0] <= 200);
+
+        let from_narrow = unarmor_blob(narrow.as_bytes()).unwrap();
+        let from_wide = unarmor_blob(wide.as_bytes()).unwrap();
+        assert_eq!(from_narrow, from_wide);
+        assert_eq!(&from_narrow[..], &blob[..]);
+        assert_eq!(decrypt_with_password(&from_wide, PASSWORD).unwrap(), b"secret key bytes");
+    }
+}
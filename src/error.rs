@@ -0,0 +1,56 @@
+use thiserror::Error;
+
+/// 进程退出码约定（供脚本判断失败原因）
+pub mod exit_code {
+    /// 其他错误（成功时为 0）
+    pub const GENERAL: i32 = 1;
+    /// 用户取消
+    pub const CANCELLED: i32 = 2;
+    /// 密码错误
+    pub const WRONG_PASSWORD: i32 = 3;
+    /// 文件格式错误
+    pub const BAD_FORMAT: i32 = 4;
+    /// 加密/后端错误
+    pub const CRYPTO: i32 = 5;
+}
+
+/// 需要区分退出码的结构化错误
+#[derive(Debug, Error)]
+pub enum AbuError {
+    #[error("用户取消了操作: {0}")]
+    Cancelled(String),
+    #[error("密码错误，或文件已被篡改/损坏")]
+    WrongPassword,
+    #[error("文件格式错误: {0}")]
+    BadFormat(String),
+    #[error("加密后端错误: {0}")]
+    Crypto(String),
+}
+
+impl AbuError {
+    /// 对应的进程退出码
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            AbuError::Cancelled(_) => exit_code::CANCELLED,
+            AbuError::WrongPassword => exit_code::WRONG_PASSWORD,
+            AbuError::BadFormat(_) => exit_code::BAD_FORMAT,
+            AbuError::Crypto(_) => exit_code::CRYPTO,
+        }
+    }
+}
+
+/// 错误链中是否包含用户取消
+pub fn is_cancelled(error: &anyhow::Error) -> bool {
+    error
+        .chain()
+        .any(|cause| matches!(cause.downcast_ref::<AbuError>(), Some(AbuError::Cancelled(_))))
+}
+
+/// 从错误链中查找结构化错误并映射为退出码，找不到时为通用错误码
+pub fn exit_code_for(error: &anyhow::Error) -> i32 {
+    error
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<AbuError>())
+        .map(AbuError::exit_code)
+        .unwrap_or(exit_code::GENERAL)
+}
@@ -0,0 +1,65 @@
+use anyhow::{anyhow, Result};
+use std::path::Path;
+use std::process::Command;
+
+/// 运行生成后钩子：参数依次为公钥路径、主密钥指纹、银行名称，并以 `ABU_*` 环境变量重复提供
+///
+/// 钩子直接执行而不经过 shell。绝不传递私钥或密码，并从子进程环境中移除 `ABU_PASSWORD`。
+pub fn run_post_generate(hook: &Path, public_key: &Path, fingerprint: &str, bank_name: &str) -> Result<()> {
+    let status = Command::new(hook)
+        .arg(public_key)
+        .arg(fingerprint)
+        .arg(bank_name)
+        .env("ABU_PUBLIC_KEY", public_key)
+        .env("ABU_FINGERPRINT", fingerprint)
+        .env("ABU_BANK_NAME", bank_name)
+        .env_remove("ABU_PASSWORD")
+        .status()
+        .map_err(|e| anyhow!("无法启动生成后钩子 {}: {}", hook.display(), e))?;
+    if !status.success() {
+        return Err(anyhow!("生成后钩子 {} 执行失败（{}）", hook.display(), status));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn hook_receives_public_path_fingerprint_and_bank_name() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = crate::testutil::temp_dir("post-generate-hook");
+        let record = dir.join("record.txt");
+        let hook = dir.join("hook.sh");
+        std::fs::write(
+            &hook,
+            format!(
+                "#!/bin/sh\nprintf '%s\\n' \"$@\" \"$ABU_PUBLIC_KEY\" \"$ABU_FINGERPRINT\" \"$ABU_BANK_NAME\" \"${{ABU_PASSWORD-unset}}\" > '{}'\n",
+                record.display(),
+            ),
+        )
+        .unwrap();
+        std::fs::set_permissions(&hook, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let public_key = dir.join("Test Bank_public.asc");
+        run_post_generate(&hook, &public_key, "0123456789ABCDEF", "Test Bank").unwrap();
+
+        let recorded = std::fs::read_to_string(&record).unwrap();
+        let public = public_key.display().to_string();
+        let args = [public.as_str(), "0123456789ABCDEF", "Test Bank"];
+        let lines: Vec<&str> = recorded.lines().collect();
+        assert_eq!(lines[..3], args);
+        assert_eq!(lines[3..6], args);
+        assert_eq!(lines[6..], ["unset"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn failing_hook_is_an_error() {
+        assert!(run_post_generate(Path::new("/bin/false"), Path::new("public.asc"), "00", "Bank").is_err());
+        assert!(run_post_generate(Path::new("/nonexistent/hook"), Path::new("public.asc"), "00", "Bank").is_err());
+    }
+}
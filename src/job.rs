@@ -0,0 +1,245 @@
+//! 作业文件：用 TOML 描述单个密钥的全部参数，非交互生成，只询问私钥密码
+//!
+//! 作业文件可纳入版本管理并在生成前审阅，适合参数较多的单个密钥；多个简单密钥请使用 CSV 批量模式。
+//!
+//! ```toml
+//! bank_name = "Alpha Bank"
+//! emails = ["ops@alpha.example", "security@alpha.example"]
+//! curve = "p384"
+//! expiry_days = 730
+//! capabilities = ["sign", "encrypt"]
+//! notations = ["role@alpha.example=treasury"]
+//! ```
+
+use crate::config::Config;
+use crate::{batch, commands, pgp, security, userid};
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// 子密钥用途：签名
+const CAPABILITY_SIGN: &str = "sign";
+/// 子密钥用途：加密
+const CAPABILITY_ENCRYPT: &str = "encrypt";
+
+/// 作业文件内容；未出现的字段使用配置中的默认值
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct JobFile {
+    bank_name: String,
+    /// 第一个邮箱组成主 User ID
+    emails: Vec<String>,
+    curve: Option<String>,
+    /// 主密钥算法，省略时使用配置（默认与子密钥相同）
+    primary_curve: Option<String>,
+    /// 0 表示永不过期
+    expiry_days: Option<u32>,
+    /// 子密钥用途：`sign` / `encrypt`，省略时两者都生成
+    capabilities: Option<Vec<String>>,
+    /// `name@domain=value`，追加在配置的注记之后
+    #[serde(default)]
+    notations: Vec<String>,
+    /// 互操作预设（gnupg / protonmail / rnp），省略时使用配置
+    profile: Option<String>,
+    output_dir: Option<PathBuf>,
+}
+
+/// 解析并校验后的作业
+struct Job {
+    bank_name: String,
+    user_ids: userid::UserIdSet,
+    /// 主 User ID 的邮箱，用于文件命名
+    primary_email: String,
+    options: security::KeyOptions,
+    output_dir: PathBuf,
+}
+
+/// 读取作业文件并与配置合并
+fn load(config: &Config, path: &Path) -> Result<Job> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| anyhow!("无法读取作业文件 {}: {}", path.display(), e))?;
+    parse(config, &text).map_err(|e| anyhow!("作业文件 {} 无效: {}", path.display(), e))
+}
+
+/// 解析作业文件内容
+fn parse(config: &Config, text: &str) -> Result<Job> {
+    let file: JobFile = toml::from_str(text)?;
+
+    let bank_name = file.bank_name.trim().to_string();
+    if bank_name.is_empty() {
+        return Err(anyhow!("bank_name 不能为空"));
+    }
+    let primary_email = file.emails.first().ok_or_else(|| anyhow!("emails 至少需要一个邮箱"))?.clone();
+    let mut user_ids = userid::UserIdSet::new()
+        .with_allowed_domains(&config.allowed_email_domains)
+        .with_comment(config.test_key.then_some(pgp::TEST_KEY_COMMENT));
+    for email in &file.emails {
+        user_ids.add(&bank_name, email)?;
+    }
+
+    let curve = match &file.curve {
+        Some(id) => security::KeyCurve::from_id(id)
+            .ok_or_else(|| anyhow!("无效的 curve: {}（可选 p256 / p384 / p521 / cv25519）", id))?,
+        None if config.minimal_cert => security::KeyCurve::Rsa3072,
+        None => config.default_curve,
+    };
+    let primary_curve = match &file.primary_curve {
+        Some(id) => Some(security::KeyCurve::from_primary_id(id).ok_or_else(|| {
+            anyhow!("无效的 primary_curve: {}（可选 p256 / p384 / p521 / cv25519 / rsa3072）", id)
+        })?),
+        None => config.primary_curve,
+    };
+    if config.minimal_cert && primary_curve.is_some() {
+        return Err(anyhow!("最小证书没有子密钥，不能与 primary_curve 一起使用"));
+    }
+    let expiry_days = match file.expiry_days {
+        Some(days) => (days > 0).then_some(days),
+        None => config.key_expiry_days,
+    };
+
+    let (signing_subkey, encryption_subkey) = match &file.capabilities {
+        Some(capabilities) => {
+            if capabilities.is_empty() {
+                return Err(anyhow!("capabilities 至少需要一项（sign / encrypt）"));
+            }
+            for capability in capabilities {
+                if capability != CAPABILITY_SIGN && capability != CAPABILITY_ENCRYPT {
+                    return Err(anyhow!("无效的 capabilities 项: {}（可选 sign / encrypt）", capability));
+                }
+            }
+            (
+                capabilities.iter().any(|c| c == CAPABILITY_SIGN),
+                capabilities.iter().any(|c| c == CAPABILITY_ENCRYPT),
+            )
+        }
+        None => (true, true),
+    };
+    if config.minimal_cert && file.capabilities.is_some() {
+        return Err(anyhow!("最小证书没有子密钥，不能与 capabilities 一起使用"));
+    }
+
+    let profile = match &file.profile {
+        Some(id) => Some(
+            security::Profile::from_id(id)
+                .ok_or_else(|| anyhow!("无效的 profile: {}（可选 gnupg / protonmail / rnp）", id))?,
+        ),
+        None => config.profile,
+    };
+
+    let mut notations = config.notations.clone();
+    for notation in &file.notations {
+        notations.push(pgp::parse_notation(notation)?);
+    }
+
+    let output_dir = file
+        .output_dir
+        .or_else(|| config.output_dir.clone())
+        .unwrap_or_else(|| PathBuf::from("."));
+    crate::config::validate_output_dir(&output_dir)?;
+
+    Ok(Job {
+        bank_name,
+        user_ids,
+        primary_email,
+        options: security::KeyOptions {
+            curve,
+            primary_curve,
+            validity: security::validity_days(expiry_days),
+            notations,
+            profile,
+            minimal: config.minimal_cert,
+            signing_subkey,
+            encryption_subkey,
+            signing_validity: security::validity_days(config.signing_subkey_expiry_days),
+            encryption_validity: security::validity_days(config.encryption_subkey_expiry_days),
+            revocation_keys: commands::load_designated_revokers(config.designated_revoker.as_deref())?,
+        },
+        output_dir,
+    })
+}
+
+/// 执行作业文件：校验全部参数后再读取密码，生成并写出密钥文件
+pub fn run(config: &Config, path: &Path, password_file: Option<&Path>) -> Result<()> {
+    security::check_backend()?;
+    let job = load(config, path)?;
+    batch::warn_clock_skew(config);
+
+    let password = zeroize::Zeroizing::new(commands::read_password(password_file)?);
+    config.password_policy.check(&password).map_err(|e| anyhow!("私钥密码不符合要求: {}", e))?;
+
+    let key_type = security::key_type_name(job.options.primary_curve.unwrap_or(job.options.curve), job.options.curve);
+    eprintln!("正在生成{}密钥对: {}", key_type, job.bank_name);
+    let (fingerprint, outputs) = batch::generate_and_save(
+        config,
+        &job.user_ids,
+        &job.bank_name,
+        &job.primary_email,
+        &job.options,
+        &password,
+        &job.output_dir,
+    )?;
+    eprintln!("指纹: {}", fingerprint);
+    for output in outputs {
+        eprintln!("已保存: {}", output.display());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sequoia_openpgp::crypto::mpi;
+    use sequoia_openpgp::policy::StandardPolicy;
+    use sequoia_openpgp::types::Curve;
+
+    fn sample(output_dir: &Path) -> String {
+        format!(
+            r#"
+bank_name = "Alpha Bank"
+emails = ["ops@alpha.example", "security@alpha.example"]
+curve = "p384"
+expiry_days = 730
+capabilities = ["sign"]
+notations = ["role@alpha.example=treasury"]
+output_dir = '{}'
+"#,
+            output_dir.display()
+        )
+    }
+
+    #[test]
+    fn sample_job_generates_matching_cert() {
+        let dir = crate::testutil::temp_dir("job-sample");
+        let job = parse(&Config::default(), &sample(&dir)).unwrap();
+        assert_eq!(job.bank_name, "Alpha Bank");
+        assert_eq!(job.primary_email, "ops@alpha.example");
+        assert_eq!(job.output_dir, dir);
+
+        let user_ids = job.user_ids.user_ids();
+        let key = security::SecureKey::generate(&user_ids, &job.options).unwrap();
+        let cert = pgp::parse_cert(&key.secret_key_bytes()).unwrap();
+        let policy = StandardPolicy::new();
+        let valid = cert.with_policy(&policy, None).unwrap();
+
+        assert_eq!(pgp::primary_userid(&cert).as_deref(), Some("Alpha Bank <ops@alpha.example>"));
+        assert_eq!(cert.userids().count(), 2);
+        assert!(matches!(
+            cert.primary_key().key().mpis(),
+            mpi::PublicKey::ECDSA { curve: Curve::NistP384, .. }
+        ));
+        assert_eq!(valid.primary_key().key_validity_period(), Some(std::time::Duration::from_secs(730 * 86400)));
+        assert_eq!(valid.keys().subkeys().for_signing().count(), 1);
+        assert_eq!(valid.keys().subkeys().for_transport_encryption().count(), 0);
+        let binding = valid.primary_userid().unwrap().binding_signature();
+        let notation: Vec<&[u8]> = binding.notation("role@alpha.example").collect();
+        assert_eq!(notation, vec![&b"treasury"[..]]);
+    }
+
+    #[test]
+    fn unknown_capability_is_rejected() {
+        let dir = crate::testutil::temp_dir("job-capability");
+        let text = sample(&dir).replace(r#"["sign"]"#, r#"["sign", "certify"]"#);
+        let error = parse(&Config::default(), &text).err().unwrap();
+        assert!(error.to_string().contains("certify"), "{}", error);
+    }
+}
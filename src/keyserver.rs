@@ -0,0 +1,179 @@
+//! 上传公钥到 HKP 密钥服务器（可选功能 `keyserver`）
+//!
+//! 按 HKP 协议以 `POST <keyserver_url>/pks/add` 提交表单 `keytext=<ASCII 装甲公钥>`。
+//! 只上传公开证书；上传失败不影响已保存的本地文件。
+
+use anyhow::{anyhow, Result};
+
+/// HKP 提交公钥的路径
+pub const HKP_ADD_PATH: &str = "/pks/add";
+
+/// 服务器响应的最大字节数
+#[cfg(feature = "keyserver")]
+const MAX_RESPONSE_SIZE: u64 = 64 * 1024;
+
+/// 显示给用户的服务器响应的最大字符数
+const MAX_MESSAGE_CHARS: usize = 500;
+
+/// 密钥服务器的响应
+pub struct UploadResponse {
+    pub status: u16,
+    /// 响应正文（截断到 [`MAX_MESSAGE_CHARS`] 个字符）
+    pub message: String,
+}
+
+/// 将 ASCII 装甲的公钥上传到密钥服务器；非 2xx 状态视为失败
+pub fn upload(url: &str, armored_public_key: &str) -> Result<UploadResponse> {
+    let endpoint = format!("{}{}", url.trim_end_matches('/'), HKP_ADD_PATH);
+    let body = format!("keytext={}", form_urlencode(armored_public_key));
+    let (status, text) = post(&endpoint, &body)?;
+    let message: String = text.trim().chars().take(MAX_MESSAGE_CHARS).collect();
+    if !(200..300).contains(&status) {
+        return Err(anyhow!("密钥服务器拒绝了上传（HTTP {}）: {}", status, message));
+    }
+    Ok(UploadResponse { status, message })
+}
+
+/// 提交表单并返回状态码与响应正文
+#[cfg(feature = "keyserver")]
+fn post(endpoint: &str, body: &str) -> Result<(u16, String)> {
+    use std::io::Read;
+
+    let response = match ureq::post(endpoint)
+        .set("Content-Type", "application/x-www-form-urlencoded")
+        .timeout(std::time::Duration::from_secs(30))
+        .send_string(body)
+    {
+        Ok(response) | Err(ureq::Error::Status(_, response)) => response,
+        Err(e) => return Err(anyhow!("无法连接密钥服务器 {}: {}", endpoint, e)),
+    };
+    let status = response.status();
+    let mut text = String::new();
+    response
+        .into_reader()
+        .take(MAX_RESPONSE_SIZE)
+        .read_to_string(&mut text)
+        .map_err(|e| anyhow!("读取密钥服务器响应失败: {}", e))?;
+    Ok((status, text))
+}
+
+/// 提交表单（未启用 `keyserver` 的构建）
+#[cfg(not(feature = "keyserver"))]
+fn post(_endpoint: &str, _body: &str) -> Result<(u16, String)> {
+    Err(anyhow!("此构建未启用密钥服务器上传支持（keyserver）"))
+}
+
+/// `application/x-www-form-urlencoded` 编码：保留非保留字符，空格为 `+`，其余按字节转为 `%XX`
+fn form_urlencode(text: &str) -> String {
+    let mut encoded = String::with_capacity(text.len() * 3);
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => encoded.push(byte as char),
+            b' ' => encoded.push('+'),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 表单解码（测试用）：`+` 为空格，`%XX` 为字节
+    fn form_urldecode(text: &str) -> String {
+        let bytes = text.as_bytes();
+        let mut decoded = Vec::new();
+        let mut index = 0;
+        while index < bytes.len() {
+            match bytes[index] {
+                b'+' => decoded.push(b' '),
+                b'%' => {
+                    decoded.push(u8::from_str_radix(&text[index + 1..index + 3], 16).unwrap());
+                    index += 2;
+                }
+                byte => decoded.push(byte),
+            }
+            index += 1;
+        }
+        String::from_utf8(decoded).unwrap()
+    }
+
+    #[test]
+    fn armored_key_is_form_encoded() {
+        let armored = "-----BEGIN PGP PUBLIC KEY BLOCK-----\n\nmDMEZ+/a=\n=AbCd\n-----END PGP PUBLIC KEY BLOCK-----\n";
+        let encoded = form_urlencode(armored);
+        assert!(encoded.starts_with("-----BEGIN+PGP+PUBLIC+KEY+BLOCK-----%0A%0AmDMEZ%2B%2Fa%3D%0A"), "{}", encoded);
+        assert!(!encoded.contains(['\n', '/', '=', ' ']));
+        assert_eq!(form_urldecode(&encoded), armored);
+    }
+
+    /// 模拟服务器收到的请求行、Content-Type 与请求体
+    #[cfg(feature = "keyserver")]
+    type Received = (String, String, String);
+
+    /// 只应答一次请求的模拟密钥服务器
+    #[cfg(feature = "keyserver")]
+    fn mock_keyserver(status: &'static str, reply: &'static str) -> (String, std::thread::JoinHandle<Received>) {
+        use std::io::{BufRead, Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = std::io::BufReader::new(stream.try_clone().unwrap());
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let (mut length, mut content_type) = (0, String::new());
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line == "\r\n" {
+                    break;
+                }
+                let lower = line.to_ascii_lowercase();
+                if let Some(value) = lower.strip_prefix("content-length:") {
+                    length = value.trim().parse().unwrap();
+                } else if let Some(value) = lower.strip_prefix("content-type:") {
+                    content_type = value.trim().to_string();
+                }
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            let header = format!("HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", status, reply.len());
+            stream.write_all(header.as_bytes()).unwrap();
+            stream.write_all(reply.as_bytes()).unwrap();
+            (request_line, content_type, String::from_utf8(body).unwrap())
+        });
+        (url, server)
+    }
+
+    #[cfg(feature = "keyserver")]
+    #[test]
+    fn armored_key_is_posted_to_hkp_add() {
+        let armored = "-----BEGIN PGP PUBLIC KEY BLOCK-----\n\nmDMEZ+/a=\n-----END PGP PUBLIC KEY BLOCK-----\n";
+        let (url, server) = mock_keyserver("200 OK", "Key block added");
+
+        let response = upload(&url, armored).unwrap();
+        let (request_line, content_type, body) = server.join().unwrap();
+
+        assert_eq!(request_line.trim_end(), format!("POST {} HTTP/1.1", HKP_ADD_PATH));
+        assert_eq!(content_type, "application/x-www-form-urlencoded");
+        let keytext = body.strip_prefix("keytext=").expect("keytext field");
+        assert_eq!(form_urldecode(keytext), armored);
+        assert_eq!(response.status, 200);
+        assert_eq!(response.message, "Key block added");
+    }
+
+    #[cfg(feature = "keyserver")]
+    #[test]
+    fn rejected_upload_reports_status_and_message() {
+        let (url, server) = mock_keyserver("422 Unprocessable Entity", "Invalid key");
+
+        let error = upload(&url, "not a key").err().unwrap();
+        server.join().unwrap();
+
+        assert!(error.to_string().contains("422"), "{}", error);
+        assert!(error.to_string().contains("Invalid key"), "{}", error);
+    }
+}
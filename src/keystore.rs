@@ -0,0 +1,104 @@
+//! 在操作系统钥匙串中保存私钥密码（可选功能 `os-keyring`）
+//!
+//! 条目以服务名 `abu-keygentool` 与主密钥指纹为键。钥匙串由操作系统登录会话保护：
+//! 任何能以当前用户身份运行程序的人都可能读取这些密码，共享或不受信任的电脑上不要启用。
+
+use zeroize::Zeroizing;
+
+/// 钥匙串条目的服务名
+#[cfg(feature = "os-keyring")]
+const SERVICE: &str = "abu-keygentool";
+
+/// 是否编译了钥匙串支持
+pub fn is_enabled() -> bool {
+    cfg!(feature = "os-keyring")
+}
+
+/// 保存某个指纹对应的私钥密码
+#[cfg(feature = "os-keyring")]
+pub fn store(fingerprint: &str, password: &str) -> anyhow::Result<()> {
+    keyring::Entry::new(SERVICE, fingerprint)
+        .and_then(|entry| entry.set_password(password))
+        .map_err(|e| anyhow::anyhow!("无法写入系统钥匙串: {}", e))
+}
+
+/// 保存某个指纹对应的私钥密码（未启用 `os-keyring` 的构建）
+#[cfg(not(feature = "os-keyring"))]
+pub fn store(_fingerprint: &str, _password: &str) -> anyhow::Result<()> {
+    Err(anyhow::anyhow!("此构建未启用系统钥匙串支持（os-keyring）"))
+}
+
+/// 读取某个指纹对应的私钥密码；没有条目或钥匙串不可用时返回 None，由调用方回退到手动输入
+#[cfg(feature = "os-keyring")]
+pub fn load(fingerprint: &str) -> Option<Zeroizing<String>> {
+    keyring::Entry::new(SERVICE, fingerprint)
+        .and_then(|entry| entry.get_password())
+        .ok()
+        .map(Zeroizing::new)
+}
+
+/// 读取某个指纹对应的私钥密码（未启用 `os-keyring` 的构建，总是 None）
+#[cfg(not(feature = "os-keyring"))]
+pub fn load(_fingerprint: &str) -> Option<Zeroizing<String>> {
+    None
+}
+
+/// 按指纹读取密码的钥匙串；测试中以内存实现代替操作系统钥匙串
+pub trait Keyring {
+    fn load(&self, fingerprint: &str) -> Option<Zeroizing<String>>;
+}
+
+/// 操作系统钥匙串（见 [`load`]）
+pub struct OsKeyring;
+
+impl Keyring for OsKeyring {
+    fn load(&self, fingerprint: &str) -> Option<Zeroizing<String>> {
+        load(fingerprint)
+    }
+}
+
+/// 在候选指纹中查找保存了密码、且该密码能解密加密私钥 `data` 的条目，返回该密码
+///
+/// 加密私钥文件不含指纹，候选指纹由调用方从私钥旁的公钥证书取得；以能否解密确认对应关系。
+pub fn find_password(keyring: &impl Keyring, data: &[u8], fingerprints: &[String]) -> Option<Zeroizing<String>> {
+    fingerprints
+        .iter()
+        .filter_map(|fingerprint| keyring.load(fingerprint))
+        .find(|password| crate::encryption::decrypt_with_password(data, password).is_ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encryption::{self, AeadCipher};
+    use crate::security::{KdfHash, KdfParams};
+    use std::collections::HashMap;
+
+    /// 内存中的钥匙串
+    struct MockKeyring(HashMap<String, String>);
+
+    impl Keyring for MockKeyring {
+        fn load(&self, fingerprint: &str) -> Option<Zeroizing<String>> {
+            self.0.get(fingerprint).cloned().map(Zeroizing::new)
+        }
+    }
+
+    #[test]
+    fn stored_password_is_found_by_fingerprint() {
+        let kdf = KdfParams { hash: KdfHash::Sha256, iterations: crate::config::MIN_ITERATIONS };
+        let data =
+            encryption::encrypt_with_password(b"secret key bytes", "right-password", &kdf, AeadCipher::Aes256Gcm).unwrap();
+        let keyring = MockKeyring(HashMap::from([
+            ("AAAA".to_string(), "other-password".to_string()),
+            ("BBBB".to_string(), "right-password".to_string()),
+        ]));
+
+        let found = find_password(&keyring, &data, &["AAAA".to_string(), "BBBB".to_string()]).unwrap();
+        assert_eq!(found.as_str(), "right-password");
+
+        // 没有条目或条目中的密码无法解密时回退到手动输入
+        assert!(find_password(&keyring, &data, &["AAAA".to_string()]).is_none());
+        assert!(find_password(&keyring, &data, &["CCCC".to_string()]).is_none());
+        assert!(find_password(&keyring, &data, &[]).is_none());
+    }
+}
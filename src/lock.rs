@@ -0,0 +1,128 @@
+use anyhow::{anyhow, Result};
+use fs2::FileExt;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// 密钥文件的建议性独占锁，防止两个实例同时读改写同一文件
+///
+/// 锁加在旁边的 `<文件>.lock` 上而不是密钥文件本身：Windows 上的文件锁是强制性的，
+/// 锁住密钥文件会使本进程随后的写入也失败。锁对象释放时删除锁文件并解锁。
+pub struct FileLock {
+    file: File,
+    path: PathBuf,
+}
+
+impl FileLock {
+    /// 尝试获取 `target` 的锁；已被其他进程持有时立即返回错误，不等待
+    pub fn acquire(target: &Path) -> Result<Self> {
+        let path = sibling_path(target, "lock");
+        loop {
+            let file = OpenOptions::new()
+                .create(true)
+                .truncate(false)
+                .write(true)
+                .open(&path)
+                .map_err(|e| anyhow!("无法创建锁文件 {}: {}", path.display(), e))?;
+            match file.try_lock_exclusive() {
+                Ok(()) => {}
+                Err(e) if e.raw_os_error() == fs2::lock_contended_error().raw_os_error() => {
+                    return Err(anyhow!("文件正被另一个进程使用: {}", target.display()));
+                }
+                Err(e) => return Err(anyhow!("无法锁定 {}: {}", path.display(), e)),
+            }
+            // 上一个持有者释放时会删除锁文件；锁住的若是已被删除的旧文件，重新打开新的锁文件
+            if is_same_file(&file, &path) {
+                return Ok(Self { file, path });
+            }
+        }
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        // 先删除再解锁，其他进程不会在解锁后锁住一个即将被删除的文件
+        if let Err(e) = std::fs::remove_file(&self.path) {
+            eprintln!("删除锁文件 {} 失败: {}", self.path.display(), e);
+        }
+        if let Err(e) = FileExt::unlock(&self.file) {
+            eprintln!("释放锁 {} 失败: {}", self.path.display(), e);
+        }
+    }
+}
+
+/// 原子地写入文件：先写入同目录的 `<文件>.tmp` 并落盘，再重命名覆盖目标
+///
+/// 写入中途出错或进程被终止时，原文件保持完整，不会留下被截断的密钥文件。
+pub fn write_atomic(path: &Path, data: &[u8]) -> Result<()> {
+    let temp = sibling_path(path, "tmp");
+    let written = File::create(&temp)
+        .and_then(|mut file| {
+            file.write_all(data)?;
+            file.sync_all()
+        })
+        .and_then(|()| std::fs::rename(&temp, path));
+    if let Err(e) = written {
+        let _ = std::fs::remove_file(&temp);
+        return Err(anyhow!("无法写入 {}: {}", path.display(), e));
+    }
+    Ok(())
+}
+
+/// `<文件>.<后缀>`，与原文件位于同一目录
+fn sibling_path(target: &Path, suffix: &str) -> PathBuf {
+    let mut path = target.as_os_str().to_owned();
+    path.push(format!(".{}", suffix));
+    PathBuf::from(path)
+}
+
+/// 已打开的文件是否仍是 `path` 指向的文件
+#[cfg(unix)]
+fn is_same_file(file: &File, path: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    match (file.metadata(), std::fs::metadata(path)) {
+        (Ok(open), Ok(current)) => open.dev() == current.dev() && open.ino() == current.ino(),
+        _ => false,
+    }
+}
+
+/// 已打开的文件是否仍是 `path` 指向的文件（Windows 上持有者删除锁文件前会一直占用它，只需检查仍然存在）
+#[cfg(not(unix))]
+fn is_same_file(_file: &File, path: &Path) -> bool {
+    path.exists()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn second_acquire_fails_while_first_is_held() {
+        let dir = crate::testutil::temp_dir("lock-contention");
+        let key = dir.join("bank_private.bin");
+        std::fs::write(&key, b"key").unwrap();
+
+        let first = FileLock::acquire(&key).unwrap();
+        let error = FileLock::acquire(&key).err().unwrap();
+        assert!(error.to_string().contains("文件正被另一个进程使用"), "{}", error);
+        assert!(dir.join("bank_private.bin.lock").is_file());
+
+        drop(first);
+        assert!(!dir.join("bank_private.bin.lock").exists());
+        let again = FileLock::acquire(&key).unwrap();
+        drop(again);
+    }
+
+    #[test]
+    fn atomic_write_replaces_file_without_leftovers() {
+        let dir = crate::testutil::temp_dir("lock-atomic-write");
+        let key = dir.join("bank_private.bin");
+        std::fs::write(&key, b"old contents").unwrap();
+
+        write_atomic(&key, b"new").unwrap();
+
+        assert_eq!(std::fs::read(&key).unwrap(), b"new");
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+    }
+}
@@ -0,0 +1,31 @@
+//! 诊断日志（`tracing`）
+//!
+//! 过滤规则优先取 `--log-level`，其次取 `RUST_LOG`，都未设置时只输出 warn 及以上级别，
+//! 默认不会在 stderr 上产生调试输出。日志写到 stderr，不影响 `sign` 等命令的 stdout 输出。
+//! 记录日志时只能使用指纹、数量、算法等公开信息，不得记录密码、私钥或解密后的内容。
+
+use anyhow::{anyhow, Result};
+use tracing_subscriber::EnvFilter;
+
+/// 未指定任何过滤规则时的默认级别
+const DEFAULT_FILTER: &str = "warn";
+
+/// 校验过滤规则（如 `debug`、`abu_keygentool=trace`）
+pub fn validate_filter(filter: &str) -> Result<()> {
+    EnvFilter::try_new(filter).map_err(|e| anyhow!("无效的 --log-level 值 {}: {}", filter, e))?;
+    Ok(())
+}
+
+/// 安装全局日志订阅者；`level` 为 --log-level 的值
+pub fn init(level: Option<&str>) -> Result<()> {
+    let filter = match level {
+        Some(level) => EnvFilter::try_new(level)?,
+        None => EnvFilter::try_from_default_env().or_else(|_| EnvFilter::try_new(DEFAULT_FILTER))?,
+    };
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr)
+        .with_target(false)
+        .try_init()
+        .map_err(|e| anyhow!("初始化日志失败: {}", e))
+}
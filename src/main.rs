@@ -2,144 +2,1599 @@ mod security;
 mod pgp;
 mod ui;
 mod encryption;
+mod config;
+mod cli;
+mod backup;
+mod userid;
+mod commands;
+mod error;
+mod seed;
+mod timing;
+mod diagnose;
+mod timestamp;
+mod state;
+mod batch;
+mod hook;
+mod naming;
+mod keystore;
+mod tsa;
+mod keyserver;
+mod clock;
+mod lock;
+mod job;
+mod manifest;
+mod password;
+mod revqr;
+mod logging;
+mod wipe;
+#[cfg(feature = "age")]
+mod agefile;
+#[cfg(feature = "card")]
+mod card;
+#[cfg(feature = "recovery-sheet")]
+mod sheet;
+#[cfg(feature = "remote-keygen")]
+mod remote;
+#[cfg(test)]
+mod testutil;
 
 use anyhow::Result;
 use std::fs;
-use chrono::Local;
-use rand::RngCore;
 
-#[derive(serde::Serialize)]
+#[derive(serde::Serialize, serde::Deserialize)]
 struct KeyMetadata {
     bank_name: String,
+    /// 主 User ID；匿名证书为 "anonymous"
+    user_id: String,
     generation_date: String,
     key_type: String,
     key_size: u32,
+    /// 主密钥与各子密钥允许的用途
+    key_usage: Vec<pgp::KeyUsage>,
     abu_version: String,
     notes: String,
+    /// 测试密钥（--test-key），不得用于生产
+    test_key: bool,
+    /// 私钥加密强度：interactive / sensitive / paranoid，手动设置为 custom，沿用配置为 configured
+    kdf_level: String,
+    /// 指定吊销人的主密钥指纹（--designated-revoker），未指定时为 null
+    designated_revoker: Option<String>,
+    /// 互操作预设（--profile：gnupg / protonmail / rnp），未指定时为 null
+    profile: Option<String>,
+}
+
+/// [`KeyMetadata`] 的全部字段名，查看元数据时用于标出未知字段与缺失字段；增删字段时需同步
+const KEY_METADATA_FIELDS: [&str; 12] = [
+    "bank_name",
+    "user_id",
+    "generation_date",
+    "key_type",
+    "key_size",
+    "key_usage",
+    "abu_version",
+    "notes",
+    "test_key",
+    "kdf_level",
+    "designated_revoker",
+    "profile",
+];
+
+/// 按 [`KeyMetadata`] 的格式检查元数据 JSON，返回发现的问题（未知字段、缺失字段、类型错误）
+fn metadata_problems(value: &serde_json::Value) -> Vec<String> {
+    let Some(object) = value.as_object() else {
+        return vec!["元数据不是 JSON 对象".to_string()];
+    };
+    let mut problems: Vec<String> = object
+        .keys()
+        .filter(|key| !KEY_METADATA_FIELDS.contains(&key.as_str()))
+        .map(|key| format!("未知字段: {}", key))
+        .collect();
+    let missing: Vec<String> = KEY_METADATA_FIELDS
+        .iter()
+        .filter(|field| !object.contains_key(**field))
+        .map(|field| format!("缺少字段: {}（可能由旧版本生成）", field))
+        .collect();
+    // 字段齐全时再检查各字段的类型
+    if missing.is_empty() {
+        let invalid = serde_json::from_value::<KeyMetadata>(value.clone()).err();
+        problems.extend(invalid.map(|e| format!("字段类型不符: {}", e)));
+    }
+    problems.extend(missing);
+    problems
+}
+
+/// 生成密钥后、写入文件前供用户核对的内容
+struct GenerationPlan<'a> {
+    bank_name: &'a str,
+    user_ids: &'a [String],
+    key_type: &'a str,
+    expiry_days: Option<u32>,
+    outputs: &'a [&'a std::path::Path],
+}
+
+/// 规范 JSON：所有层级的键按字典序排列，同一逻辑内容总是得到相同的字节
+///
+/// 先转换为 `serde_json::Value`，其对象在未启用 `preserve_order` 时以 BTreeMap 存储，键自然有序。
+fn canonical_json<T: serde::Serialize>(value: &T) -> Result<String> {
+    Ok(serde_json::to_string_pretty(&serde_json::to_value(value)?)?)
+}
+
+/// 用密码加密的任意文件的扩展名
+const ENCRYPTED_FILE_EXTENSION: &str = "abuenc";
+
+/// age 加密文件的扩展名
+const AGE_EXTENSION: &str = "age";
+
+/// 导出私钥原文前需要输入的确认短语
+const PLAINTEXT_EXPORT_PHRASE: &str = "I UNDERSTAND";
+
+/// 紧急销毁前需要输入的确认短语
+const PANIC_WIPE_PHRASE: &str = "WIPE MY KEYS";
+
+/// 元数据中表示沿用配置（--iterations / --kdf-hash）的加密强度名称
+const KDF_LEVEL_CONFIGURED: &str = "configured";
+
+/// 元数据中表示手动设置的加密强度名称
+const KDF_LEVEL_CUSTOM: &str = "custom";
+
+/// 推荐加密强度时允许的最长解锁耗时
+const KDF_RECOMMEND_TARGET: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// 密码恢复模式中两次尝试之间的延迟（毫秒）
+const RECOVERY_ATTEMPT_DELAY_MS: u64 = 500;
+
+/// 本次会话中上一次未完成生成时输入的回答，重试时作为默认值
+#[derive(Clone, Default)]
+struct GenerationAnswers {
+    bank_name: Option<String>,
+    email: Option<String>,
+    curve: Option<security::KeyCurve>,
 }
 
 pub struct KeyGenerator {
     ui: ui::UserInterface,
+    /// 生成失败或取消后保留的回答；文件全部写出后清空
+    remembered: std::cell::RefCell<GenerationAnswers>,
+    /// 启动时检测到的加密后端；检测失败时为 None，此时禁用密钥生成
+    backend: Option<security::BackendInfo>,
+    /// 启动时检测到的系统时钟偏差（秒）；未配置时间源或偏差在阈值内时为 None
+    clock_skew: std::cell::Cell<Option<i64>>,
+    /// 启动时探测的曲线支持情况；未检测到后端时不探测
+    curve_support: security::CurveSupport,
+    config: config::Config,
 }
 
 impl KeyGenerator {
-    pub fn new() -> Self {
+    pub fn new(backend: Option<security::BackendInfo>, config: config::Config) -> Self {
+        Self::with_ui(ui::UserInterface::new(), backend, config)
+    }
+
+    /// 使用给定的界面（如脚本应答界面）创建，并按配置设置界面选项
+    pub fn with_ui(ui: ui::UserInterface, backend: Option<security::BackendInfo>, config: config::Config) -> Self {
         Self {
-            ui: ui::UserInterface::new(),
+            ui: ui
+                .with_output_dir(config.output_dir.clone())
+                .with_password_policy(config.password_policy.clone())
+                .with_vcs_markers(config.vcs_markers.clone())
+                .with_clear_mode(config.clear_screen)
+                .with_language(config.language),
+            remembered: std::cell::RefCell::default(),
+            curve_support: if backend.is_some() { security::CurveSupport::probe() } else { security::CurveSupport::default() },
+            backend,
+            clock_skew: std::cell::Cell::new(None),
+            config,
         }
     }
 
+    /// 保存目录中已有主密钥指纹相同的证书时警告，并询问是否仍要写入
+    fn check_duplicate_fingerprint(&self, save_path: &std::path::Path, fingerprint: &sequoia_openpgp::Fingerprint) -> Result<()> {
+        let dir = save_path.parent().unwrap_or(std::path::Path::new(""));
+        let Some(existing) = commands::find_duplicate_fingerprint(dir, fingerprint)? else {
+            return Ok(());
+        };
+        println!(
+            "{} {}",
+            ui::style("⚠").yellow().bold(),
+            ui::style(format!("目录中已存在指纹相同的证书: {}（可能重复生成了同一密钥）", existing.display())).yellow().bold(),
+        );
+        if !self.ui.confirm("仍要继续保存吗？", false)? {
+            return Err(anyhow::anyhow!("已取消：目录中已存在指纹相同的证书"));
+        }
+        Ok(())
+    }
+
+    /// 按配置（--utc）捕获当前时刻
+    fn now(&self) -> timestamp::Timestamp {
+        timestamp::Timestamp::now(self.config.utc)
+    }
+
     /// 解密并导出私钥文件的交互流程
     fn decrypt_private_key_flow(&self) -> Result<()> {
         // 选择要解密的加密私钥文件
         let path = self.ui.select_open_location()?;
-        let data = std::fs::read(&path)?;
+        // 解密期间持有锁，避免读到另一个实例正在改写的文件
+        let _lock = lock::FileLock::acquire(&path)?;
+        let data = commands::read_key_file(&path)?;
+
+        // 可选：从系统钥匙串读取密码（以私钥所在目录中公钥证书的指纹为键），找不到时回退到手动输入
+        let stored = if keystore::is_enabled() && self.ui.confirm("是否从系统钥匙串读取该私钥的密码？", false)? {
+            let dir = path.parent().unwrap_or(std::path::Path::new(""));
+            let fingerprints: Vec<String> = commands::cert_fingerprints_in(dir)?
+                .into_iter()
+                .map(|(_, fingerprint)| fingerprint.to_hex())
+                .collect();
+            let stored = keystore::find_password(&keystore::OsKeyring, &data, &fingerprints);
+            if stored.is_none() {
+                println!("钥匙串中没有能解密该私钥的密码（或钥匙串不可用、私钥旁没有对应的公钥），请手动输入。");
+            }
+            stored
+        } else {
+            None
+        };
 
-        if data.len() < 28 {
-            return Err(anyhow::anyhow!("文件太短，无法包含 salt/nonce/密文"));
+        // 输入密码；Ctrl-C 只取消本次操作，返回主菜单
+        let password = match stored {
+            Some(password) => password,
+            None => match self.ui.input_password("请输入用于解密私钥的密码（输入时不可见）", false) {
+                Err(e) if error::is_cancelled(&e) => {
+                    println!("已取消解密，返回主菜单。");
+                    return Ok(());
+                }
+                result => zeroize::Zeroizing::new(result?),
+            },
+        };
+
+        let plaintext = zeroize::Zeroizing::new(encryption::decrypt_with_password(&data, &password)?);
+        let cert = commands::parse_decrypted_tsk(&plaintext)?;
+
+        // 可选：使用同一密码查看加密的元数据（.json.enc）
+        if self.ui.confirm("是否查看对应的加密元数据 (.json.enc)？", false)?
+            && let Err(e) = self.reveal_encrypted_metadata(&password)
+        {
+            self.ui.show_error(&format!("无法查看加密元数据: {}", e));
         }
 
-        // 读取 salt(16) + nonce(12) + ciphertext
-        let salt = &data[0..16];
-        let nonce = &data[16..28];
-        let ciphertext = &data[28..];
+        // 优先提供口令保护的导出（GnuPG 可直接导入），明文导出作为后备
+        if self.ui.confirm("是否导出为口令保护的 OpenPGP 私钥（可直接导入 GnuPG，推荐）？", true)? {
+            return self.export_protected_private_key(cert);
+        }
+        #[cfg(feature = "age")]
+        if self.ui.confirm("是否导出为 age 加密文件（可用 age 命令行解密）？", false)? {
+            return self.export_age_private_key(&cert);
+        }
+        self.export_plaintext_private_key(&cert)
+    }
 
-        // 输入密码
-        let password = self.ui.input_password("请输入用于解密私钥的密码（输入时不可见）", false)?;
+    /// 以 OpenPGP 原生 S2K 口令保护重新加密 TSK 并保存
+    fn export_protected_private_key(&self, cert: sequoia_openpgp::Cert) -> Result<()> {
+        let passphrase = zeroize::Zeroizing::new(
+            self.ui.input_password("请设置导出私钥的口令（导入 GnuPG 时需要，输入时不可见）", true)?,
+        );
+        if passphrase.is_empty() {
+            return Err(anyhow::anyhow!("导出口令不能为空"));
+        }
+        let protected = pgp::protect_secrets(cert, &passphrase)?;
+
+        let armor = self.config.armor_exports();
+        let default_name = format!(
+            "protected_private_{}.{}",
+            self.now().file_stamp(),
+            pgp::cert_extension(armor),
+        );
+        let save_path = self.ui.select_save_location(&default_name)?;
+        fs::write(&save_path, pgp::export_tsk(&protected, armor, &self.config.armor)?)?;
+        self.ui.show_success(&format!("口令保护的私钥已保存到: {}", save_path.display()));
+
+        Ok(())
+    }
 
-        // 派生密钥并解密
-        let key = security::SecureKey::derive_encryption_key(&password, salt)?;
-        let mut nonce_arr = [0u8; 12];
-        nonce_arr.copy_from_slice(nonce);
+    /// 将解密后的私钥（ASCII 装甲 TSK）以 age 格式加密导出，可选口令或 age 接收者公钥
+    #[cfg(feature = "age")]
+    fn export_age_private_key(&self, cert: &sequoia_openpgp::Cert) -> Result<()> {
+        let tsk = zeroize::Zeroizing::new(pgp::armor_tsk(cert)?);
+        let modes = vec!["口令（age -d 时输入口令）".to_string(), "age 接收者公钥（age1...）".to_string()];
+        let encrypted = if self.ui.select_index("选择 age 加密方式", &modes)? == 0 {
+            let passphrase = zeroize::Zeroizing::new(
+                self.ui.input_password("请设置 age 口令（输入时不可见）", true)?,
+            );
+            agefile::encrypt_with_passphrase(&tsk, &passphrase)?
+        } else {
+            let recipient = self.ui.input_validated("请输入 age 接收者公钥", "", |value| {
+                agefile::parse_recipient(value).map(|_| ())
+            })?;
+            agefile::encrypt_to_recipient(&tsk, &recipient)?
+        };
+
+        let default_name = format!("private_{}.asc.{}", self.now().file_stamp(), AGE_EXTENSION);
+        let save_path = self.ui.select_save_location(&default_name)?;
+        fs::write(&save_path, encrypted)?;
+        self.ui.show_success(&format!(
+            "age 加密的私钥已保存到: {}\n解密: age -d -o private.asc {}",
+            save_path.display(),
+            save_path.display(),
+        ));
 
-        let plaintext = encryption::aes_gcm_decrypt(ciphertext, &key, &nonce_arr)?;
+        Ok(())
+    }
 
-        // 警告并询问是否保存明文私钥
+    /// 警告并询问是否以 ASCII 装甲保存解密后的私钥原文
+    fn export_plaintext_private_key(&self, cert: &sequoia_openpgp::Cert) -> Result<()> {
         println!("警告：即将导出私钥原文，可能导致密钥泄露！");
-        if dialoguer::Confirm::new()
-            .with_prompt("确认导出私钥原文并以 ASCII 装甲保存？")
-            .default(false)
-            .interact()? {
-            let default_name = format!("decrypted_private_{}.asc", Local::now().format("%Y%m%d_%H%M%S"));
-            let save_path = self.ui.select_save_location(&default_name)?;
-            let armored = pgp::add_ascii_armor(&plaintext, sequoia_openpgp::armor::Kind::SecretKey)?;
-            std::fs::write(save_path, armored)?;
-            println!("私钥已保存（明文装甲）。请尽快安全删除该文件。");
+        if !self.ui.confirm("确认导出私钥原文并以 ASCII 装甲保存？", false)? {
+            return Ok(());
+        }
+        if !self.ui.confirm_dangerous(PLAINTEXT_EXPORT_PHRASE)? {
+            println!("确认短语不匹配，已取消导出。");
+            return Ok(());
         }
 
-        Ok(())
+        // 按装甲开关重新序列化 TSK，避免重复装甲
+        let armor = self.config.armor_exports();
+        let default_name = format!(
+            "decrypted_private_{}.{}",
+            self.now().file_stamp(),
+            pgp::cert_extension(armor),
+        );
+        let save_path = self.ui.select_save_location(&default_name)?;
+        std::fs::write(save_path, pgp::export_tsk(cert, armor, &self.config.armor)?)?;
+        println!("私钥已保存（明文）。请尽快安全删除该文件。");
+
+        self.ui.clear_sensitive()
+    }
+
+    /// 托管恢复：使用恢复证书的私钥（而非用户密码）解密加密私钥
+    fn recovery_decrypt_flow(&self) -> Result<()> {
+        let path = self.ui.select_key_file("选择要恢复的加密私钥文件 (.bin)", ui::FileFilter::EncryptedKey)?;
+        let data = commands::read_key_file(&path)?;
+
+        let recovery_path = self.ui.select_key_file("选择恢复证书的私钥文件 (.asc / .bin)", ui::FileFilter::Cert)?;
+        let recovery_tsk = self.load_cert(&recovery_path)?;
+        if !recovery_tsk.is_tsk() {
+            return Err(anyhow::anyhow!("所选恢复证书不包含私钥"));
+        }
+
+        let plaintext = zeroize::Zeroizing::new(encryption::decrypt_with_recovery_key(&data, &recovery_tsk)?);
+        let cert = commands::parse_decrypted_tsk(&plaintext)?;
+        self.ui.show_success("已使用恢复密钥解密私钥");
+
+        self.export_plaintext_private_key(&cert)
     }
     
+    /// 选择并解密 `.json.enc` 元数据文件，打印其内容
+    fn reveal_encrypted_metadata(&self, password: &str) -> Result<()> {
+        let path = self.ui.select_open_file("选择加密元数据文件 (.json.enc)")?;
+        let data = commands::read_key_file(&path)?;
+        let json = encryption::decrypt_with_password(&data, password)?;
+        let json = String::from_utf8(json).map_err(|_| anyhow::anyhow!("元数据不是有效的 UTF-8 文本"))?;
+
+        println!();
+        println!("{}", ui::style("元数据:").bold());
+        println!("{}", json);
+        println!();
+        self.ui.clear_sensitive()
+    }
+
+    /// 读取密钥文件中的证书：证书文件直接解析，加密私钥需输入密码后在内存中解密
+    fn load_cert(&self, path: &std::path::Path) -> Result<sequoia_openpgp::Cert> {
+        let data = commands::read_key_file(path)?;
+        if let Ok(cert) = pgp::parse_cert(&data) {
+            return Ok(cert);
+        }
+
+        println!("{} 为加密私钥文件，需要密码", path.display());
+        let password = self.ui.input_password("请输入该私钥的密码（输入时不可见）", false)?;
+        let plaintext = zeroize::Zeroizing::new(encryption::decrypt_with_password(&data, &password)?);
+        commands::parse_decrypted_tsk(&plaintext)
+    }
+
+    /// 读取密钥文件的主密钥指纹
+    fn load_fingerprint(&self, path: &std::path::Path) -> Result<sequoia_openpgp::Fingerprint> {
+        Ok(self.load_cert(path)?.fingerprint())
+    }
+
+    /// 诊断可能被截断的密钥文件（仅报告问题位置，不修复数据）
+    fn diagnose_file_flow(&self) -> Result<()> {
+        let path = self.ui.select_key_file("选择要诊断的密钥文件 (.asc / .bin)", ui::FileFilter::Cert)?;
+        let data = commands::read_key_file(&path)?;
+        let diagnosis = diagnose::diagnose(&data);
+
+        println!();
+        println!("文件: {}（{} 字节）", path.display(), data.len());
+        println!("类型: {}", diagnosis.kind);
+        for note in &diagnosis.notes {
+            println!("  · {}", note);
+        }
+        if diagnosis.problems.is_empty() {
+            self.ui.show_success("未发现截断或结构问题");
+        } else {
+            for problem in &diagnosis.problems {
+                self.ui.show_error(problem);
+            }
+            println!("{}", ui::style("本工具无法恢复缺失的数据，请从备份中恢复该文件。").yellow());
+        }
+
+        Ok(())
+    }
+
+    /// 将已有证书的主密钥指纹导出为 `.fpr` 文本文件
+    fn export_fingerprint_flow(&self) -> Result<()> {
+        let path = self.ui.select_key_file("选择公钥或私钥文件 (.asc / .bin)", ui::FileFilter::Cert)?;
+        let fingerprint = self.load_fingerprint(&path)?;
+
+        let hex = fingerprint.to_hex();
+        let default_name = format!("{}.fpr", &hex[hex.len() - 16..]);
+        let save_path = self.ui.select_save_location(&default_name)?;
+        fs::write(&save_path, pgp::fingerprint_file_content(&fingerprint))?;
+        self.ui.show_success(&format!(
+            "指纹 {} 已保存到: {}",
+            fingerprint.to_spaced_hex(),
+            save_path.display(),
+        ));
+
+        Ok(())
+    }
+
+    /// 格式化显示元数据 JSON，并标出与 [`KeyMetadata`] 格式不符之处；加密的元数据可输入密码解密后查看
+    fn view_metadata_flow(&self) -> Result<()> {
+        let path = self.ui.select_open_file("选择元数据文件 (.json / .json.enc)")?;
+        let data = commands::read_key_file(&path)?;
+        let data = if data.starts_with(encryption::BLOB_MAGIC) {
+            if !self.ui.confirm("该元数据已加密，是否输入私钥密码解密查看？", true)? {
+                return Ok(());
+            }
+            let password = self.ui.input_password("请输入私钥密码", false)?;
+            encryption::decrypt_with_password(&data, &password)?
+        } else {
+            data
+        };
+        let value: serde_json::Value = serde_json::from_slice(&data)
+            .map_err(|e| error::AbuError::BadFormat(format!("元数据不是有效的 JSON: {}", e)))?;
+
+        println!();
+        println!("{}", serde_json::to_string_pretty(&value)?);
+        println!();
+        let problems = metadata_problems(&value);
+        if problems.is_empty() {
+            self.ui.show_success("元数据格式正确");
+        }
+        for problem in &problems {
+            println!("{} {}", ui::style("⚠").yellow().bold(), ui::style(problem).yellow());
+        }
+        Ok(())
+    }
+
+    /// 显示证书中主密钥与各子密钥的指纹、长/短 Key ID 与 GnuPG keygrip
+    fn show_key_ids_flow(&self) -> Result<()> {
+        let path = self.ui.select_key_file("选择公钥或私钥文件 (.asc / .bin)", ui::FileFilter::Cert)?;
+        let cert = self.load_cert(&path)?;
+
+        println!();
+        if pgp::is_test_key(&cert) {
+            println!("{}", ui::style("⚠ 测试密钥 — 切勿用于生产环境！").red().bold());
+        }
+        for ids in pgp::key_ids(&cert) {
+            let role = if ids.primary { "主密钥" } else { "子密钥" };
+            println!("{}", ui::style(role).bold());
+            println!("  指纹:        {}", ids.fingerprint);
+            println!("  长 Key ID:   {}", ids.long_id);
+            println!("  短 Key ID:   {}", ids.short_id);
+            println!("  keygrip:     {}", ids.keygrip.as_deref().unwrap_or("（不支持该算法）"));
+        }
+        println!();
+
+        Ok(())
+    }
+
+    /// 将主密钥或所选子密钥的公钥点导出为 SubjectPublicKeyInfo PEM
+    fn export_spki_flow(&self) -> Result<()> {
+        let path = self.ui.select_key_file("选择公钥或私钥文件 (.asc / .bin)", ui::FileFilter::Cert)?;
+        let cert = self.load_cert(&path)?;
+
+        let keys = pgp::key_ids(&cert);
+        let index = if keys.len() > 1 {
+            let items: Vec<String> = keys
+                .iter()
+                .map(|ids| format!("{} {}", if ids.primary { "主密钥" } else { "子密钥" }, ids.long_id))
+                .collect();
+            self.ui.select_index("选择要导出的密钥", &items)?
+        } else {
+            0
+        };
+        let fingerprint = pgp::parse_fingerprint(&keys[index].fingerprint)?;
+        let pem = pgp::export_spki_pem(&cert, Some(&fingerprint))?;
+
+        let save_path = self.ui.select_save_location(&format!("{}.pem", keys[index].long_id))?;
+        fs::write(&save_path, pem)?;
+        self.ui.show_success(&format!("SPKI 公钥已保存到: {}", save_path.display()));
+
+        Ok(())
+    }
+
+    /// 将证书的公开信息导出为 JSON 文档
+    fn export_json_flow(&self) -> Result<()> {
+        let path = self.ui.select_key_file("选择公钥或私钥文件 (.asc / .bin)", ui::FileFilter::Cert)?;
+        let cert = self.load_cert(&path)?;
+
+        let hex = cert.fingerprint().to_hex();
+        let default_name = format!("{}.json", &hex[hex.len() - 16..]);
+        let (save_path, format) = self.save_public_export(&cert, &default_name)?;
+        self.ui.show_success(&format!("{}已保存到: {}", format.name(), save_path.display()));
+
+        Ok(())
+    }
+
+    /// 选择保存位置并按其扩展名（`.asc` / `.gpg` / `.json` / `.ssh` / `.pub`）导出证书的公开部分
+    fn save_public_export(
+        &self,
+        cert: &sequoia_openpgp::Cert,
+        default_name: &str,
+    ) -> Result<(std::path::PathBuf, pgp::ExportFormat)> {
+        let save_path = self.ui.select_save_location(default_name)?;
+        let format = pgp::format_for_path(&save_path);
+        fs::write(&save_path, pgp::export_cert(cert, format, &self.config.armor)?)?;
+        Ok((save_path, format))
+    }
+
+    /// 将银行名称、指纹、创建日期与指纹二维码导出为 PNG 卡片，便于打印或分享
+    #[cfg(feature = "card")]
+    fn export_card_flow(&self) -> Result<()> {
+        let path = self.ui.select_key_file("选择公钥或私钥文件 (.asc / .bin)", ui::FileFilter::Cert)?;
+        let cert = self.load_cert(&path)?;
+
+        // 卡片只显示主 User ID 中的名称部分
+        let user_id = pgp::primary_userid(&cert).unwrap_or_default();
+        let bank_name = user_id.split(" <").next().unwrap_or_default();
+        let hex = cert.fingerprint().to_hex();
+        let created = chrono::DateTime::<chrono::Utc>::from(cert.primary_key().key().creation_time())
+            .format("%Y-%m-%d UTC")
+            .to_string();
+        let png = card::render_png(&card::CardFields { bank_name, fingerprint: &hex, created: &created })?;
+
+        let default_name = format!("{}_card.png", &hex[hex.len() - 16..]);
+        let save_path = self.ui.select_save_location(&default_name)?;
+        fs::write(&save_path, png)?;
+        self.ui.show_success(&format!(
+            "公钥卡片（{}×{}）已保存到: {}",
+            card::CARD_WIDTH,
+            card::CARD_HEIGHT,
+            save_path.display(),
+        ));
+
+        Ok(())
+    }
+
+    /// 导出离线恢复单 PDF：指纹、创建日期、银行名称、公钥二维码与手写密码提示框（不含密码）
+    #[cfg(feature = "recovery-sheet")]
+    fn export_recovery_sheet_flow(&self) -> Result<()> {
+        let path = self.ui.select_key_file("选择公钥或私钥文件 (.asc / .bin)", ui::FileFilter::Cert)?;
+        let cert = self.load_cert(&path)?;
+
+        let user_id = pgp::primary_userid(&cert).unwrap_or_default();
+        let bank_name = user_id.split(" <").next().unwrap_or_default();
+        let hex = cert.fingerprint().to_hex();
+        let created = chrono::DateTime::<chrono::Utc>::from(cert.primary_key().key().creation_time())
+            .format("%Y-%m-%d UTC")
+            .to_string();
+        // 二维码只含公钥，即使选择的是私钥文件
+        let public_key = pgp::armor_public_cert(&cert, &self.config.armor)?;
+        let pdf = sheet::render_pdf(&sheet::SheetFields {
+            bank_name,
+            fingerprint: &hex,
+            created: &created,
+            public_key: &public_key,
+        })?;
+
+        let default_name = format!("{}_recovery_sheet.pdf", &hex[hex.len() - 16..]);
+        let save_path = self.ui.select_save_location(&default_name)?;
+        fs::write(&save_path, pdf)?;
+        self.ui.show_success(&format!("恢复单已保存到: {}（请在打印后手写密码提示，切勿写下密码本身）", save_path.display()));
+
+        Ok(())
+    }
+
+    /// 将吊销证书导出为分片二维码 PNG，供纸质备份
+    #[cfg(feature = "card")]
+    fn export_revocation_qr_flow(&self) -> Result<()> {
+        let path = self.ui.select_key_file("选择吊销证书文件", ui::FileFilter::Cert)?;
+        let revocation = pgp::parse_revocation_cert(&fs::read(&path)?)?;
+        let parts = revqr::split(&revocation);
+
+        let dir = self.ui.select_directory("选择二维码图片的保存目录")?;
+        let stem = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or("revocation");
+        for (index, part) in parts.iter().enumerate() {
+            let caption = format!("ABU REVOCATION {}/{}", index + 1, parts.len());
+            let png = card::render_qr_png(part, &caption)?;
+            let png_path = dir.join(format!("{}_qr_{}of{}.png", stem, index + 1, parts.len()));
+            fs::write(&png_path, png)?;
+            println!("已保存: {}", png_path.display());
+        }
+        self.ui.show_success(&format!(
+            "吊销证书已导出为 {} 个二维码，请全部打印并一同保管；恢复时扫描全部二维码后使用“从扫描的二维码重组吊销证书”",
+            parts.len(),
+        ));
+
+        Ok(())
+    }
+
+    /// 从扫描器输出的二维码文本（每行一片，顺序任意）重组吊销证书并保存
+    fn import_revocation_qr_flow(&self) -> Result<()> {
+        let path = self.ui.select_open_file("选择扫描得到的二维码文本文件")?;
+        let revocation = revqr::join(&fs::read_to_string(&path)?)?;
+        // 重组后再次校验确为主密钥吊销签名
+        let revocation = pgp::parse_revocation_cert(&revocation)?;
+
+        let armor = self.config.armor_exports();
+        let data = if armor {
+            pgp::add_ascii_armor_with(&revocation, sequoia_openpgp::armor::Kind::PublicKey, &self.config.armor)?
+                .into_bytes()
+        } else {
+            revocation
+        };
+        let default_name = format!("revocation_restored.{}", pgp::cert_extension(armor));
+        let save_path = self.ui.select_save_location(&default_name)?;
+        fs::write(&save_path, data)?;
+        self.ui.show_success(&format!("吊销证书已重组并保存到: {}", save_path.display()));
+
+        Ok(())
+    }
+
+    /// 紧急销毁：安全删除所选目录中的私钥与加密文件，公钥与元数据默认保留
+    ///
+    /// 依次要求：确认文件列表、确认删除、倒计时后输入确认短语，任一步拒绝即取消。
+    fn panic_wipe_flow(&self) -> Result<()> {
+        let dir = self.ui.select_directory("选择要紧急销毁私钥的目录")?;
+        let files = wipe::scan(&dir)?;
+        let unreadable: Vec<_> = files.iter().filter(|(_, kind)| *kind == wipe::FileKind::Unreadable).collect();
+        if !unreadable.is_empty() {
+            println!("{}", ui::style("以下文件无法读取，无法判断是否为私钥，不会被删除，请手动检查：").yellow().bold());
+            for (path, _) in &unreadable {
+                println!("  ? {}", path.display());
+            }
+        }
+        if !files.iter().any(|(_, kind)| *kind == wipe::FileKind::Private) {
+            println!("{} 中没有找到私钥或加密文件", dir.display());
+            return Ok(());
+        }
+
+        let keep_public = !self.ui.confirm("是否同时删除公钥、吊销证书与元数据等其余文件？", false)?;
+        let targets = wipe::targets(&files, keep_public);
+        println!("{}", ui::style("以下文件将被覆盖后删除，无法恢复：").red().bold());
+        for path in &targets {
+            println!("  ✗ {}", path.display());
+        }
+        let kept: Vec<_> = files
+            .iter()
+            .filter(|(path, kind)| *kind != wipe::FileKind::Unreadable && !targets.contains(path))
+            .collect();
+        if !kept.is_empty() {
+            println!("以下文件将保留：");
+            for (path, _) in kept {
+                println!("  ✓ {}", path.display());
+            }
+        }
+
+        if !self.ui.confirm(&format!("确认销毁以上 {} 个文件？", targets.len()), false)? {
+            return Ok(());
+        }
+        if !self.ui.confirm_dangerous(PANIC_WIPE_PHRASE)? {
+            println!("确认短语不匹配，已取消销毁。");
+            return Ok(());
+        }
+
+        let mut failed = Vec::new();
+        for path in &targets {
+            if let Err(e) = wipe::secure_delete(path) {
+                failed.push(format!("{}: {}", path.display(), e));
+            }
+        }
+        if !failed.is_empty() {
+            return Err(anyhow::anyhow!("{} 个文件未能删除:\n{}", failed.len(), failed.join("\n")));
+        }
+        self.ui.show_success(&format!("已销毁 {} 个文件", targets.len()));
+        Ok(())
+    }
+
+    /// 在远程 HSM 签名服务上生成密钥：私钥不离开 HSM，本地只保存公钥证书与服务端密钥句柄
+    #[cfg(feature = "remote-keygen")]
+    fn remote_generate_flow(&self) -> Result<()> {
+        let url = self
+            .config
+            .remote_keygen_url
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("未配置远程密钥生成服务地址（remote_keygen_url / ABU_REMOTE_KEYGEN_URL）"))?;
+
+        let bank_name = self.ui.input_bank_name(None)?;
+        let email = self.ui.input_email(None)?;
+        let mut user_ids = userid::UserIdSet::new()
+            .with_allowed_domains(&self.config.allowed_email_domains)
+            .with_comment(self.config.test_key.then_some(pgp::TEST_KEY_COMMENT));
+        user_ids.add(&bank_name, &email)?;
+        // 曲线由远程 HSM 支持，与本地后端无关
+        let curves = security::KeyCurve::ALL.map(|curve| (curve, true));
+        let curve = self.ui.select_curve(&curves, self.config.default_curve)?;
+
+        let request = remote::KeyRequest {
+            version: remote::PROTOCOL_VERSION,
+            bank_name: bank_name.clone(),
+            user_ids: user_ids.user_ids(),
+            curve: curve.id().to_string(),
+            expiry_days: self.config.key_expiry_days,
+            notations: self
+                .config
+                .notations
+                .iter()
+                .map(|(name, value)| remote::Notation { name: name.clone(), value: value.clone() })
+                .collect(),
+        };
+        println!();
+        println!("{} 正在请求远程服务生成{}密钥对: {}", ui::style("⏳").cyan(), curve.name(), url);
+        let key = remote::generate(&url, &request)?;
+
+        let default_name = format!("public_{}.asc", self.now().file_stamp());
+        let save_path = self.ui.select_save_location(&default_name)?;
+        fs::write(&save_path, &key.public_cert)?;
+        // 记录服务端句柄，后续签名请求以此引用 HSM 中的私钥
+        let mut handle_path = save_path.as_os_str().to_owned();
+        handle_path.push(".remote.json");
+        let handle = serde_json::json!({
+            "service": url,
+            "key_id": key.key_id,
+            "fingerprint": key.cert.fingerprint().to_hex(),
+        });
+        fs::write(&handle_path, serde_json::to_string_pretty(&handle)?)?;
+
+        self.ui.show_success(&format!(
+            "远程密钥已生成，指纹: {}\n公钥已保存到: {}\n密钥句柄已保存到: {}\n私钥保存在远程 HSM 中，本地没有私钥文件。",
+            key.cert.fingerprint().to_spaced_hex(),
+            save_path.display(),
+            std::path::Path::new(&handle_path).display(),
+        ));
+
+        Ok(())
+    }
+
+    /// 将证书按 WKD 目录结构写入网站根目录，供银行自建的 Web Key Directory 发布
+    fn export_wkd_flow(&self) -> Result<()> {
+        let path = self.ui.select_key_file("选择要发布的公钥或私钥文件 (.asc / .bin)", ui::FileFilter::Cert)?;
+        let cert = self.load_cert(&path)?;
+
+        let emails: Vec<String> = cert
+            .userids()
+            .filter_map(|ua| {
+                let userid = String::from_utf8_lossy(ua.userid().value()).into_owned();
+                pgp::userid_email(&userid).map(str::to_string)
+            })
+            .collect();
+        if emails.is_empty() {
+            return Err(anyhow::anyhow!("证书没有包含邮箱的 User ID，无法发布到 WKD"));
+        }
+        let email = &emails[self.ui.select_index("选择要发布的邮箱", &emails)?];
+        let (relative, data) = pgp::wkd_export(&cert, email)?;
+
+        let root = self.ui.select_directory("选择网站根目录（将在其中创建 .well-known/openpgpkey）")?;
+        let target = root.join(&relative);
+        if let Some(dir) = target.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&target, data)?;
+        // WKD 客户端要求存在 policy 文件（可为空）
+        let policy = root.join(".well-known").join("openpgpkey").join("policy");
+        if !policy.exists() {
+            fs::write(&policy, "")?;
+        }
+
+        let (local, domain) = email.rsplit_once('@').unwrap_or((email.as_str(), ""));
+        let hash = relative.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+        self.ui.show_success(&format!(
+            "WKD 证书已保存到: {}\n访问地址: https://{}/.well-known/openpgpkey/hu/{}?l={}",
+            target.display(),
+            domain,
+            hash,
+            local,
+        ));
+        Ok(())
+    }
+
+    /// 设置菜单：查看并修改配置文件中的常用项，保存后下次启动生效
+    ///
+    /// 保存时按当前各项重新生成整个 TOML 文件，原文件中的注释与排版不会保留。
+    fn settings_flow(&self) -> Result<()> {
+        let path = self.config.config_file.clone();
+        let mut layer = if path.is_file() {
+            config::ConfigLayer::from_file(&path)?
+        } else {
+            config::ConfigLayer::default()
+        };
+
+        loop {
+            let iterations = layer.encryption_iterations.unwrap_or(self.config.kdf.iterations);
+            let curve = match &layer.default_curve {
+                Some(id) => security::KeyCurve::from_id(id).unwrap_or(self.config.default_curve),
+                None => self.config.default_curve,
+            };
+            let expiry_days = layer.key_expiry_days.or(self.config.key_expiry_days).unwrap_or(0);
+            let output_dir = layer.output_dir.as_ref().or(self.config.output_dir.as_ref());
+            let language = match &layer.language {
+                Some(name) => ui::Language::from_name(name).unwrap_or(self.config.language),
+                None => self.config.language,
+            };
+
+            let items = vec![
+                format!("PBKDF2 迭代次数: {}", iterations),
+                format!("默认曲线: {}", curve.name()),
+                match expiry_days {
+                    0 => "密钥有效期: 永不过期".to_string(),
+                    days => format!("密钥有效期: {} 天", days),
+                },
+                format!(
+                    "输出目录: {}",
+                    output_dir.map_or("（未设置）".to_string(), |dir| dir.display().to_string()),
+                ),
+                format!("界面语言: {}", language.name()),
+                "保存并返回".to_string(),
+                "放弃修改并返回".to_string(),
+            ];
+            match self.ui.select_index(&format!("设置（{}）", path.display()), &items)? {
+                0 => {
+                    let value = self.ui.input_validated("PBKDF2 迭代次数", &iterations.to_string(), |text| {
+                        let iterations = text.trim().parse().map_err(|_| anyhow::anyhow!("请输入整数"))?;
+                        config::parse_iterations(iterations).map(|_| ())
+                    })?;
+                    layer.encryption_iterations = Some(value.parse()?);
+                }
+                1 => {
+                    let names: Vec<String> =
+                        security::KeyCurve::ALL.iter().map(|curve| curve.name().to_string()).collect();
+                    let index = self.ui.select_index("默认曲线", &names)?;
+                    layer.default_curve = Some(security::KeyCurve::ALL[index].id().to_string());
+                }
+                2 => {
+                    let value = self.ui.input_validated(
+                        "密钥有效期（天，0 表示永不过期）",
+                        &expiry_days.to_string(),
+                        |text| config::parse_expiry_days(text).map(|_| ()),
+                    )?;
+                    layer.key_expiry_days = Some(config::parse_expiry_days(&value)?);
+                }
+                3 => {
+                    let current = output_dir.map(|dir| dir.display().to_string()).unwrap_or_default();
+                    let value = self.ui.input_validated("输出目录（留空表示不设置）", &current, |text| {
+                        if text.trim().is_empty() {
+                            Ok(())
+                        } else {
+                            config::validate_output_dir(std::path::Path::new(text.trim()))
+                        }
+                    })?;
+                    layer.output_dir = (!value.is_empty()).then(|| std::path::PathBuf::from(value));
+                }
+                4 => {
+                    let names: Vec<String> = ui::Language::ALL.iter().map(|language| language.name().to_string()).collect();
+                    let index = self.ui.select_index("界面语言", &names)?;
+                    layer.language = Some(ui::Language::ALL[index].id().to_string());
+                }
+                5 => {
+                    if path.is_file() {
+                        println!("{}", ui::style("保存会重写整个配置文件，文件中的注释与排版将丢失。").yellow());
+                        if !self.ui.confirm("仍要保存吗？", true)? {
+                            continue;
+                        }
+                    }
+                    layer.save(&path)?;
+                    self.ui.show_success(&format!(
+                        "设置已保存到: {}\n重启程序后生效（ABU_* 环境变量与命令行参数仍会覆盖）",
+                        path.display(),
+                    ));
+                    return Ok(());
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    /// 用自己的私钥认证对方证书上的 User ID，导出带认证签名的证书发回给对方
+    fn certify_key_flow(&self) -> Result<()> {
+        let own_path = self.ui.select_key_file("选择您自己的私钥文件 (.bin / .asc)", ui::FileFilter::EncryptedKey)?;
+        let certifier = self.load_cert(&own_path)?;
+        if !certifier.is_tsk() {
+            return Err(anyhow::anyhow!("所选文件不含私钥，无法用于认证"));
+        }
+
+        let target_path = self.ui.select_key_file("选择要认证的对方公钥文件 (.asc)", ui::FileFilter::Cert)?;
+        let target = pgp::parse_cert(&commands::read_key_file(&target_path)?)?;
+        let userids: Vec<String> = target
+            .userids()
+            .map(|ua| String::from_utf8_lossy(ua.userid().value()).into_owned())
+            .collect();
+        if userids.is_empty() {
+            return Err(anyhow::anyhow!("对方证书没有 User ID"));
+        }
+
+        println!();
+        println!("对方证书指纹: {}", target.fingerprint().to_spaced_hex());
+        println!("{}", ui::style("请先通过可信渠道核对上述指纹，再进行认证！").yellow());
+        let userid = &userids[self.ui.select_index("选择要认证的 User ID", &userids)?];
+        let levels: Vec<String> = pgp::CertificationLevel::ALL
+            .iter()
+            .map(|level| level.name().to_string())
+            .collect();
+        let level = pgp::CertificationLevel::ALL[self.ui.select_index("选择核验程度", &levels)?];
+
+        let certified = pgp::certify_userid(&certifier, target, userid, level, self.config.signature_hash)?;
+
+        let armor = self.config.armor_exports();
+        let fpr = certified.fingerprint().to_hex();
+        let default_name = format!("certified_{}.{}", &fpr[fpr.len() - 16..], pgp::cert_extension(armor));
+        let (save_path, _) = self.save_public_export(&certified, &default_name)?;
+        self.ui.show_success(&format!(
+            "已认证 User ID: {}\n带认证签名的证书已保存到: {}\n请将该文件发回给对方导入",
+            userid,
+            save_path.display(),
+        ));
+
+        Ok(())
+    }
+
+    /// 生成密钥迁移声明：列出新旧密钥指纹的明文，由新旧两把密钥共同签名
+    fn transition_statement_flow(&self) -> Result<()> {
+        let old_path = self.ui.select_key_file("选择旧密钥的私钥文件 (.bin / .asc)", ui::FileFilter::EncryptedKey)?;
+        let old = self.load_cert(&old_path)?;
+        let new_path = self.ui.select_key_file("选择新密钥的私钥文件 (.bin / .asc)", ui::FileFilter::EncryptedKey)?;
+        let new = self.load_cert(&new_path)?;
+        if !old.is_tsk() || !new.is_tsk() {
+            return Err(anyhow::anyhow!("迁移声明需要新旧两把密钥共同签名，所选文件必须都含私钥"));
+        }
+        if old.fingerprint() == new.fingerprint() {
+            return Err(anyhow::anyhow!("新旧密钥是同一把密钥"));
+        }
+
+        let statement = pgp::transition_statement(&old, &new, &self.now().display());
+        let signed = pgp::clearsign(&[&old, &new], &statement, self.config.signature_hash)?;
+
+        let fpr = new.fingerprint().to_hex();
+        let default_name = format!("transition_{}.txt", &fpr[fpr.len() - 16..]);
+        let save_path = self.ui.select_save_location(&default_name)?;
+        fs::write(&save_path, signed)?;
+        self.ui.show_success(&format!(
+            "迁移声明已保存到: {}\n可用 gpg --verify 分别以新旧公钥验证",
+            save_path.display(),
+        ));
+        Ok(())
+    }
+
+    /// 导入 GnuPG 导出的私钥（可受口令保护），重新以 ABU 格式加密保存
+    fn import_gnupg_key_flow(&self) -> Result<()> {
+        let path = self.ui.select_key_file("选择 GnuPG 导出的私钥文件 (.asc)", ui::FileFilter::Cert)?;
+        let cert = pgp::parse_cert(&commands::read_key_file(&path)?)?;
+        if !cert.is_tsk() {
+            return Err(anyhow::anyhow!("该文件不含私钥（请使用 gpg --export-secret-keys 导出）"));
+        }
+        // 占位密钥无法解锁，在询问口令之前报告，避免被误判为口令错误
+        let stubs = pgp::gnupg_stub_keys(&cert);
+        if !stubs.is_empty() {
+            return Err(pgp::stub_error(&stubs).into());
+        }
+        let cert = if pgp::has_encrypted_secrets(&cert) {
+            let passphrase = zeroize::Zeroizing::new(
+                self.ui.input_password("该私钥受 GnuPG 口令保护，请输入口令以解锁（输入时不可见）", false)?,
+            );
+            pgp::unlock_secrets(cert, &passphrase)?
+        } else {
+            cert
+        };
+
+        let user_id = pgp::primary_userid(&cert);
+        println!();
+        println!("指纹: {}", cert.fingerprint().to_spaced_hex());
+        println!("主 User ID: {}", user_id.as_deref().unwrap_or(pgp::NO_USER_ID));
+
+        let password = self.ui.input_password("请为导入的私钥设置 ABU 保护密码（输入时不可见）", true)?;
+        let tsk = pgp::armor_tsk(&cert)?;
+        let mut encrypted = self.export_and_encrypt_private_key(&tsk, &password, &self.config.kdf)?;
+        if self.config.armor_private_blob() {
+            encrypted = encryption::armor_blob(&encrypted, &self.config.armor_label, self.config.blob_armor_width).into_bytes();
+        }
+
+        let name = user_id
+            .as_deref()
+            .and_then(|uid| uid.split(" <").next())
+            .filter(|name| !name.is_empty())
+            .unwrap_or("imported");
+        let now = self.now();
+        let default_name = format!(
+            "{}_private_{}.{}",
+            name.replace(' ', "_"),
+            now.file_stamp(),
+            self.config.private_blob_extension(),
+        );
+        let save_path = self.ui.select_save_location(&default_name)?;
+        fs::write(&save_path, encrypted)?;
+
+        // 元数据由证书本身推导：创建时间与主密钥算法
+        let primary = cert.primary_key().key();
+        let created = chrono::DateTime::<chrono::Utc>::from(primary.creation_time());
+        let metadata = KeyMetadata {
+            bank_name: name.to_string(),
+            user_id: user_id.clone().unwrap_or_else(|| "anonymous".to_string()),
+            generation_date: self.metadata_date(timestamp::Timestamp::at(created, self.config.utc)),
+            key_type: primary.pk_algo().to_string(),
+            key_size: primary.mpis().bits().unwrap_or(0) as u32,
+            key_usage: pgp::key_usage(&cert),
+            abu_version: "1.0".to_string(),
+            notes: "Imported from GnuPG".to_string(),
+            test_key: pgp::is_test_key(&cert),
+            kdf_level: KDF_LEVEL_CONFIGURED.to_string(),
+            designated_revoker: pgp::designated_revokers(&cert).first().map(|fpr| fpr.to_hex()),
+            profile: None,
+        };
+        let metadata_path = self.save_metadata_or_warn(&metadata, &save_path, &password);
+
+        let mut message = format!("私钥（已加密）已保存到: {}", save_path.display());
+        if let Some(path) = metadata_path {
+            message.push_str(&format!("\n元数据已保存到: {}", path.display()));
+        }
+        self.ui.show_success(&message);
+
+        Ok(())
+    }
+
+    /// 比较两个密钥文件是否为同一密钥（比较主密钥指纹）
+    fn compare_keys_flow(&self) -> Result<()> {
+        let first = self.ui.select_key_file("选择第一个密钥文件 (.asc / .bin)", ui::FileFilter::Cert)?;
+        let second = self.ui.select_key_file("选择第二个密钥文件 (.asc / .bin)", ui::FileFilter::Cert)?;
+
+        let first_fpr = self.load_fingerprint(&first)?;
+        let second_fpr = self.load_fingerprint(&second)?;
+
+        println!();
+        println!("第一个密钥指纹: {}", first_fpr.to_spaced_hex());
+        println!("第二个密钥指纹: {}", second_fpr.to_spaced_hex());
+
+        if first_fpr == second_fpr {
+            self.ui.show_success("两个文件为同一密钥（指纹一致）");
+        } else {
+            self.ui.show_error("两个文件不是同一密钥（指纹不一致）");
+        }
+
+        Ok(())
+    }
+
+    /// 验证公钥证书与加密私钥是否属于同一密钥（解密私钥后比较指纹）
+    fn verify_pair_flow(&self) -> Result<()> {
+        let public_path = self.ui.select_key_file("选择公钥文件 (.asc)", ui::FileFilter::Cert)?;
+        let private_path = self.ui.select_key_file("选择加密私钥文件 (.bin / .asc)", ui::FileFilter::EncryptedKey)?;
+        let public = commands::read_key_file(&public_path)?;
+        let private = commands::read_key_file(&private_path)?;
+        let password = zeroize::Zeroizing::new(self.ui.input_password("请输入该私钥的密码（输入时不可见）", false)?);
+
+        let check = commands::check_key_pair(&public, &private, &password)?;
+        println!();
+        println!("公钥指纹: {}", check.public.to_spaced_hex());
+        println!("私钥指纹: {}", check.private.to_spaced_hex());
+        for fingerprint in &check.missing_subkeys {
+            println!("公钥证书中缺少子密钥: {}", fingerprint.to_spaced_hex());
+        }
+
+        if check.matches() {
+            self.ui.show_success("公钥与私钥配对（指纹一致）");
+        } else if check.public == check.private {
+            self.ui.show_error("主密钥一致，但公钥证书缺少私钥中的子密钥，可能是较旧的公钥导出");
+        } else {
+            self.ui.show_error("公钥与私钥不配对（指纹不一致）");
+        }
+
+        Ok(())
+    }
+
+    /// 验证分离签名：不需要事先知道签名者，依次尝试公钥环中的全部证书并报告匹配的一个
+    fn verify_signature_flow(&self) -> Result<()> {
+        let signature_path = self.ui.select_key_file("选择分离签名文件 (.sig / .asc)", ui::FileFilter::Signature)?;
+        let data_path = self.ui.select_open_file("选择被签名的文件")?;
+        let keyring_path = match &self.config.keyring {
+            Some(path) => path.clone(),
+            None => self.ui.select_key_file("选择公钥环文件（多个公钥拼接，例如 gpg --export 的输出）", ui::FileFilter::Cert)?,
+        };
+
+        let certs = pgp::parse_keyring(&commands::read_key_file(&keyring_path)?)?;
+        let signature = commands::read_key_file(&signature_path)?;
+        // 对摘要的签名（sign --digest）：先流式计算文件摘要，再以摘要字节验证，大文件不必整个读入
+        let data = match pgp::prehashed_algorithm(&signature) {
+            Some(algorithm) => {
+                println!("此签名针对文件的 {} 摘要，将先计算文件摘要再验证", algorithm);
+                let file = fs::File::open(&data_path)
+                    .map_err(|e| anyhow::anyhow!("无法读取文件 {}: {}", data_path.display(), e))?;
+                pgp::PrehashedDigest::compute(&algorithm, std::io::BufReader::new(file))?.bytes
+            }
+            None => commands::read_data_file(&data_path)?,
+        };
+
+        match pgp::verify_detached(&certs, &signature, &data)? {
+            Some(cert) => {
+                let user_id = pgp::primary_userid_label(cert);
+                self.ui.show_success(&format!(
+                    "签名有效\n签名者: {}\n指纹: {}",
+                    user_id,
+                    cert.fingerprint().to_spaced_hex(),
+                ));
+            }
+            None => self.ui.show_error(&format!(
+                "公钥环 {} 中的 {} 个证书都不能验证此签名",
+                keyring_path.display(),
+                certs.len(),
+            )),
+        }
+        Ok(())
+    }
+
+    /// 校验生成文件清单：先用公钥验证清单签名，再逐个重新计算所列文件的哈希
+    fn verify_manifest_flow(&self) -> Result<()> {
+        let manifest_path = self.ui.select_open_file("选择文件清单 (.manifest)")?;
+        let signature_path = manifest::find_signature(&manifest_path).ok_or_else(|| {
+            anyhow::anyhow!("找不到清单签名 {}（或 .sig）", manifest::signature_path(&manifest_path, true).display())
+        })?;
+        let cert_path = self.ui.select_key_file("选择生成该清单的公钥文件 (.asc)", ui::FileFilter::Cert)?;
+
+        let cert = pgp::parse_cert(&commands::read_key_file(&cert_path)?)?;
+        let manifest = fs::read_to_string(&manifest_path)?;
+        let signature = fs::read(&signature_path)
+            .map_err(|e| anyhow::anyhow!("无法读取清单签名 {}: {}", signature_path.display(), e))?;
+        let certs = [cert];
+        if pgp::verify_detached(&certs, &signature, manifest.as_bytes())?.is_none() {
+            return Err(anyhow::anyhow!(
+                "清单签名无效：清单已被修改，或不是由 {} 签名",
+                certs[0].fingerprint().to_spaced_hex(),
+            ));
+        }
+
+        println!();
+        let mut failed = 0;
+        for (name, status) in manifest::check(&manifest_path, &manifest)? {
+            match status {
+                manifest::EntryStatus::Ok => println!("  {} {}", ui::style("✓").green(), name),
+                manifest::EntryStatus::Modified => {
+                    failed += 1;
+                    println!("  {} {}（内容已被修改）", ui::style("✗").red(), name);
+                }
+                manifest::EntryStatus::Missing => {
+                    failed += 1;
+                    println!("  {} {}（文件缺失或无法读取）", ui::style("✗").red(), name);
+                }
+            }
+        }
+        println!();
+        if failed > 0 {
+            return Err(anyhow::anyhow!("清单签名有效，但有 {} 个文件与清单不符", failed));
+        }
+        self.ui.show_success(&format!(
+            "清单签名有效（{}），所列文件均未被修改",
+            certs[0].fingerprint().to_spaced_hex(),
+        ));
+        Ok(())
+    }
+
+    /// 从包含私钥的文件中去除秘密材料，导出仅含公钥的证书
+    fn strip_secret_flow(&self) -> Result<()> {
+        let path = self.ui.select_key_file("选择包含私钥的文件 (.asc / .bin)", ui::FileFilter::Cert)?;
+        let cert = self.load_cert(&path)?;
+        if !cert.is_tsk() {
+            println!("该文件不包含私钥材料，将直接重新导出公钥");
+        }
+
+        let mut public = pgp::strip_secrets(cert)?;
+        // 可选：最小化导出，分发紧凑的公钥且不暴露认证者
+        if self.ui.confirm("是否最小化导出（只保留最新自签名，去除第三方认证）？", false)? {
+            let removed = pgp::third_party_certification_count(&public);
+            public = pgp::minimize_cert(&public)?;
+            println!("已最小化：去除了 {} 个第三方认证及被取代的旧自签名", removed);
+        }
+        let fpr = public.fingerprint().to_hex();
+        let default_name = format!(
+            "public_{}.{}",
+            &fpr[fpr.len() - 16..],
+            pgp::cert_extension(self.config.armor_exports()),
+        );
+        let (save_path, format) = self.save_public_export(&public, &default_name)?;
+
+        self.ui.show_success(&format!("仅含公钥的证书（{}）已保存到: {}", format.name(), save_path.display()));
+        Ok(())
+    }
+
+    /// 自助恢复：从候选密码列表中逐个尝试解密加密私钥
+    fn recover_password_flow(&self) -> Result<()> {
+        println!();
+        println!("{}", ui::style("密码恢复模式仅用于找回您本人的私钥密码！").yellow().bold());
+        println!("{}", ui::style("候选密码文件每行一个密码，请在使用后安全删除该文件。").yellow());
+        if !self.ui.confirm("确认这是您本人的私钥并继续？", false)? {
+            return Ok(());
+        }
+
+        let key_path = self.ui.select_key_file("选择加密私钥文件 (.bin / .asc)", ui::FileFilter::EncryptedKey)?;
+        let data = commands::read_key_file(&key_path)?;
+        // 先校验文件格式，避免把格式错误误判为密码错误
+        encryption::parse_blob(&encryption::unarmor_blob(&data)?)?;
+
+        let list_path = self.ui.select_open_file("选择候选密码列表文件")?;
+        let list = fs::read_to_string(&list_path)?;
+        let candidates = commands::parse_candidate_passwords(&list);
+        if candidates.is_empty() {
+            return Err(anyhow::anyhow!("候选密码列表为空"));
+        }
+
+        let found = commands::find_candidate_password(&data, &candidates, |index| {
+            // 每次尝试之间固定延迟，限制尝试速率
+            if index > 0 {
+                std::thread::sleep(std::time::Duration::from_millis(RECOVERY_ATTEMPT_DELAY_MS));
+            }
+            println!("正在尝试第 {}/{} 个候选密码...", index + 1, candidates.len());
+        });
+        if let Some(index) = found {
+            self.ui.show_success(&format!(
+                "找到正确密码：候选列表第 {} 行（非空行计数）",
+                index + 1,
+            ));
+            return Ok(());
+        }
+
+        self.ui.show_error(&format!("{} 个候选密码均不正确", candidates.len()));
+        Ok(())
+    }
+
+    /// 更换证书的主 User ID（解密私钥、添加新 User ID 并设为主 User ID、重新加密并导出公钥）
+    fn change_primary_userid_flow(&self) -> Result<()> {
+        let path = self.ui.select_key_file("选择加密私钥文件 (.bin / .asc)", ui::FileFilter::EncryptedKey)?;
+        // 读取到写回期间持有锁，防止另一个实例同时改写同一私钥
+        let _lock = lock::FileLock::acquire(&path)?;
+        let data = commands::read_key_file(&path)?;
+        let (header, _) = encryption::parse_blob(&encryption::unarmor_blob(&data)?)?;
+        let password = self.ui.input_password("请输入该私钥的密码（输入时不可见）", false)?;
+        let plaintext = zeroize::Zeroizing::new(encryption::decrypt_with_password(&data, &password)?);
+        let cert = commands::parse_decrypted_tsk(&plaintext)?;
+        let fingerprint = cert.fingerprint();
+        if !self.confirm_recovery_slot_loss(&header)? {
+            return Ok(());
+        }
+
+        match pgp::primary_userid(&cert) {
+            Some(uid) => println!("当前主 User ID: {}", ui::style(uid).bold()),
+            None => println!("当前证书没有有效的 User ID"),
+        }
+
+        // 与生成时相同的校验：邮箱格式与域名策略
+        let name = self.ui.input_user_id_name("")?;
+        let email = self.ui.input_email(None)?;
+        let mut user_ids = userid::UserIdSet::new().with_allowed_domains(&self.config.allowed_email_domains);
+        user_ids.add(&name, &email)?;
+        let new_userid = user_ids.user_ids().remove(0);
+        let revoke_old = self.ui.confirm("是否吊销原主 User ID？", false)?;
+
+        let cert = pgp::set_primary_userid(cert, &new_userid, revoke_old)?;
+        if cert.fingerprint() != fingerprint {
+            return Err(anyhow::anyhow!("更新后的证书指纹发生变化，已中止"));
+        }
+
+        // 使用同一密码以新的 salt/nonce 重新加密私钥；配置了恢复证书时同样写入恢复槽
+        let tsk = pgp::armor_tsk(&cert)?;
+        let mut encrypted = self.export_and_encrypt_private_key(&tsk, &password, &self.config.kdf)?;
+        if self.config.armor_private_blob() {
+            encrypted = encryption::armor_blob(&encrypted, &self.config.armor_label, self.config.blob_armor_width).into_bytes();
+        }
+        let private_name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("private.bin")
+            .to_string();
+        let private_path = self.ui.select_save_location(&private_name)?;
+        // 另存为其他文件时，该文件同样需要加锁
+        let _target_lock = if private_path == path { None } else { Some(lock::FileLock::acquire(&private_path)?) };
+        lock::write_atomic(&private_path, &encrypted)?;
+
+        // 重新导出公钥证书
+        let public = pgp::strip_secrets(cert)?;
+        let default_pub_name = format!(
+            "public_{}.{}",
+            self.now().file_stamp(),
+            pgp::cert_extension(self.config.armor_exports()),
+        );
+        let (pub_path, _) = self.save_public_export(&public, &default_pub_name)?;
+
+        self.ui.show_success(&format!(
+            "主 User ID 已更新为: {}\n私钥（已加密）已保存到: {}\n公钥已保存到: {}\n指纹未变化: {}",
+            new_userid,
+            private_path.display(),
+            pub_path.display(),
+            fingerprint.to_spaced_hex(),
+        ));
+        Ok(())
+    }
+
+    /// 原文件含托管恢复槽而当前未配置恢复证书时，重新加密会丢失恢复槽：警告并要求确认
+    fn confirm_recovery_slot_loss(&self, header: &encryption::BlobHeader) -> Result<bool> {
+        if header.key_slots.is_none() || self.config.recovery_cert.is_some() {
+            return Ok(true);
+        }
+        println!("{}", ui::style("原文件含托管恢复槽，但当前未配置恢复证书，新文件将不再支持托管恢复。").yellow());
+        self.ui.confirm("仍要继续吗？", false)
+    }
+
+    /// 修改私钥密码：解密后以新密码重新加密，每次都使用新的 salt 与 nonce，保持原文件的二进制/装甲格式
+    fn change_password_flow(&self) -> Result<()> {
+        let path = self.ui.select_key_file("选择加密私钥文件 (.bin / .asc)", ui::FileFilter::EncryptedKey)?;
+        let _lock = lock::FileLock::acquire(&path)?;
+        let data = commands::read_key_file(&path)?;
+        let unarmored = encryption::unarmor_blob(&data)?;
+        let (header, _) = encryption::parse_blob(&unarmored)?;
+        let armored = std::str::from_utf8(&data).is_ok_and(encryption::is_armored_blob);
+
+        let old_password = zeroize::Zeroizing::new(self.ui.input_password("请输入当前密码（输入时不可见）", false)?);
+        let plaintext = zeroize::Zeroizing::new(encryption::decrypt_with_password(&data, &old_password)?);
+        commands::parse_decrypted_tsk(&plaintext)?;
+        let new_password = zeroize::Zeroizing::new(self.ui.input_password("请设置新密码（输入时不可见）", true)?);
+
+        if !self.confirm_recovery_slot_loss(&header)? {
+            return Ok(());
+        }
+
+        // 新密码绝不复用原 salt：每次加密都生成新的随机 salt，并在写出前再次核对
+        let encrypted = self.export_and_encrypt_private_key(&plaintext, &new_password, &self.config.kdf)?;
+        let (new_header, _) = encryption::parse_blob(&encrypted)?;
+        if new_header.salt == header.salt {
+            return Err(anyhow::anyhow!("重新加密后的 salt 与原文件相同，已中止"));
+        }
+
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("private.bin").to_string();
+        let save_path = self.ui.select_save_location(&name)?;
+        let _target_lock = if save_path == path { None } else { Some(lock::FileLock::acquire(&save_path)?) };
+        let content = encryption::blob_file_content(&encrypted, armored, &self.config.armor_label, self.config.blob_armor_width);
+        lock::write_atomic(&save_path, &content)?;
+
+        self.ui.show_success(&format!(
+            "密码已修改，私钥已保存到: {}\n原 salt: {}\n新 salt: {}",
+            save_path.display(),
+            commands::to_hex(&header.salt),
+            commands::to_hex(&new_header.salt),
+        ));
+        Ok(())
+    }
+
     /// 生成新密钥对
     pub fn generate_keys(&self) -> Result<()> {
+        // 后端不可用时直接拒绝，避免在生成中途才失败
+        if self.backend.is_none() {
+            return Err(anyhow::anyhow!("加密后端不可用，无法生成密钥（详见启动时的提示）"));
+        }
+
         // 显示欢迎和警告
         self.ui.show_welcome();
         self.ui.show_warning();
 
+        // 尽力检查系统熵池（刚启动的虚拟机/嵌入式设备上可能不足），仅提示不阻止
+        if let Some(bits) = security::low_entropy() {
+            self.ui.show_low_entropy_warning(bits);
+        }
+
+        // 系统时钟偏差过大时，证书的创建时间与有效期都会出错，由用户决定是否继续
+        if let Some(skew) = self.clock_skew.get() {
+            self.ui.show_clock_skew_warning(skew);
+            if !self.ui.confirm("时钟偏差过大，仍要继续生成吗？", false)? {
+                return Ok(());
+            }
+        }
+
         // 获取银行名称
-        let bank_name = self.ui.input_bank_name()?;
+        let remembered = self.remembered.borrow().clone();
+        let bank_name = self.ui.input_bank_name(remembered.bank_name.as_deref())?;
+        self.remembered.borrow_mut().bank_name = Some(bank_name.clone());
 
         // 输入密码
         let password = self.ui.input_password(
             "请为私钥设置保护密码（输入时不可见）",
             true,
         )?;
+        let (kdf, kdf_level) = self.select_kdf()?;
 
-        println!();
-        println!("{} 正在生成ECC P-256密钥对...", ui::style("⏳").cyan());
-
-        // 生成密钥（使用 OpenPGP user id 使得证书与私钥匹配）
-        // 让用户输入邮箱，以便构建标准的 User ID
-        let email = self.ui.input_email()?;
-        let user_id = format!("{} <{}>", bank_name, email);
-        let secure_key = match security::SecureKey::generate(&user_id) {
-            Ok(k) => k,
-            Err(e) => {
-                // 打印错误链以便诊断
-                eprintln!("SecureKey::generate() failed: {:?}", e);
-                let mut src = e.source();
-                while let Some(s) = src {
-                    eprintln!("Caused by: {:?}", s);
-                    src = s.source();
+        let mut user_ids = userid::UserIdSet::new()
+            .with_allowed_domains(&self.config.allowed_email_domains)
+            .with_comment(self.config.test_key.then_some(pgp::TEST_KEY_COMMENT));
+        if self.config.anonymous {
+            // 匿名证书：银行名称只用于本地文件名，不写入证书
+            println!("{}", ui::style("匿名模式：证书不含任何 User ID，他人无法从证书得知持有者身份。").yellow());
+        } else {
+            // 生成密钥（使用 OpenPGP user id 使得证书与私钥匹配）
+            // 让用户输入邮箱，以便构建标准的 User ID
+            let email = self.ui.input_email(remembered.email.as_deref())?;
+            self.remembered.borrow_mut().email = Some(email.clone());
+            user_ids.add(&bank_name, &email)?;
+
+            // 可选：添加更多 User ID（重复或仅大小写不同的邮箱会被拒绝）
+            while self.ui.confirm("是否添加其他 User ID？", false)? {
+                let name = self.ui.input_user_id_name(&bank_name)?;
+                let email = self.ui.input_email(None)?;
+                if let Err(e) = user_ids.add(&name, &email) {
+                    self.ui.show_error(&e.to_string());
                 }
-                return Err(e);
             }
+        }
+        let primary_user_id = user_ids.user_ids().into_iter().next();
+
+        let (primary_curve, curve, expiry_days, secure_key) = if self.config.from_mnemonic {
+            // 高级模式：由助记词确定性派生，曲线与有效期固定
+            let curve = security::KeyCurve::Cv25519;
+            if !self.curve_support.supported(curve) {
+                return Err(anyhow::anyhow!("当前加密后端不支持 {}，无法使用助记词生成", curve.name()));
+            }
+            self.ui.show_mnemonic_warning();
+            if !self.ui.confirm("确认由助记词确定性生成密钥？", false)? {
+                return Ok(());
+            }
+            let (mnemonic, passphrase) = self.ui.input_mnemonic()?;
+            let mnemonic = zeroize::Zeroizing::new(mnemonic);
+            let passphrase = zeroize::Zeroizing::new(passphrase);
+
+            println!();
+            println!("{} 正在由助记词派生{}密钥对...", ui::style("⏳").cyan(), curve.name());
+            let secure_key = security::SecureKey::from_mnemonic(
+                &user_ids.user_ids(),
+                &mnemonic,
+                &passphrase,
+                &self.config.notations,
+            )?;
+            (curve, curve, None, secure_key)
+        } else {
+            let curve = if self.config.minimal_cert {
+                // 最小证书：无子密钥，由 RSA 主密钥承担全部用途
+                self.ui.show_minimal_cert_warning();
+                if !self.ui.confirm("确认生成不含子密钥的最小证书？", false)? {
+                    return Ok(());
+                }
+                security::KeyCurve::Rsa3072
+            } else {
+                self.select_generation_curve(&remembered)?
+            };
+
+            let primary_curve = self.config.primary_curve.unwrap_or(curve);
+            if !self.curve_support.supported(primary_curve) {
+                return Err(anyhow::anyhow!("当前加密后端不支持主密钥算法 {}", primary_curve.name()));
+            }
+
+            println!();
+            println!(
+                "{} 正在生成{}密钥对...",
+                ui::style("⏳").cyan(),
+                security::key_type_name(primary_curve, curve),
+            );
+
+            let key_options = security::KeyOptions {
+                curve,
+                primary_curve: Some(primary_curve),
+                validity: security::validity_days(self.config.key_expiry_days),
+                notations: self.config.notations.clone(),
+                profile: self.config.profile,
+                minimal: self.config.minimal_cert,
+                signing_subkey: true,
+                encryption_subkey: true,
+                signing_validity: security::validity_days(self.config.signing_subkey_expiry_days),
+                encryption_validity: security::validity_days(self.config.encryption_subkey_expiry_days),
+                revocation_keys: commands::load_designated_revokers(self.config.designated_revoker.as_deref())?,
+            };
+            let secure_key = match security::SecureKey::generate(&user_ids.user_ids(), &key_options) {
+                Ok(k) => k,
+                Err(e) => {
+                    // 完整错误链（--log-level debug 或 RUST_LOG 时输出）
+                    tracing::debug!(error = ?e, "SecureKey::generate() failed");
+                    return Err(e);
+                }
+            };
+            (primary_curve, curve, self.config.key_expiry_days, secure_key)
         };
-        let public_bytes = secure_key.public_cert_bytes();
+        // 文件名、元数据与摘要统一使用同一时刻
+        let now = self.now();
 
         // 导出私钥并加密
         println!("{} 正在加密私钥...", ui::style("⏳").cyan());
-        let private_key_data = self.export_and_encrypt_private_key(&secure_key, &password)?;
+        let private_key_data = timing::timed("encrypt", || self.export_and_encrypt_private_key(&secure_key.secret_key_bytes(), &password, &kdf))?;
 
         // 创建并保存公钥（ASCII 装甲），以及保存加密私钥为单独二进制文件
         println!("{} 正在创建并导出公钥与加密私钥...", ui::style("⏳").cyan());
 
-        // 公钥已由 SecureKey 以 ASCII 装甲生成，直接使用 bytes
-        // public_bytes may already be an ASCII-armored UTF-8 buffer; try to convert safely
-        let armored_public = match String::from_utf8(public_bytes.clone()) {
-            Ok(s) => s,
-            Err(_) => pgp::add_ascii_armor(&public_bytes, sequoia_openpgp::armor::Kind::PublicKey)?,
+        // 公钥已由 SecureKey 以 ASCII 装甲生成，默认格式时直接使用
+        let armored_public = if !self.config.armor_exports() {
+            // --no-armor：导出二进制证书
+            secure_key.public_cert_binary()?
+        } else if self.config.armor != pgp::ArmorOptions::default() {
+            // 自定义装甲格式（行宽 / CRC）时，从二进制证书重新装甲
+            pgp::add_ascii_armor_with(
+                &secure_key.public_cert_binary()?,
+                sequoia_openpgp::armor::Kind::PublicKey,
+                &self.config.armor,
+            )?
+            .into_bytes()
+        } else {
+            secure_key.public_cert_armored().as_str().as_bytes().to_vec()
+        };
+
+        let key_usage = secure_key.key_usage();
+
+        // 文件名按模板渲染（默认 {bank}_{kind}_{date}_{time}）
+        let fingerprint_hex = secure_key.fingerprint().to_hex();
+        let name_fields = naming::NameFields {
+            bank: &bank_name,
+            email: primary_user_id.as_deref().and_then(pgp::userid_email),
+            fingerprint: &fingerprint_hex,
+            timestamp: &now,
         };
+        let template = &self.config.name_template;
 
         // 选择保存公钥位置
-        let default_pub_name = format!("{}_public_{}.asc",
-            bank_name.replace(' ', "_"),
-            Local::now().format("%Y%m%d_%H%M%S")
+        let default_pub_name = format!("{}.{}",
+            template.render(&name_fields, "public"),
+            pgp::cert_extension(self.config.armor_exports()),
         );
         let pub_save_path = self.ui.select_save_location(&default_pub_name)?;
 
-        // 保存公钥文件
-        fs::write(&pub_save_path, armored_public)?;
-
-        // 私钥文件名和路径（与公钥所在目录相同）
-        let private_name = format!("{}_private_{}.bin",
-            bank_name.replace(' ', "_"),
-            Local::now().format("%Y%m%d_%H%M%S")
+        // 私钥、吊销证书与元数据的路径（与公钥所在目录相同）
+        let private_name = format!("{}.{}",
+            template.render(&name_fields, "private"),
+            self.config.private_blob_extension(),
         );
         let private_path = pub_save_path.parent().unwrap_or(std::path::Path::new("")).join(private_name);
+        let revocation_name = format!("{}.{}",
+            template.render(&name_fields, "revocation"),
+            pgp::cert_extension(self.config.armor_exports()),
+        );
+        let revocation_path = pub_save_path.parent().unwrap_or(std::path::Path::new("")).join(revocation_name);
+        let manifest_name = format!("{}.{}", template.render(&name_fields, "manifest"), manifest::MANIFEST_EXTENSION);
+        let manifest_path = pub_save_path.parent().unwrap_or(std::path::Path::new("")).join(manifest_name);
+        let manifest_signature_path = manifest::signature_path(&manifest_path, self.config.armor_exports());
+        // 可选：另一种格式的加密私钥（二进制与装甲各一份）
+        let extra_private = self
+            .config
+            .extra_private_blob()
+            .map(|(extension, armored)| (private_path.with_extension(extension), armored));
+
+        // 写入任何文件前确认各输出路径互不相同，避免一个文件覆盖另一个
+        let mut outputs = vec![
+            pub_save_path.as_path(),
+            private_path.as_path(),
+            revocation_path.as_path(),
+            manifest_path.as_path(),
+            manifest_signature_path.as_path(),
+        ];
+        if let Some((path, _)) = &extra_private {
+            outputs.push(path.as_path());
+        }
+        let metadata_target = self.metadata_path(&pub_save_path);
+        if let Some(path) = &metadata_target {
+            outputs.push(path.as_path());
+        }
+        commands::ensure_distinct_paths(&outputs)?;
+        if self.config.check_duplicate_fingerprints {
+            self.check_duplicate_fingerprint(&pub_save_path, &secure_key.fingerprint())?;
+        }
+        let plan = GenerationPlan {
+            bank_name: &bank_name,
+            user_ids: &user_ids.user_ids(),
+            key_type: &security::key_type_name(primary_curve, curve),
+            expiry_days,
+            outputs: &outputs,
+        };
+        if !self.confirm_generation_plan(&plan)? {
+            // 保留记住的回答，重新生成时可直接修正有误的一项
+            println!("已取消，未写入任何文件。重新生成时将以本次输入的名称、邮箱与曲线作为默认值。");
+            return Ok(());
+        }
+
+        // 保存公钥文件
+        fs::write(&pub_save_path, armored_public)?;
 
-        // 保存加密私钥（二进制包含 salt||nonce||ciphertext）
-        fs::write(&private_path, &private_key_data)?;
+        // 保存加密私钥（二进制包含 文件头||salt||nonce||ciphertext；--armor 时为 ABU 装甲块）
+        let label = &self.config.armor_label;
+        let width = self.config.blob_armor_width;
+        fs::write(
+            &private_path,
+            encryption::blob_file_content(&private_key_data, self.config.armor_private_blob(), label, width),
+        )?;
+        if let Some((path, armored)) = &extra_private {
+            fs::write(path, encryption::blob_file_content(&private_key_data, *armored, label, width))?;
+        }
 
         // 注意：不在生成完成时导出可直接被 GnuPG 导入的私钥。
         // 私钥的明文导出改为通过主菜单的“解密并导出”功能进行，
@@ -148,70 +1603,482 @@ impl KeyGenerator {
         // 创建元数据文件
         let metadata = KeyMetadata {
             bank_name: bank_name.clone(),
-            generation_date: Local::now().to_rfc3339(),
-            key_type: "ECC P-256".to_string(),
-            key_size: 256,
+            user_id: primary_user_id.clone().unwrap_or_else(|| "anonymous".to_string()),
+            generation_date: self.metadata_date(now),
+            key_type: security::key_type_name(primary_curve, curve),
+            key_size: primary_curve.key_size(),
+            key_usage: key_usage.clone(),
             abu_version: "1.0".to_string(),
             notes: "Alpha Coin Banking System".to_string(),
+            test_key: self.config.test_key,
+            kdf_level: kdf_level.to_string(),
+            designated_revoker: secure_key.designated_revokers().first().map(|fpr| fpr.to_hex()),
+            profile: self.config.profile.map(|profile| profile.id().to_string()),
         };
 
-        let metadata_json = serde_json::to_string_pretty(&metadata)?;
-        let metadata_path = pub_save_path.with_extension("json");
-        fs::write(metadata_path, metadata_json)?;
+        // 保存吊销证书（密钥丢失或泄露时用于吊销）
+        fs::write(&revocation_path, secure_key.revocation_cert(self.config.armor_exports())?)?;
+
+        let metadata_path = self.save_metadata_or_warn(&metadata, &pub_save_path, &password);
+
+        // 由新密钥签名的文件清单，绑定全部输出文件；失败时只警告，不影响已保存的密钥文件
+        let mut manifest_files = vec![pub_save_path.as_path(), private_path.as_path()];
+        if let Some((path, _)) = &extra_private {
+            manifest_files.push(path.as_path());
+        }
+        if let Some(path) = &metadata_path {
+            manifest_files.push(path.as_path());
+        }
+        manifest_files.push(revocation_path.as_path());
+        let manifest_display = match self.write_manifest(&secure_key, &manifest_path, &manifest_files) {
+            Ok(()) => format!("\n签名的文件清单已保存到: {}", manifest_path.display()),
+            Err(e) => {
+                self.ui.show_error(&format!("文件清单写入失败（密钥文件已保存，不受影响）: {}", e));
+                String::new()
+            }
+        };
 
         // 显示成功消息（列出公钥与私钥保存位置）
+        let private_display = match &extra_private {
+            Some((path, _)) => format!("{}\n私钥（已加密，另一格式）已保存到: {}", private_path.display(), path.display()),
+            None => private_path.display().to_string(),
+        };
         self.ui.show_success(&format!(
-            "公钥已保存到: {}\n私钥（已加密）已保存到: {}\n\n请妥善保管您的私钥文件！",
+            "公钥已保存到: {}\n私钥（已加密）已保存到: {}\n吊销证书已保存到: {}{}\n\n请妥善保管您的私钥文件！",
             pub_save_path.display(),
-            private_path.display(),
+            private_display,
+            revocation_path.display(),
+            manifest_display,
+        ));
+
+        self.show_key_summary(&plan, &pub_save_path, &key_usage, &now);
+
+        // 可选：为公钥申请 RFC 3161 时间戳，证明密钥的创建时间；失败不影响已保存的密钥文件
+        if let Some(url) = &self.config.tsa_url
+            && let Err(e) = self.timestamp_public_key(url, &pub_save_path)
+        {
+            self.ui.show_error(&format!("申请时间戳失败: {}", e));
+        }
+
+        // 可选：上传公钥到 HKP 密钥服务器；失败不影响已保存的密钥文件
+        if let Some(url) = &self.config.keyserver_url
+            && self.ui.confirm(&format!("是否将公钥上传到密钥服务器 {}？", url), false)?
+        {
+            match keyserver::upload(url, secure_key.public_cert_armored().as_str()) {
+                Ok(response) => self.ui.show_success(&format!(
+                    "公钥已上传到密钥服务器（HTTP {}）\n{}",
+                    response.status,
+                    response.message,
+                )),
+                Err(e) => self.ui.show_error(&format!("上传公钥失败（本地文件不受影响）: {}", e)),
+            }
+        }
+
+        // 可选：仅导出指纹，供只需确认身份的登记系统使用
+        if self.ui.confirm("是否另存指纹文件 (.fpr)？", false)? {
+            let fpr_path = pub_save_path.with_extension("fpr");
+            fs::write(&fpr_path, pgp::fingerprint_file_content(&secure_key.fingerprint()))?;
+            println!("指纹已保存到: {}", fpr_path.display());
+        }
+
+        // 可选：将全部相关文件打包为加密备份，便于离线保存
+        if self.ui.confirm("是否将公钥、加密私钥、元数据和吊销证书打包为加密备份？", false)? {
+            let backup_name = format!("{}.abubak", template.render(&name_fields, "backup"));
+            let mut files = vec![pub_save_path.as_path(), private_path.as_path()];
+            if let Some(path) = &metadata_path {
+                files.push(path.as_path());
+            }
+            files.push(revocation_path.as_path());
+            if let Err(e) = self.create_backup_bundle(&files, &backup_name) {
+                // 备份失败不影响已保存的密钥文件
+                self.ui.show_error(&format!("创建备份包失败: {}", e));
+            }
+        }
+
+        // 可选：将密码保存到系统钥匙串，解密时无需重新输入
+        if keystore::is_enabled() {
+            println!(
+                "{}",
+                ui::style("安全提示：钥匙串中的密码可被以您身份运行的任何程序读取，请勿在共享电脑上保存。").yellow()
+            );
+            if self.ui.confirm("是否将私钥密码保存到系统钥匙串？", false)? {
+                match keystore::store(&secure_key.fingerprint().to_hex(), &password) {
+                    Ok(()) => println!("密码已保存到系统钥匙串。"),
+                    Err(e) => self.ui.show_error(&e.to_string()),
+                }
+            }
+        }
+
+        // 可选：运行配置的生成后钩子（如上传公钥到登记系统），每次运行前需用户确认
+        if let Some(hook) = &self.config.post_generate_hook {
+            let prompt = format!("是否运行生成后钩子 {}（仅传递公钥路径、指纹与银行名称）？", hook.display());
+            if self.ui.confirm(&prompt, false)? {
+                let fingerprint = secure_key.fingerprint().to_hex();
+                match hook::run_post_generate(hook, &pub_save_path, &fingerprint, &bank_name) {
+                    Ok(()) => self.ui.show_success("生成后钩子已执行"),
+                    // 钩子失败不影响已保存的密钥文件
+                    Err(e) => self.ui.show_error(&e.to_string()),
+                }
+            }
+        }
+
+        // 记录备份状态；未确认备份时，之后每次启动都会提醒
+        if self.config.backup_reminder {
+            let backed_up = self.ui.confirm("您是否已将私钥与吊销证书备份到安全的离线位置？", false)?;
+            if let Err(e) = self.record_backup_state(&secure_key.fingerprint().to_hex(), backed_up) {
+                self.ui.show_error(&format!("无法保存备份提醒状态: {}", e));
+            }
+        }
+
+        self.remembered.take();
+        Ok(())
+    }
+
+    /// 写出各文件 SHA-256 的清单，并用新密钥对清单生成分离签名（`<清单>.asc`，二进制时为 `.sig`）
+    fn write_manifest(
+        &self,
+        secure_key: &security::SecureKey,
+        manifest_path: &std::path::Path,
+        files: &[&std::path::Path],
+    ) -> Result<()> {
+        let manifest = manifest::build(manifest_path, files)?;
+        let armor = self.config.armor_exports();
+        let signature = secure_key.sign_detached(manifest.as_bytes(), armor, self.config.signature_hash)?;
+        fs::write(manifest_path, &manifest)?;
+        fs::write(manifest::signature_path(manifest_path, armor), signature)?;
+        Ok(())
+    }
+
+    /// 为公钥文件申请时间戳，令牌保存在公钥旁（`<公钥>.tst`）
+    fn timestamp_public_key(&self, url: &str, public_path: &std::path::Path) -> Result<()> {
+        let public_key = fs::read(public_path)?;
+        println!("正在向 {} 申请时间戳...", url);
+        let token = tsa::timestamp(url, &public_key)?;
+        let token_path = tsa::token_path(public_path);
+        fs::write(&token_path, &token.token)?;
+        println!("时间戳令牌（{}）已保存到: {}", token.gen_time, token_path.display());
+        Ok(())
+    }
+
+    /// 选择生成使用的曲线，并记住选择以便失败后重试
+    fn select_generation_curve(&self, remembered: &GenerationAnswers) -> Result<security::KeyCurve> {
+        // 选择曲线前先检查当前后端支持情况，避免在生成中途才失败
+        let curves: Vec<_> = security::KeyCurve::ALL
+            .into_iter()
+            .map(|curve| (curve, self.curve_support.supported(curve)))
+            .collect();
+        let curve = self.ui.select_curve(&curves, remembered.curve.unwrap_or(self.config.default_curve))?;
+        self.remembered.borrow_mut().curve = Some(curve);
+        Ok(curve)
+    }
+
+    /// 本地状态文件路径（与配置文件同目录）
+    fn state_path(&self) -> std::path::PathBuf {
+        self.config.config_file.with_file_name(state::STATE_FILE)
+    }
+
+    /// 更新某个密钥的备份确认状态
+    fn record_backup_state(&self, fingerprint: &str, backed_up: bool) -> Result<()> {
+        let path = self.state_path();
+        let mut state = state::State::load(&path)?;
+        state.record(fingerprint, backed_up);
+        state.save(&path)
+    }
+
+    /// 启动时提醒尚未确认备份的密钥，直到用户确认
+    fn backup_reminder(&self) -> Result<()> {
+        let pending = state::State::load(&self.state_path())?.pending_backups();
+        for fingerprint in pending {
+            println!(
+                "{} {}",
+                ui::style("⚠").yellow().bold(),
+                ui::style(format!("密钥 {} 尚未确认已备份，私钥丢失将无法挽回！", fingerprint)).yellow().bold()
+            );
+            if self.ui.confirm("该密钥现已完成备份？", false)? {
+                self.record_backup_state(&fingerprint, true)?;
+            }
+        }
+        Ok(())
+    }
+    
+    /// 在 `base` 旁写出元数据（`.json`，或加密的 `.json.enc`），返回写出的路径
+    ///
+    /// 用户可通过 --no-metadata 关闭元数据输出（避免泄露银行名称与时间）。
+    fn save_metadata(
+        &self,
+        metadata: &KeyMetadata,
+        base: &std::path::Path,
+        password: &str,
+    ) -> Result<Option<std::path::PathBuf>> {
+        let Some(path) = self.metadata_path(base) else {
+            return Ok(None);
+        };
+
+        let metadata_json = if self.config.canonical_metadata {
+            canonical_json(metadata)?
+        } else {
+            serde_json::to_string_pretty(metadata)?
+        };
+        if self.config.encrypt_metadata {
+            // 使用同一密码加密元数据（独立的 salt/nonce），解锁后仍可查看
+            let encrypted = encryption::encrypt_with_password(
+                metadata_json.as_bytes(),
+                password,
+                &self.config.kdf,
+                self.config.cipher,
+            )?;
+            fs::write(&path, encrypted)?;
+        } else {
+            fs::write(&path, metadata_json)?;
+        }
+        Ok(Some(path))
+    }
+
+    /// 元数据中的 `generation_date`；规范模式下与本地时区无关
+    fn metadata_date(&self, timestamp: timestamp::Timestamp) -> String {
+        if self.config.canonical_metadata {
+            timestamp.canonical_rfc3339()
+        } else {
+            timestamp.rfc3339()
+        }
+    }
+
+    /// 写出元数据；失败时只警告并返回 None
+    ///
+    /// 元数据只是辅助信息，调用时密钥文件都已写出，不能让元数据失败使用户误以为整个操作失败。
+    fn save_metadata_or_warn(
+        &self,
+        metadata: &KeyMetadata,
+        base: &std::path::Path,
+        password: &str,
+    ) -> Option<std::path::PathBuf> {
+        match self.save_metadata(metadata, base, password) {
+            Ok(path) => path,
+            Err(e) => {
+                self.ui.show_error(&format!("元数据写入失败（密钥文件已保存，不受影响）: {}", e));
+                None
+            }
+        }
+    }
+
+    /// 元数据将写出的路径；关闭元数据输出时为 None
+    fn metadata_path(&self, base: &std::path::Path) -> Option<std::path::PathBuf> {
+        if !self.config.write_metadata {
+            return None;
+        }
+        Some(base.with_extension(if self.config.encrypt_metadata { "json.enc" } else { "json" }))
+    }
+
+    /// 选择私钥加密强度：沿用配置、预设级别（按本机速度推荐）或手动设置，返回参数与写入元数据的名称
+    fn select_kdf(&self) -> Result<(security::KdfParams, &'static str)> {
+        let estimates = security::KdfLevel::ALL
+            .into_iter()
+            .map(|level| Ok((level, security::estimate_kdf_time(&level.params())?)))
+            .collect::<Result<Vec<_>>>()?;
+        let recommended = security::recommend_kdf_level(&estimates, KDF_RECOMMEND_TARGET);
+        let configured = self.config.kdf;
+        let mut items = vec![format!("沿用当前配置（{}，{} 次迭代）", configured.hash.name(), configured.iterations)];
+        for (level, time) in &estimates {
+            let params = level.params();
+            items.push(format!(
+                "{}（{}，{} 次迭代，本机约 {:.1} 秒）{}",
+                level.name(),
+                params.hash.name(),
+                params.iterations,
+                time.as_secs_f64(),
+                if *level == recommended { " 推荐" } else { "" },
+            ));
+        }
+        items.push("高级：手动设置哈希与迭代次数".to_string());
+
+        let selection = self.ui.select_index("选择私钥加密强度（解锁越慢越能抵御暴力破解）", &items)?;
+        match selection {
+            0 => Ok((configured, KDF_LEVEL_CONFIGURED)),
+            index if index <= security::KdfLevel::ALL.len() => {
+                let level = security::KdfLevel::ALL[index - 1];
+                Ok((level.params(), level.name()))
+            }
+            _ => {
+                let hashes = [security::KdfHash::Sha256, security::KdfHash::Sha512];
+                let names: Vec<String> = hashes.iter().map(|hash| hash.name().to_string()).collect();
+                let hash = hashes[self.ui.select_index("选择 PBKDF2 哈希", &names)?];
+                let value = self.ui.input_validated("PBKDF2 迭代次数", &configured.iterations.to_string(), |text| {
+                    let iterations = text.trim().parse().map_err(|_| anyhow::anyhow!("请输入整数"))?;
+                    config::parse_iterations(iterations).map(|_| ())
+                })?;
+                Ok((security::KdfParams { hash, iterations: value.parse()? }, KDF_LEVEL_CUSTOM))
+            }
+        }
+    }
+
+    /// 加密私钥（OpenPGP secret bytes，未加密）
+    fn export_and_encrypt_private_key(
+        &self,
+        private_key_bytes: &[u8],
+        password: &str,
+        kdf: &security::KdfParams,
+    ) -> Result<Vec<u8>> {
+        // 配置了托管恢复证书时，数据密钥同时封装给密码与恢复证书
+        match &self.config.recovery_cert {
+            Some(path) => {
+                let recovery_cert = pgp::parse_cert(&commands::read_key_file(path)?)?;
+                encryption::encrypt_with_password_and_recovery(
+                    private_key_bytes,
+                    password,
+                    kdf,
+                    self.config.cipher,
+                    &recovery_cert,
+                )
+            }
+            None => encryption::encrypt_with_password(private_key_bytes, password, kdf, self.config.cipher),
+        }
+    }
+
+    /// 将生成的相关文件打包为单个加密备份
+    fn create_backup_bundle(&self, files: &[&std::path::Path], default_name: &str) -> Result<()> {
+        let mut entries = Vec::with_capacity(files.len());
+        for path in files {
+            let name = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .ok_or_else(|| anyhow::anyhow!("无效的文件名: {}", path.display()))?;
+            entries.push((name.to_string(), commands::read_key_file(path)?));
+        }
+
+        let password = self.ui.input_password("请为备份包设置密码（输入时不可见）", true)?;
+        let bundle = backup::pack(&entries)?;
+        let encrypted = encryption::encrypt_with_password(&bundle, &password, &self.config.kdf, self.config.cipher)?;
+
+        let save_path = self.ui.select_save_location(default_name)?;
+        fs::write(&save_path, encrypted)?;
+
+        self.ui.show_success(&format!(
+            "备份包已保存到: {}（包含 {} 个文件）",
+            save_path.display(),
+            entries.len(),
         ));
+        Ok(())
+    }
+
+    /// 使用与加密私钥相同的密码方案（KDF + AES-GCM，带版本文件头）加密任意文件
+    fn encrypt_file_flow(&self) -> Result<()> {
+        let path = self.ui.select_open_file("选择要加密的文件")?;
+        let data = zeroize::Zeroizing::new(commands::read_data_file(&path)?);
+        let password = zeroize::Zeroizing::new(self.ui.input_password("请为该文件设置密码（输入时不可见）", true)?);
 
-        self.show_key_summary(&bank_name, &pub_save_path);
+        let encrypted = encryption::encrypt_with_password(&data, &password, &self.config.kdf, self.config.cipher)?;
 
+        let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("file");
+        let save_path = self.ui.select_save_location(&format!("{}.{}", file_name, ENCRYPTED_FILE_EXTENSION))?;
+        fs::write(&save_path, encrypted)?;
+        self.ui.show_success(&format!("加密文件已保存到: {}", save_path.display()));
         Ok(())
     }
-    
-    /// 导出并加密私钥
-    fn export_and_encrypt_private_key(
-        &self,
-        secure_key: &security::SecureKey,
-        password: &str,
-    ) -> Result<Vec<u8>> {
-        use encryption::aes_gcm_encrypt;
 
-        // 生成盐值
-        let mut salt = [0u8; 16];
-        let mut rng = rand::rngs::OsRng;
-        rng.fill_bytes(&mut salt);
+    /// 解密由“用密码加密任意文件”生成的文件
+    fn decrypt_file_flow(&self) -> Result<()> {
+        let path = self.ui.select_open_file("选择要解密的文件 (.abuenc)")?;
+        let data = commands::read_data_file(&path)?;
+        let password = zeroize::Zeroizing::new(self.ui.input_password("请输入该文件的密码（输入时不可见）", false)?);
 
-        // 派生加密密钥
-        let encryption_key = security::SecureKey::derive_encryption_key(password, &salt)?;
+        let plaintext = zeroize::Zeroizing::new(encryption::decrypt_with_password(&data, &password)?);
 
-        // 导出私钥为 OpenPGP secret bytes（未加密）
-        let private_key_bytes = secure_key.secret_key_bytes();
+        let default_name = match path.file_stem().and_then(|n| n.to_str()) {
+            Some(stem) if path.extension().is_some_and(|ext| ext == ENCRYPTED_FILE_EXTENSION) => stem.to_string(),
+            _ => format!("{}.dec", path.file_name().and_then(|n| n.to_str()).unwrap_or("file")),
+        };
+        let save_path = self.ui.select_save_location(&default_name)?;
+        fs::write(&save_path, &*plaintext)?;
+        self.ui.show_success(&format!("解密后的文件已保存到: {}", save_path.display()));
+        Ok(())
+    }
+
+    /// 解密备份包并将其中的文件解出到指定目录
+    fn extract_backup_flow(&self) -> Result<()> {
+        let path = self.ui.select_open_file("选择要解出的备份包 (.abubak)")?;
+        let data = commands::read_key_file(&path)?;
+        let password = self.ui.input_password("请输入备份包密码（输入时不可见）", false)?;
 
-        // 加密私钥
-        let (ciphertext, nonce) = aes_gcm_encrypt(&private_key_bytes, &encryption_key)?;
+        let bundle = encryption::decrypt_with_password(&data, &password)?;
+        let entries = backup::unpack(&bundle)?;
 
-        // 组合数据：盐 + nonce + 密文
-        let mut encrypted_data = Vec::new();
-        encrypted_data.extend_from_slice(&salt);
-        encrypted_data.extend_from_slice(&nonce);
-        encrypted_data.extend_from_slice(&ciphertext);
+        let dir = self.ui.select_directory("选择解出文件的目录")?;
+        for (name, content) in &entries {
+            // 只取文件名部分，防止备份包中的路径跳出目标目录
+            let file_name = std::path::Path::new(name)
+                .file_name()
+                .ok_or_else(|| anyhow::anyhow!("备份包中存在无效的文件名: {}", name))?;
+            let target = dir.join(file_name);
+            if target.exists() {
+                return Err(anyhow::anyhow!("目标文件已存在: {}", target.display()));
+            }
+            fs::write(&target, content)?;
+            println!("已解出: {}", target.display());
+        }
 
-        Ok(encrypted_data)
+        self.ui.show_success(&format!("已从备份包解出 {} 个文件", entries.len()));
+        Ok(())
     }
     
+    /// 写入文件前显示将要创建的内容，由用户确认；拒绝时调用方不写入任何文件
+    fn confirm_generation_plan(&self, plan: &GenerationPlan) -> Result<bool> {
+        println!();
+        println!("{}", ui::style("即将写入以下内容，请核对：").bold());
+        println!("🏦 银行/玩家名: {}", plan.bank_name);
+        if plan.user_ids.is_empty() {
+            println!("👤 User ID: 无（匿名证书）");
+        }
+        for user_id in plan.user_ids {
+            println!("👤 User ID: {}", user_id);
+        }
+        println!("🔐 密钥类型: {}", plan.key_type);
+        match plan.expiry_days {
+            Some(days) => println!("⌛ 有效期: {} 天", days),
+            None => println!("⌛ 有效期: 永不过期"),
+        }
+        for path in plan.outputs {
+            println!("📁 {}", path.display());
+        }
+        println!();
+        self.ui.confirm("确认写入以上文件？", true)
+    }
+
     /// 显示密钥摘要
-    fn show_key_summary(&self, bank_name: &str, path: &std::path::Path) {
+    fn show_key_summary(
+        &self,
+        plan: &GenerationPlan,
+        path: &std::path::Path,
+        key_usage: &[pgp::KeyUsage],
+        generated_at: &timestamp::Timestamp,
+    ) {
         println!();
         println!("{}", ui::style("══════════════════════════════════════════").cyan());
         println!("{}", ui::style("              密钥生成摘要                ").bold());
         println!("{}", ui::style("══════════════════════════════════════════").cyan());
-        println!("🏦 银行/玩家名: {}", ui::style(bank_name).bold());
+        if self.config.test_key {
+            println!("{}", ui::style("⚠ 测试密钥 — 切勿用于生产环境！").red().bold());
+        }
+        println!("🏦 银行/玩家名: {}", ui::style(plan.bank_name).bold());
+        println!("👤 主 User ID: {}", plan.user_ids.first().map_or(pgp::NO_USER_ID, String::as_str));
         println!("📁 密钥文件: {}", ui::style(path.display()).bold());
-        println!("🔐 密钥类型: ECC P-256 (椭圆曲线加密)");
-        println!("📅 生成时间: {}", Local::now().format("%Y-%m-%d %H:%M:%S"));
+        println!("🔐 密钥类型: {}", plan.key_type);
+        for usage in key_usage {
+            let role = if usage.primary { "主密钥" } else { "子密钥" };
+            let flags = if usage.flags.is_empty() { "无".to_string() } else { usage.flags.join(" / ") };
+            let expires = usage.expires.as_deref().unwrap_or("永不过期");
+            println!(
+                "🔑 {} {}: {}（过期: {}）",
+                role,
+                &usage.fingerprint[usage.fingerprint.len() - 16..],
+                flags,
+                expires,
+            );
+        }
+        match plan.expiry_days {
+            Some(days) => println!("⌛ 有效期: {} 天", days),
+            None => println!("⌛ 有效期: 永不过期"),
+        }
+        println!("📅 生成时间: {}", generated_at.display());
         println!("{}", ui::style("══════════════════════════════════════════").cyan());
         println!();
         
@@ -224,12 +2091,43 @@ impl KeyGenerator {
     /// 运行主程序
     pub fn run(&self) -> Result<()> {
         self.ui.show_welcome();
+        if let Some(backend) = &self.backend {
+            println!("🔧 加密后端: {}", ui::style(&backend.name).bold());
+            println!();
+        }
+        if let Some(url) = &self.config.time_source_url {
+            match clock::check(url) {
+                Ok(Some(skew)) => {
+                    self.ui.show_clock_skew_warning(skew);
+                    self.clock_skew.set(Some(skew));
+                }
+                Ok(None) => {}
+                Err(e) => self.ui.show_error(&format!("无法检查系统时钟: {}", e)),
+            }
+        }
+        #[cfg(not(feature = "remote-keygen"))]
+        if self.config.remote_keygen_url.is_some() {
+            self.ui.show_error("此构建未启用远程密钥生成支持（remote-keygen），已忽略 remote_keygen_url");
+        }
+        if self.config.backup_reminder
+            && let Err(e) = self.backup_reminder()
+        {
+            self.ui.show_error(&format!("读取备份提醒状态失败: {}", e));
+        }
         
         loop {
             match self.ui.select_operation()? {
                 ui::Operation::Generate => {
+                    // 成功时由 generate_keys 清空记住的回答；失败时保留，重试时作为默认值
                     if let Err(e) = self.generate_keys() {
                         self.ui.show_error(&format!("生成失败: {}", e));
+                        println!("已记住本次输入的名称、邮箱与曲线，重新生成时将作为默认值。");
+                    }
+                }
+                #[cfg(feature = "remote-keygen")]
+                ui::Operation::RemoteGenerate => {
+                    if let Err(e) = self.remote_generate_flow() {
+                        self.ui.show_error(&format!("远程生成失败: {}", e));
                     }
                 }
                 ui::Operation::Decrypt => {
@@ -237,17 +2135,158 @@ impl KeyGenerator {
                         self.ui.show_error(&format!("解密失败: {}", e));
                     }
                 }
+                ui::Operation::Compare => {
+                    if let Err(e) = self.compare_keys_flow() {
+                        self.ui.show_error(&format!("比较失败: {}", e));
+                    }
+                }
+                ui::Operation::VerifyPair => {
+                    if let Err(e) = self.verify_pair_flow() {
+                        self.ui.show_error(&format!("配对验证失败: {}", e));
+                    }
+                }
+                ui::Operation::VerifyManifest => {
+                    if let Err(e) = self.verify_manifest_flow() {
+                        self.ui.show_error(&format!("清单校验失败: {}", e));
+                    }
+                }
+                ui::Operation::VerifySignature => {
+                    if let Err(e) = self.verify_signature_flow() {
+                        self.ui.show_error(&format!("签名验证失败: {}", e));
+                    }
+                }
+                ui::Operation::StripSecret => {
+                    if let Err(e) = self.strip_secret_flow() {
+                        self.ui.show_error(&format!("导出公钥失败: {}", e));
+                    }
+                }
+                ui::Operation::ExtractBackup => {
+                    if let Err(e) = self.extract_backup_flow() {
+                        self.ui.show_error(&format!("解出备份失败: {}", e));
+                    }
+                }
+                ui::Operation::EncryptFile => {
+                    if let Err(e) = self.encrypt_file_flow() {
+                        self.ui.show_error(&format!("加密文件失败: {}", e));
+                    }
+                }
+                ui::Operation::DecryptFile => {
+                    if let Err(e) = self.decrypt_file_flow() {
+                        self.ui.show_error(&format!("解密文件失败: {}", e));
+                    }
+                }
+                ui::Operation::RecoverPassword => {
+                    if let Err(e) = self.recover_password_flow() {
+                        self.ui.show_error(&format!("密码恢复失败: {}", e));
+                    }
+                }
+                ui::Operation::ChangePrimaryUserId => {
+                    if let Err(e) = self.change_primary_userid_flow() {
+                        self.ui.show_error(&format!("更换主 User ID 失败: {}", e));
+                    }
+                }
+                ui::Operation::ChangePassword => {
+                    if let Err(e) = self.change_password_flow() {
+                        self.ui.show_error(&format!("修改密码失败: {}", e));
+                    }
+                }
+                ui::Operation::RecoveryDecrypt => {
+                    if let Err(e) = self.recovery_decrypt_flow() {
+                        self.ui.show_error(&format!("恢复解密失败: {}", e));
+                    }
+                }
+                ui::Operation::ShowKeyIds => {
+                    if let Err(e) = self.show_key_ids_flow() {
+                        self.ui.show_error(&format!("读取密钥 ID 失败: {}", e));
+                    }
+                }
+                ui::Operation::ViewMetadata => {
+                    if let Err(e) = self.view_metadata_flow() {
+                        self.ui.show_error(&format!("查看元数据失败: {}", e));
+                    }
+                }
+                ui::Operation::ExportFingerprint => {
+                    if let Err(e) = self.export_fingerprint_flow() {
+                        self.ui.show_error(&format!("导出指纹失败: {}", e));
+                    }
+                }
+                ui::Operation::ExportWkd => {
+                    if let Err(e) = self.export_wkd_flow() {
+                        self.ui.show_error(&format!("导出 WKD 失败: {}", e));
+                    }
+                }
+                ui::Operation::ExportSpki => {
+                    if let Err(e) = self.export_spki_flow() {
+                        self.ui.show_error(&format!("导出 SPKI 公钥失败: {}", e));
+                    }
+                }
+                ui::Operation::ExportJson => {
+                    if let Err(e) = self.export_json_flow() {
+                        self.ui.show_error(&format!("导出 JSON 失败: {}", e));
+                    }
+                }
+                #[cfg(feature = "card")]
+                ui::Operation::ExportCard => {
+                    if let Err(e) = self.export_card_flow() {
+                        self.ui.show_error(&format!("导出公钥卡片失败: {}", e));
+                    }
+                }
+                #[cfg(feature = "recovery-sheet")]
+                ui::Operation::ExportRecoverySheet => {
+                    if let Err(e) = self.export_recovery_sheet_flow() {
+                        self.ui.show_error(&format!("导出恢复单失败: {}", e));
+                    }
+                }
+                #[cfg(feature = "card")]
+                ui::Operation::ExportRevocationQr => {
+                    if let Err(e) = self.export_revocation_qr_flow() {
+                        self.ui.show_error(&format!("导出吊销证书二维码失败: {}", e));
+                    }
+                }
+                ui::Operation::ImportRevocationQr => {
+                    if let Err(e) = self.import_revocation_qr_flow() {
+                        self.ui.show_error(&format!("重组吊销证书失败: {}", e));
+                    }
+                }
+                ui::Operation::PanicWipe => {
+                    if let Err(e) = self.panic_wipe_flow() {
+                        self.ui.show_error(&format!("紧急销毁失败: {}", e));
+                    }
+                }
+                ui::Operation::Settings => {
+                    if let Err(e) = self.settings_flow() {
+                        self.ui.show_error(&format!("设置失败: {}", e));
+                    }
+                }
+                ui::Operation::CertifyKey => {
+                    if let Err(e) = self.certify_key_flow() {
+                        self.ui.show_error(&format!("认证失败: {}", e));
+                    }
+                }
+                ui::Operation::TransitionStatement => {
+                    if let Err(e) = self.transition_statement_flow() {
+                        self.ui.show_error(&format!("生成迁移声明失败: {}", e));
+                    }
+                }
+                ui::Operation::ImportGnupg => {
+                    if let Err(e) = self.import_gnupg_key_flow() {
+                        self.ui.show_error(&format!("导入失败: {}", e));
+                    }
+                }
+                ui::Operation::Diagnose => {
+                    if let Err(e) = self.diagnose_file_flow() {
+                        self.ui.show_error(&format!("诊断失败: {}", e));
+                    }
+                }
                 ui::Operation::Exit => {
                     println!("感谢使用ABU密钥生成器");
                     break;
                 }
             }
             
-            // 询问是否继续
-            if !dialoguer::Confirm::new()
-                .with_prompt("是否继续执行其他操作？")
-                .default(true)
-                .interact()?
+            // 询问是否继续（--once 时执行一个操作后直接退出）
+            if self.config.once
+                || !self.ui.confirm("是否继续执行其他操作？", self.config.continue_by_default)?
             {
                 println!("感谢使用ABU密钥生成器");
                 break;
@@ -258,13 +2297,761 @@ impl KeyGenerator {
     }
 }
 
+/// 校验配置文件并报告全部问题后退出（--check-config）
+fn check_config(path: &std::path::Path) -> ! {
+    match config::check_file(path) {
+        Ok(problems) if problems.is_empty() => {
+            println!("配置文件有效: {}", path.display());
+            std::process::exit(0);
+        }
+        Ok(problems) => {
+            eprintln!("配置文件 {} 有 {} 个问题:", path.display(), problems.len());
+            for problem in &problems {
+                eprintln!("  - {}", problem);
+            }
+            std::process::exit(error::exit_code::GENERAL);
+        }
+        Err(e) => {
+            eprintln!("配置错误: {}", e);
+            std::process::exit(error::exit_code::GENERAL);
+        }
+    }
+}
+
 fn main() -> Result<()> {
-    let generator = KeyGenerator::new();
+    // 配置加载顺序：默认值 → 配置文件 → ABU_* 环境变量 → 命令行参数
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match cli::check_config_path(&args) {
+        Ok(Some(path)) => check_config(&path),
+        Ok(None) => {}
+        Err(e) => {
+            eprintln!("参数错误: {}", e);
+            std::process::exit(error::exit_code::GENERAL);
+        }
+    }
+    let mut config = match cli::config_path(&args).and_then(|path| config::Config::load(path.as_deref())) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("配置错误: {}", e);
+            std::process::exit(error::exit_code::GENERAL);
+        }
+    };
+    let command = match cli::parse(&mut config, args) {
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("参数错误: {}", e);
+            std::process::exit(error::exit_code::GENERAL);
+        }
+    };
+    timing::set_verbose(config.verbose);
+    if let Err(e) = logging::init(config.log_level.as_deref()) {
+        eprintln!("参数错误: {}", e);
+        std::process::exit(error::exit_code::GENERAL);
+    }
+    if let Some(warning) = config.cipher.weakness_warning() {
+        eprintln!("{} {}", ui::style("⚠").yellow().bold(), ui::style(warning).yellow());
+    }
+
+    // 非交互命令：不显示菜单与文件对话框
+    match &command {
+        cli::Command::Sign { key, password_file, signer, inputs, digest, allow_expired } => {
+            let armor = config.armor_exports();
+            let hash = config.signature_hash;
+            let password_file = password_file.as_deref();
+            let result = if inputs.is_empty() {
+                let digest = digest.as_ref();
+                commands::sign_to_stdout(key, password_file, signer.as_ref(), armor, hash, digest, *allow_expired)
+            } else {
+                commands::sign_files(key, password_file, signer.as_ref(), armor, hash, inputs, *allow_expired)
+            };
+            if let Err(e) = result {
+                eprintln!("签名失败: {}", e);
+                std::process::exit(error::exit_code_for(&e));
+            }
+            return Ok(());
+        }
+        cli::Command::DumpHeader { path } => {
+            if let Err(e) = commands::dump_header(path) {
+                eprintln!("读取文件头失败: {}", e);
+                std::process::exit(error::exit_code_for(&e));
+            }
+            return Ok(());
+        }
+        cli::Command::SelfTest => {
+            if let Err(e) = commands::self_test() {
+                eprintln!("自检失败: {}", e);
+                std::process::exit(error::exit_code_for(&e));
+            }
+            return Ok(());
+        }
+        cli::Command::Batch { input, password_file, report } => {
+            if let Err(e) = batch::run(&config, input, password_file.as_deref(), report.as_deref()) {
+                eprintln!("批量生成失败: {}", e);
+                std::process::exit(error::exit_code_for(&e));
+            }
+            return Ok(());
+        }
+        cli::Command::Job { input, password_file } => {
+            if let Err(e) = job::run(&config, input, password_file.as_deref()) {
+                eprintln!("作业执行失败: {}", e);
+                std::process::exit(error::exit_code_for(&e));
+            }
+            return Ok(());
+        }
+        cli::Command::Interactive => {}
+    }
+
+    // 启动时检测加密后端，失败时给出可操作的提示，仍允许使用不依赖后端的功能
+    let backend = match security::check_backend() {
+        Ok(info) => Some(info),
+        Err(e) => {
+            ui::show_backend_error(&e);
+            None
+        }
+    };
+
+    // test-ui 构建：设置 ABU_UI_SCRIPT 时按脚本应答驱动整个交互会话
+    #[cfg(feature = "test-ui")]
+    let generator = match std::env::var_os(ui::SCRIPT_ENV) {
+        Some(path) => match ui::read_script(std::path::Path::new(&path)) {
+            Ok(responses) => KeyGenerator::with_ui(ui::UserInterface::with_script(responses), backend, config),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(error::exit_code::GENERAL);
+            }
+        },
+        None => KeyGenerator::new(backend, config),
+    };
+    #[cfg(not(feature = "test-ui"))]
+    let generator = KeyGenerator::new(backend, config);
+
+    // 交互会话中 Ctrl-C 只取消当前密码输入，返回主菜单
+    if let Err(e) = ui::install_interrupt_handler() {
+        eprintln!("{}", e);
+    }
     
     if let Err(e) = generator.run() {
         eprintln!("程序错误: {}", e);
-        std::process::exit(1);
+        std::process::exit(error::exit_code_for(&e));
     }
     
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 测试用配置：降低迭代次数以加快测试，不写备份提醒状态，不检查版本库
+    fn test_config() -> config::Config {
+        config::Config {
+            kdf: security::KdfParams { hash: security::KdfHash::Sha256, iterations: config::MIN_ITERATIONS },
+            backup_reminder: false,
+            vcs_markers: Vec::new(),
+            ..Default::default()
+        }
+    }
+
+    fn sample_metadata() -> KeyMetadata {
+        KeyMetadata {
+            bank_name: "TestBank".to_string(),
+            user_id: "TestBank <bank@example.com>".to_string(),
+            generation_date: "2026-01-01T00:00:00Z".to_string(),
+            key_type: "ECDSA P-256".to_string(),
+            key_size: 256,
+            key_usage: Vec::new(),
+            abu_version: "1.0".to_string(),
+            notes: "Alpha Coin Banking System".to_string(),
+            test_key: false,
+            kdf_level: KDF_LEVEL_CONFIGURED.to_string(),
+            designated_revoker: None,
+            profile: None,
+        }
+    }
+
+    /// 一次完整生成的脚本应答：接受警告、P-256、不添加其他 User ID、确认写入、不导出指纹与备份
+    #[cfg(feature = "test-ui")]
+    fn generation_script(public_path: &std::path::Path) -> Vec<String> {
+        let password = "Correct-Horse-Battery-42";
+        [
+            "y",
+            "TestBank",
+            password,
+            password,
+            "0",
+            "bank@example.com",
+            "n",
+            "",
+            &public_path.display().to_string(),
+            "y",
+            "n",
+            "n",
+        ]
+        .map(str::to_string)
+        .to_vec()
+    }
+
+    #[cfg(feature = "test-ui")]
+    fn scripted_generator(config: config::Config, script: Vec<String>) -> KeyGenerator {
+        KeyGenerator::with_ui(ui::UserInterface::with_script(script), security::check_backend().ok(), config)
+    }
+
+    /// 目录中的文件名
+    #[cfg(feature = "test-ui")]
+    fn file_names(dir: &std::path::Path) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn no_metadata_writes_no_json() {
+        let dir = testutil::temp_dir("no-metadata-save");
+        let generator = KeyGenerator::new(None, config::Config { write_metadata: false, ..test_config() });
+
+        let written = generator.save_metadata(&sample_metadata(), &dir.join("bank_public.asc"), "password").unwrap();
+
+        assert_eq!(written, None);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+    }
+
+    #[cfg(feature = "test-ui")]
+    #[test]
+    fn no_metadata_generation_still_writes_keys() {
+        let dir = testutil::temp_dir("no-metadata-generate");
+        let public_path = dir.join("bank_public.asc");
+        let config = config::Config { write_metadata: false, ..test_config() };
+
+        scripted_generator(config, generation_script(&public_path)).generate_keys().unwrap();
+
+        let names = file_names(&dir);
+        assert!(public_path.is_file());
+        assert!(names.iter().any(|name| name.contains("_private_")));
+        assert!(names.iter().any(|name| name.contains("_revocation_")));
+        assert!(!names.iter().any(|name| name.ends_with(".json")));
+    }
+
+    #[test]
+    fn encrypted_metadata_decrypts_to_original_json() {
+        let dir = testutil::temp_dir("encrypted-metadata");
+        let generator = KeyGenerator::new(None, config::Config { encrypt_metadata: true, ..test_config() });
+        let metadata = sample_metadata();
+
+        let path = generator.save_metadata(&metadata, &dir.join("bank_public.asc"), "password").unwrap().unwrap();
+        let decrypted = encryption::decrypt_with_password(&fs::read(&path).unwrap(), "password").unwrap();
+
+        assert!(path.to_string_lossy().ends_with(".json.enc"));
+        assert_eq!(String::from_utf8(decrypted).unwrap(), serde_json::to_string_pretty(&metadata).unwrap());
+    }
+
+    #[cfg(feature = "test-ui")]
+    #[test]
+    fn imported_gnupg_key_decrypts_to_same_cert() {
+        let dir = testutil::temp_dir("import-gnupg");
+        let options = security::KeyOptions::default();
+        let key = security::SecureKey::generate(&["Imported Bank <bank@example.com>".to_string()], &options).unwrap();
+        let original = pgp::parse_cert(&key.secret_key_bytes()).unwrap();
+        let protected = pgp::protect_secrets(original.clone(), "gnupg passphrase").unwrap();
+        let tsk_path = dir.join("exported.asc");
+        fs::write(&tsk_path, pgp::export_tsk(&protected, true, &pgp::ArmorOptions::default()).unwrap()).unwrap();
+        let save_path = dir.join("imported.bin");
+        let password = "Correct-Horse-Battery-42";
+        let script = [&tsk_path.display().to_string(), "gnupg passphrase", password, password, &save_path.display().to_string()]
+            .map(str::to_string)
+            .to_vec();
+
+        scripted_generator(test_config(), script).import_gnupg_key_flow().unwrap();
+
+        let plaintext = encryption::decrypt_with_password(&fs::read(&save_path).unwrap(), password).unwrap();
+        let imported = commands::parse_decrypted_tsk(&plaintext).unwrap();
+        assert_eq!(imported.fingerprint(), original.fingerprint());
+        assert!(!pgp::has_encrypted_secrets(&imported));
+        assert_eq!(
+            imported.keys().secret().count(),
+            original.keys().secret().count(),
+        );
+    }
+
+    #[cfg(feature = "test-ui")]
+    #[test]
+    fn public_path_colliding_with_private_path_writes_nothing() {
+        let dir = testutil::temp_dir("path-collision");
+        // 不含时间的模板下，私钥文件名固定为 TestBank_private.bin；把公钥也保存到这里
+        let public_path = dir.join("TestBank_private.bin");
+        let config = config::Config {
+            name_template: naming::NameTemplate::parse("{bank}_{kind}").unwrap(),
+            ..test_config()
+        };
+
+        let error = scripted_generator(config, generation_script(&public_path)).generate_keys().unwrap_err();
+
+        assert!(error.to_string().contains("路径冲突"));
+        assert!(file_names(&dir).is_empty());
+    }
+
+    #[cfg(feature = "test-ui")]
+    #[test]
+    fn encrypted_file_round_trips_and_rejects_wrong_password() {
+        let dir = testutil::temp_dir("encrypt-file");
+        let original = dir.join("ledger.csv");
+        fs::write(&original, b"account,balance\nalpha,100\n").unwrap();
+        let encrypted = dir.join(format!("ledger.csv.{}", ENCRYPTED_FILE_EXTENSION));
+        let decrypted = dir.join("ledger.decrypted.csv");
+        let password = "Correct-Horse-Battery-42";
+        let path = |path: &std::path::Path| path.display().to_string();
+
+        let script = vec![path(&original), password.to_string(), password.to_string(), path(&encrypted)];
+        scripted_generator(test_config(), script).encrypt_file_flow().unwrap();
+        let blob = fs::read(&encrypted).unwrap();
+        assert!(blob.starts_with(encryption::BLOB_MAGIC));
+        assert!(!blob.windows(7).any(|window| window == b"balance"));
+
+        let script = vec![path(&encrypted), "Wrong-Horse-Battery-42".to_string(), path(&decrypted)];
+        assert!(scripted_generator(test_config(), script).decrypt_file_flow().is_err());
+        assert!(!decrypted.exists());
+
+        let script = vec![path(&encrypted), password.to_string(), path(&decrypted)];
+        scripted_generator(test_config(), script).decrypt_file_flow().unwrap();
+        assert_eq!(fs::read(&decrypted).unwrap(), fs::read(&original).unwrap());
+    }
+
+    #[cfg(feature = "test-ui")]
+    #[test]
+    fn new_primary_user_id_must_meet_domain_policy() {
+        let dir = testutil::temp_dir("change-userid-domain");
+        scripted_generator(test_config(), generation_script(&dir.join("bank_public.asc"))).generate_keys().unwrap();
+        let private = dir.join(file_names(&dir).into_iter().find(|name| name.contains("_private_")).unwrap());
+        let before = fs::read(&private).unwrap();
+
+        let config = config::Config { allowed_email_domains: vec!["example.com".to_string()], ..test_config() };
+        let script = [&private.display().to_string(), "Correct-Horse-Battery-42", "New Bank", "bank@other.example"];
+        let error = scripted_generator(config, script.map(str::to_string).to_vec())
+            .change_primary_userid_flow()
+            .unwrap_err();
+
+        assert!(error.to_string().contains("other.example"), "{}", error);
+        assert_eq!(fs::read(&private).unwrap(), before);
+    }
+
+    #[cfg(feature = "test-ui")]
+    #[test]
+    fn failed_generation_offers_answers_as_defaults() {
+        let dir = testutil::temp_dir("remembered-answers");
+        let config = config::Config {
+            name_template: naming::NameTemplate::parse("{bank}_{kind}").unwrap(),
+            ..test_config()
+        };
+        // 第一次：公钥保存到私钥的文件名上，写入前因路径冲突失败
+        let mut script = generation_script(&dir.join("TestBank_private.bin"));
+        script.truncate(9);
+        // 第二次：名称与邮箱留空，接受记住的默认值
+        let mut retry = generation_script(&dir.join("retry_public.asc"));
+        retry[1] = String::new();
+        retry[5] = String::new();
+        script.extend(retry);
+        let generator = scripted_generator(config, script);
+
+        assert!(generator.generate_keys().is_err());
+        {
+            let remembered = generator.remembered.borrow();
+            assert_eq!(remembered.bank_name.as_deref(), Some("TestBank"));
+            assert_eq!(remembered.email.as_deref(), Some("bank@example.com"));
+        }
+
+        generator.generate_keys().unwrap();
+        let cert = pgp::parse_cert(&fs::read(dir.join("retry_public.asc")).unwrap()).unwrap();
+        assert_eq!(pgp::primary_userid(&cert).as_deref(), Some("TestBank <bank@example.com>"));
+        assert!(dir.join("TestBank_private.bin").is_file());
+    }
+
+    #[cfg(feature = "test-ui")]
+    #[test]
+    fn both_private_key_formats_decrypt_to_the_same_key() {
+        let dir = testutil::temp_dir("private-both-formats");
+        let config = config::Config { private_key_both_formats: true, ..test_config() };
+        scripted_generator(config, generation_script(&dir.join("bank_public.asc"))).generate_keys().unwrap();
+
+        let private: Vec<String> = file_names(&dir).into_iter().filter(|name| name.contains("_private_")).collect();
+        let binary = private.iter().find(|name| name.ends_with(".bin")).expect("binary private key");
+        let armored = private.iter().find(|name| name.ends_with(".asc")).expect("armored private key");
+        let binary = fs::read(dir.join(binary)).unwrap();
+        let armored = fs::read(dir.join(armored)).unwrap();
+        assert!(binary.starts_with(encryption::BLOB_MAGIC));
+        assert!(encryption::is_armored_blob(&String::from_utf8(armored.clone()).unwrap()));
+
+        let password = "Correct-Horse-Battery-42";
+        let from_binary = encryption::decrypt_with_password(&binary, password).unwrap();
+        let from_armored = encryption::decrypt_with_password(&armored, password).unwrap();
+        assert_eq!(from_binary, from_armored);
+    }
+
+    #[cfg(feature = "test-ui")]
+    #[test]
+    fn metadata_write_failure_keeps_generated_keys() {
+        let dir = testutil::temp_dir("metadata-write-failure");
+        let public_path = dir.join("bank_public.asc");
+        // 元数据路径被目录占用，写入必然失败
+        let metadata_path = public_path.with_extension("json");
+        fs::create_dir(&metadata_path).unwrap();
+
+        scripted_generator(test_config(), generation_script(&public_path)).generate_keys().unwrap();
+
+        let names = file_names(&dir);
+        assert!(public_path.is_file());
+        assert!(names.iter().any(|name| name.contains("_private_")));
+        assert!(names.iter().any(|name| name.contains("_revocation_")));
+        assert!(metadata_path.is_dir());
+    }
+
+    #[test]
+    fn metadata_write_failure_is_reported_as_missing_metadata() {
+        let dir = testutil::temp_dir("metadata-write-warn");
+        let base = dir.join("bank_public.asc");
+        fs::create_dir(base.with_extension("json")).unwrap();
+        let generator = KeyGenerator::new(None, test_config());
+
+        assert!(generator.save_metadata(&sample_metadata(), &base, "password").is_err());
+        assert_eq!(generator.save_metadata_or_warn(&sample_metadata(), &base, "password"), None);
+    }
+
+    #[cfg(feature = "test-ui")]
+    #[test]
+    fn successive_password_changes_rotate_the_salt() {
+        let dir = testutil::temp_dir("change-password-salt");
+        scripted_generator(test_config(), generation_script(&dir.join("bank_public.asc"))).generate_keys().unwrap();
+        let private = dir.join(file_names(&dir).into_iter().find(|name| name.contains("_private_")).unwrap());
+        let salt = || encryption::parse_blob(&fs::read(&private).unwrap()).unwrap().0.salt;
+        let original = salt();
+
+        let passwords = ["Correct-Horse-Battery-42", "Second-Horse-Battery-42", "Third-Horse-Battery-42"];
+        let mut salts = vec![original];
+        for pair in passwords.windows(2) {
+            let path = private.display().to_string();
+            let script = [path.as_str(), pair[0], pair[1], pair[1], path.as_str()];
+            scripted_generator(test_config(), script.map(str::to_string).to_vec()).change_password_flow().unwrap();
+            salts.push(salt());
+        }
+
+        assert_ne!(salts[0], salts[1]);
+        assert_ne!(salts[1], salts[2]);
+        assert_ne!(salts[0], salts[2]);
+        let data = fs::read(&private).unwrap();
+        assert!(encryption::decrypt_with_password(&data, passwords[2]).is_ok());
+        assert!(encryption::decrypt_with_password(&data, passwords[1]).is_err());
+    }
+
+    #[cfg(all(feature = "remote-keygen", feature = "test-ui"))]
+    #[test]
+    fn remote_generation_saves_the_returned_cert_and_handle() {
+        use std::io::{BufRead, Read, Write};
+
+        let user_ids = ["TestBank <bank@example.com>".to_string()];
+        let key = security::SecureKey::generate(&user_ids, &security::KeyOptions::default()).unwrap();
+        let public_cert = key.public_cert_armored().as_str().to_string();
+        let response = serde_json::json!({
+            "key_id": "hsm-key-42",
+            "fingerprint": key.fingerprint().to_hex(),
+            "public_cert": public_cert,
+        })
+        .to_string();
+
+        // 只应答一次请求的模拟服务，返回收到的请求行与请求体
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = std::io::BufReader::new(stream.try_clone().unwrap());
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line == "\r\n" {
+                    break;
+                }
+                if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                    length = value.trim().parse().unwrap();
+                }
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            let header = format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", response.len());
+            stream.write_all(header.as_bytes()).unwrap();
+            stream.write_all(response.as_bytes()).unwrap();
+            (request_line, String::from_utf8(body).unwrap())
+        });
+
+        let dir = testutil::temp_dir("remote-keygen");
+        let save_path = dir.join("bank_public.asc");
+        let config = config::Config { remote_keygen_url: Some(url), ..test_config() };
+        let script = ["TestBank", "bank@example.com", "0", &save_path.display().to_string()];
+        scripted_generator(config, script.map(str::to_string).to_vec()).remote_generate_flow().unwrap();
+
+        let (request_line, body) = server.join().unwrap();
+        assert!(request_line.starts_with("POST /v1/keys "), "{}", request_line);
+        let request: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(request["user_ids"], serde_json::json!(["TestBank <bank@example.com>"]));
+        assert_eq!(request["curve"], security::KeyCurve::ALL[0].id());
+
+        assert_eq!(fs::read_to_string(&save_path).unwrap(), public_cert);
+        let saved = pgp::parse_cert(&fs::read(&save_path).unwrap()).unwrap();
+        assert_eq!(saved.fingerprint(), key.fingerprint());
+        assert!(!saved.is_tsk());
+        let handle: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(dir.join("bank_public.asc.remote.json")).unwrap()).unwrap();
+        assert_eq!(handle["key_id"], "hsm-key-42");
+        assert_eq!(handle["fingerprint"], key.fingerprint().to_hex());
+    }
+
+    #[test]
+    fn canonical_metadata_is_byte_identical_for_the_same_content() {
+        use chrono::TimeZone;
+
+        let instant = chrono::Utc.with_ymd_and_hms(2026, 10, 15, 9, 30, 0).unwrap()
+            + chrono::Duration::milliseconds(250);
+        let generator = KeyGenerator::new(None, config::Config { canonical_metadata: true, ..test_config() });
+        let serialize = |utc: bool| {
+            let metadata = KeyMetadata {
+                generation_date: generator.metadata_date(timestamp::Timestamp::at(instant, utc)),
+                ..sample_metadata()
+            };
+            canonical_json(&metadata).unwrap()
+        };
+
+        let first = serialize(false);
+        assert_eq!(first, serialize(true));
+        assert!(first.contains("\"generation_date\": \"2026-10-15T09:30:00Z\""), "{}", first);
+
+        let keys: Vec<String> =
+            serde_json::from_str::<serde_json::Value>(&first).unwrap().as_object().unwrap().keys().cloned().collect();
+        let mut sorted = keys.clone();
+        sorted.sort();
+        assert_eq!(keys, sorted);
+        let abu_version = first.find("\"abu_version\"").unwrap();
+        assert!(abu_version < first.find("\"bank_name\"").unwrap());
+    }
+
+    #[cfg(feature = "test-ui")]
+    #[test]
+    fn manifest_verifies_and_detects_tampering() {
+        let dir = testutil::temp_dir("manifest-verify");
+        let public_path = dir.join("bank_public.asc");
+        scripted_generator(test_config(), generation_script(&public_path)).generate_keys().unwrap();
+        let manifest_name = file_names(&dir).into_iter().find(|name| name.ends_with(".manifest")).unwrap();
+        let manifest_path = dir.join(&manifest_name);
+        let verify = || {
+            let script = vec![manifest_path.display().to_string(), public_path.display().to_string()];
+            scripted_generator(test_config(), script).verify_manifest_flow()
+        };
+        verify().unwrap();
+
+        let manifest = fs::read_to_string(&manifest_path).unwrap();
+        let listed: Vec<&str> =
+            manifest.lines().filter_map(|line| line.split_once("  ")).map(|(_, name)| name).collect();
+        assert!(listed.contains(&"bank_public.asc"));
+        assert!(listed.iter().any(|name| name.contains("_private_")));
+        assert!(listed.iter().any(|name| name.ends_with(".json")));
+
+        // 修改任一所列文件都会使校验失败，恢复后重新通过
+        for name in &listed {
+            let path = dir.join(name);
+            let original = fs::read(&path).unwrap();
+            fs::write(&path, [original.as_slice(), b"\n"].concat()).unwrap();
+            assert!(verify().is_err(), "tampering with {} went unnoticed", name);
+            fs::write(&path, &original).unwrap();
+        }
+        verify().unwrap();
+
+        // 修改清单本身（例如替换哈希）使签名失效
+        fs::write(&manifest_path, manifest.replacen('0', "1", 1)).unwrap();
+        let error = verify().unwrap_err();
+        assert!(error.to_string().contains("清单签名无效"), "{}", error);
+    }
+
+    #[cfg(feature = "test-ui")]
+    #[test]
+    fn test_key_is_marked_in_cert_files_and_metadata() {
+        let dir = testutil::temp_dir("test-key");
+        let mut config = test_config();
+        cli::parse(&mut config, ["--test-key".to_string()]).unwrap();
+        let public_path = dir.join("bank_public.asc");
+        scripted_generator(config, generation_script(&public_path)).generate_keys().unwrap();
+
+        let cert = pgp::parse_cert(&fs::read(&public_path).unwrap()).unwrap();
+        assert!(pgp::is_test_key(&cert));
+        let user_id = pgp::primary_userid(&cert).unwrap();
+        assert!(user_id.contains(pgp::TEST_KEY_COMMENT), "{}", user_id);
+
+        // 由模板命名的文件都带有 test_ 前缀
+        let names = file_names(&dir);
+        for kind in ["_private_", "_revocation_", ".manifest"] {
+            let name = names.iter().find(|name| name.contains(kind)).unwrap();
+            assert!(name.starts_with(naming::TEST_FILE_PREFIX), "{}", name);
+        }
+
+        let metadata: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(public_path.with_extension("json")).unwrap()).unwrap();
+        assert_eq!(metadata["test_key"], true);
+    }
+
+    #[cfg(feature = "test-ui")]
+    #[test]
+    fn regular_key_is_not_marked_as_test_key() {
+        let dir = testutil::temp_dir("not-test-key");
+        let public_path = dir.join("bank_public.asc");
+        scripted_generator(test_config(), generation_script(&public_path)).generate_keys().unwrap();
+
+        let cert = pgp::parse_cert(&fs::read(&public_path).unwrap()).unwrap();
+        assert!(!pgp::is_test_key(&cert));
+        assert!(!file_names(&dir).iter().any(|name| name.starts_with(naming::TEST_FILE_PREFIX)));
+    }
+
+    #[cfg(feature = "test-ui")]
+    #[test]
+    fn duplicate_fingerprint_warns_and_aborts_when_declined() {
+        let dir = testutil::temp_dir("duplicate-fingerprint-warn");
+        let generate = |user_id: &str| {
+            security::SecureKey::generate(&[user_id.to_string()], &security::KeyOptions::default()).unwrap()
+        };
+        let key = generate("TestBank <bank@example.com>");
+        fs::write(dir.join("existing_public.asc"), key.public_cert_armored().as_str()).unwrap();
+        let save_path = dir.join("bank_public.asc");
+
+        let error = scripted_generator(test_config(), vec!["n".to_string()])
+            .check_duplicate_fingerprint(&save_path, &key.fingerprint())
+            .unwrap_err();
+        assert!(error.to_string().contains("指纹相同"), "{}", error);
+
+        scripted_generator(test_config(), vec!["y".to_string()])
+            .check_duplicate_fingerprint(&save_path, &key.fingerprint())
+            .unwrap();
+        // 没有重复时不询问（脚本为空也不会出错）
+        let other = generate("Other <other@example.com>");
+        scripted_generator(test_config(), Vec::new())
+            .check_duplicate_fingerprint(&save_path, &other.fingerprint())
+            .unwrap();
+    }
+
+    #[cfg(feature = "test-ui")]
+    #[test]
+    fn kdf_chooser_maps_levels_and_validates_manual_values() {
+        let choose = |answers: &[&str]| {
+            scripted_generator(test_config(), answers.iter().map(|a| a.to_string()).collect()).select_kdf()
+        };
+
+        assert_eq!(choose(&["0"]).unwrap(), (test_config().kdf, KDF_LEVEL_CONFIGURED));
+        for (index, level) in security::KdfLevel::ALL.into_iter().enumerate() {
+            assert_eq!(choose(&[(index + 1).to_string().as_str()]).unwrap(), (level.params(), level.name()));
+        }
+
+        let advanced = (security::KdfLevel::ALL.len() + 1).to_string();
+        let manual = choose(&[advanced.as_str(), "1", "250000"]).unwrap();
+        let expected = security::KdfParams { hash: security::KdfHash::Sha512, iterations: 250_000 };
+        assert_eq!(manual, (expected, KDF_LEVEL_CUSTOM));
+        let below_minimum = (config::MIN_ITERATIONS - 1).to_string();
+        assert!(choose(&[advanced.as_str(), "0", below_minimum.as_str()]).is_err());
+        assert!(choose(&[advanced.as_str(), "0", "many"]).is_err());
+    }
+
+    #[cfg(feature = "test-ui")]
+    #[test]
+    fn designated_revoker_is_recorded_in_cert_and_metadata() {
+        let dir = testutil::temp_dir("designated-revoker");
+        let revoker = security::SecureKey::generate(&["Trustee <trustee@example.com>".to_string()], &Default::default())
+            .unwrap();
+        let revoker_path = dir.join("trustee.asc");
+        fs::write(&revoker_path, revoker.public_cert_armored().as_str()).unwrap();
+        let keys = dir.join("keys");
+        fs::create_dir(&keys).unwrap();
+        let public_path = keys.join("bank_public.asc");
+
+        let config = config::Config { designated_revoker: Some(revoker_path), ..test_config() };
+        scripted_generator(config, generation_script(&public_path)).generate_keys().unwrap();
+
+        let cert = pgp::parse_cert(&fs::read(&public_path).unwrap()).unwrap();
+        assert_eq!(pgp::designated_revokers(&cert), [revoker.fingerprint()]);
+        let policy = sequoia_openpgp::policy::StandardPolicy::new();
+        let (algorithm, fingerprint) = cert.revocation_keys(&policy).next().unwrap().revoker();
+        assert_eq!(fingerprint, &revoker.fingerprint());
+        assert_eq!(algorithm, sequoia_openpgp::types::PublicKeyAlgorithm::ECDSA);
+
+        let metadata: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(public_path.with_extension("json")).unwrap()).unwrap();
+        assert_eq!(metadata["designated_revoker"], revoker.fingerprint().to_hex());
+    }
+
+    #[test]
+    fn metadata_schema_check_flags_unknown_missing_and_mistyped_fields() {
+        let dir = crate::testutil::temp_dir("view-metadata");
+        let read = |name: &str, value: &serde_json::Value| -> Vec<String> {
+            let path = dir.join(name);
+            fs::write(&path, serde_json::to_vec_pretty(value).unwrap()).unwrap();
+            metadata_problems(&serde_json::from_slice(&fs::read(&path).unwrap()).unwrap())
+        };
+        let valid = serde_json::to_value(sample_metadata()).unwrap();
+        let fields: Vec<&str> = valid.as_object().unwrap().keys().map(String::as_str).collect();
+        assert_eq!(fields.len(), KEY_METADATA_FIELDS.len());
+        assert!(KEY_METADATA_FIELDS.iter().all(|field| fields.contains(field)));
+        assert!(read("valid.json", &valid).is_empty());
+
+        let mut extra = valid.clone();
+        extra["unexpected"] = serde_json::json!("surprise");
+        assert_eq!(read("extra.json", &extra), ["未知字段: unexpected"]);
+
+        let mut missing = valid.clone();
+        missing.as_object_mut().unwrap().remove("notes");
+        assert_eq!(read("missing.json", &missing), ["缺少字段: notes（可能由旧版本生成）"]);
+
+        let mut mistyped = valid;
+        mistyped["key_size"] = serde_json::json!("256");
+        let problems = read("mistyped.json", &mistyped);
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].starts_with("字段类型不符"), "{:?}", problems);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(feature = "test-ui")]
+    #[test]
+    fn declining_the_generation_plan_writes_no_files_and_keeps_answers() {
+        let dir = testutil::temp_dir("decline-plan");
+        let mut script = generation_script(&dir.join("bank_public.asc"));
+        // 第 10 个应答为写入前的核对确认
+        script.truncate(10);
+        script[9] = "n".to_string();
+        let generator = scripted_generator(test_config(), script);
+
+        generator.generate_keys().unwrap();
+        assert!(file_names(&dir).is_empty(), "{:?}", file_names(&dir));
+        let remembered = generator.remembered.borrow();
+        assert_eq!(remembered.bank_name.as_deref(), Some("TestBank"));
+        assert_eq!(remembered.email.as_deref(), Some("bank@example.com"));
+        assert!(remembered.curve.is_some());
+    }
+
+    #[cfg(feature = "test-ui")]
+    #[test]
+    fn gnupg_export_saved_with_bom_and_crlf_is_imported() {
+        let dir = testutil::temp_dir("import-bom-crlf");
+        let user_ids = ["Pasted Bank <bank@example.com>".to_string()];
+        let key = security::SecureKey::generate(&user_ids, &security::KeyOptions::default()).unwrap();
+        let original = pgp::parse_cert(&key.secret_key_bytes()).unwrap();
+        let exported = pgp::export_tsk(&original, true, &pgp::ArmorOptions::default()).unwrap();
+        let armored = String::from_utf8(exported).unwrap();
+        let tsk_path = dir.join("pasted.asc");
+        fs::write(&tsk_path, format!("\u{feff}{}", armored.replace('\n', "\r\n"))).unwrap();
+        let save_path = dir.join("imported.bin");
+        let password = "Correct-Horse-Battery-42";
+        let script = [&tsk_path.display().to_string(), password, password, &save_path.display().to_string()]
+            .map(str::to_string)
+            .to_vec();
+
+        scripted_generator(test_config(), script).import_gnupg_key_flow().unwrap();
+
+        let plaintext = encryption::decrypt_with_password(&fs::read(&save_path).unwrap(), password).unwrap();
+        assert_eq!(commands::parse_decrypted_tsk(&plaintext).unwrap().fingerprint(), original.fingerprint());
+    }
+}
@@ -0,0 +1,96 @@
+//! 生成文件清单：列出每个输出文件的 SHA-256，并由新生成的密钥签名，之后对任一文件的篡改都可被发现
+//!
+//! 清单格式与 `sha256sum` 相同（每行 `<十六进制哈希>  <文件名>`），可直接用 `sha256sum -c` 只校验哈希。
+//! 与清单同目录的文件只记录文件名，其他文件记录绝对路径。分离签名保存在清单旁（`<清单>.asc`，二进制时为 `<清单>.sig`）。
+
+use anyhow::{anyhow, Result};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+/// 清单文件的扩展名
+pub const MANIFEST_EXTENSION: &str = "manifest";
+
+/// 清单中一个文件的校验结果
+pub enum EntryStatus {
+    Ok,
+    Modified,
+    Missing,
+}
+
+/// 计算各文件的哈希并生成清单内容
+pub fn build(manifest_path: &Path, files: &[&Path]) -> Result<String> {
+    let dir = manifest_path.parent().unwrap_or(Path::new(""));
+    let mut manifest = String::new();
+    for file in files {
+        let data = std::fs::read(file).map_err(|e| anyhow!("无法读取 {}: {}", file.display(), e))?;
+        let name = match file.parent() {
+            Some(parent) if parent == dir => file.file_name().map(PathBuf::from).unwrap_or_else(|| file.to_path_buf()),
+            _ => std::path::absolute(file)?,
+        };
+        let name = name.to_str().ok_or_else(|| anyhow!("文件名不是有效的 UTF-8: {}", file.display()))?;
+        if name.contains('\n') {
+            return Err(anyhow!("文件名不能包含换行: {}", file.display()));
+        }
+        manifest.push_str(&format!("{}  {}\n", crate::commands::to_hex(&Sha256::digest(&data)), name));
+    }
+    Ok(manifest)
+}
+
+/// 签名文件路径（`<清单>.asc`，二进制时为 `<清单>.sig`）
+pub fn signature_path(manifest_path: &Path, armor: bool) -> PathBuf {
+    let mut path = manifest_path.as_os_str().to_owned();
+    path.push(format!(".{}", crate::pgp::signature_extension(armor)));
+    PathBuf::from(path)
+}
+
+/// 查找清单旁已存在的签名文件（先找装甲 `.asc`，再找二进制 `.sig`）
+pub fn find_signature(manifest_path: &Path) -> Option<PathBuf> {
+    [true, false].into_iter().map(|armor| signature_path(manifest_path, armor)).find(|path| path.is_file())
+}
+
+/// 按清单逐个重新计算文件哈希；相对文件名按清单所在目录解析
+pub fn check(manifest_path: &Path, manifest: &str) -> Result<Vec<(String, EntryStatus)>> {
+    let dir = manifest_path.parent().unwrap_or(Path::new(""));
+    let mut results = Vec::new();
+    for (index, line) in manifest.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+        let (hash, name) = line
+            .split_once("  ")
+            .filter(|(hash, _)| hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit()))
+            .ok_or_else(|| anyhow!("清单第 {} 行格式无效", index + 1))?;
+        let status = match std::fs::read(dir.join(name)) {
+            Ok(data) if crate::commands::to_hex(&Sha256::digest(&data)).eq_ignore_ascii_case(hash) => EntryStatus::Ok,
+            Ok(_) => EntryStatus::Modified,
+            Err(_) => EntryStatus::Missing,
+        };
+        results.push((name.to_string(), status));
+    }
+    if results.is_empty() {
+        return Err(anyhow!("清单中没有任何文件"));
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signature_path_follows_armor_toggle() {
+        let manifest = Path::new("out/bank_manifest.manifest");
+        assert_eq!(signature_path(manifest, true), Path::new("out/bank_manifest.manifest.asc"));
+        assert_eq!(signature_path(manifest, false), Path::new("out/bank_manifest.manifest.sig"));
+    }
+
+    #[test]
+    fn find_signature_accepts_either_extension() {
+        let dir = crate::testutil::temp_dir("manifest-signature");
+        let manifest = dir.join("bank.manifest");
+        assert_eq!(find_signature(&manifest), None);
+
+        std::fs::write(signature_path(&manifest, false), b"sig").unwrap();
+        assert_eq!(find_signature(&manifest), Some(signature_path(&manifest, false)));
+
+        std::fs::write(signature_path(&manifest, true), b"asc").unwrap();
+        assert_eq!(find_signature(&manifest), Some(signature_path(&manifest, true)));
+    }
+}
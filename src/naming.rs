@@ -0,0 +1,175 @@
+use crate::timestamp::Timestamp;
+use anyhow::{anyhow, Result};
+
+/// 默认文件名模板，与引入模板前的命名一致（如 `Bank_public_20240101_120000`）
+pub const DEFAULT_NAME_TEMPLATE: &str = "{bank}_{kind}_{date}_{time}";
+
+/// 测试密钥（--test-key）文件名的前缀
+pub const TEST_FILE_PREFIX: &str = "test_";
+
+/// 模板支持的占位符
+const PLACEHOLDERS: [&str; 7] = ["bank", "email", "fpr", "fpr8", "date", "time", "kind"];
+
+/// 生成文件的文件名模板（不含扩展名）
+///
+/// 模板中没有 `{kind}` 时自动在末尾追加 `_{kind}`，保证公钥、私钥等文件名互不相同。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NameTemplate(String);
+
+impl Default for NameTemplate {
+    fn default() -> Self {
+        Self(DEFAULT_NAME_TEMPLATE.to_string())
+    }
+}
+
+/// 渲染模板所需的字段
+pub struct NameFields<'a> {
+    pub bank: &'a str,
+    /// 主 User ID 的邮箱；匿名证书为 None
+    pub email: Option<&'a str>,
+    /// 主密钥指纹（十六进制）
+    pub fingerprint: &'a str,
+    pub timestamp: &'a Timestamp,
+}
+
+impl NameTemplate {
+    /// 解析模板，拒绝未知占位符、不成对的花括号与路径分隔符
+    pub fn parse(template: &str) -> Result<Self> {
+        if template.trim().is_empty() {
+            return Err(anyhow!("文件名模板不能为空"));
+        }
+        if template.contains(['/', '\\']) {
+            return Err(anyhow!("文件名模板不能包含路径分隔符: {}", template));
+        }
+        let mut rest = template;
+        while let Some(start) = rest.find(['{', '}']) {
+            if rest[start..].starts_with('}') {
+                return Err(anyhow!("文件名模板中有多余的 '}}': {}", template));
+            }
+            let end = rest[start..]
+                .find('}')
+                .ok_or_else(|| anyhow!("文件名模板中的 '{{' 没有闭合: {}", template))?;
+            let name = &rest[start + 1..start + end];
+            if !PLACEHOLDERS.contains(&name) {
+                return Err(anyhow!(
+                    "未知的文件名占位符 {{{}}}（可用: {}）",
+                    name,
+                    PLACEHOLDERS.map(|p| format!("{{{}}}", p)).join(" "),
+                ));
+            }
+            rest = &rest[start + end + 1..];
+        }
+        Ok(Self(template.to_string()))
+    }
+
+    /// 在模板前加上固定前缀（如测试密钥的 `test_`）
+    pub fn with_prefix(&self, prefix: &str) -> Self {
+        Self(format!("{}{}", prefix, self.0))
+    }
+
+    /// 渲染出文件名主体（不含扩展名）；`kind` 为 public / private / revocation / backup 等
+    ///
+    /// 逐个扫描占位符一次替换，字段值中的 `{...}` 不会被再次展开；银行名称与邮箱中的
+    /// 路径分隔符和 `..` 会被替换，渲染结果始终是单个文件名。
+    pub fn render(&self, fields: &NameFields, kind: &str) -> String {
+        let fpr8 = &fields.fingerprint[fields.fingerprint.len().saturating_sub(8)..];
+        let value = |name: &str| -> Option<String> {
+            Some(match name {
+                "bank" => sanitize(fields.bank),
+                "email" => sanitize(fields.email.unwrap_or("anonymous")),
+                "fpr" => fields.fingerprint.to_string(),
+                "fpr8" => fpr8.to_string(),
+                "date" => fields.timestamp.date_stamp(),
+                "time" => fields.timestamp.time_stamp(),
+                "kind" => kind.to_string(),
+                _ => return None,
+            })
+        };
+
+        let mut name = String::new();
+        let mut rest = self.0.as_str();
+        while let Some(start) = rest.find('{') {
+            name.push_str(&rest[..start]);
+            let placeholder = rest[start..].find('}').and_then(|end| {
+                value(&rest[start + 1..start + end]).map(|value| (value, start + end + 1))
+            });
+            match placeholder {
+                Some((value, next)) => {
+                    name.push_str(&value);
+                    rest = &rest[next..];
+                }
+                // 模板已在解析时校验；未知内容原样保留
+                None => {
+                    name.push('{');
+                    rest = &rest[start + 1..];
+                }
+            }
+        }
+        name.push_str(rest);
+        if !self.0.contains("{kind}") {
+            name = format!("{}_{}", name, kind);
+        }
+        name
+    }
+}
+
+/// 将字段值处理为单个文件名片段：空格、路径分隔符与控制字符替换为 `_`，并消除 `..`
+fn sanitize(value: &str) -> String {
+    let mut out: String = value
+        .chars()
+        .map(|c| if c == ' ' || c == '/' || c == '\\' || c.is_control() { '_' } else { c })
+        .collect();
+    while out.contains("..") {
+        out = out.replace("..", "_");
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FINGERPRINT: &str = "0123456789ABCDEF0123456789ABCDEF89ABCDEF";
+
+    fn timestamp() -> Timestamp {
+        Timestamp::at("2024-01-02T03:04:05Z".parse().unwrap(), true)
+    }
+
+    #[test]
+    fn renders_several_placeholders() {
+        let now = timestamp();
+        let fields = NameFields {
+            bank: "Alpha Bank",
+            email: Some("ops@alpha.example"),
+            fingerprint: FINGERPRINT,
+            timestamp: &now,
+        };
+
+        let template = NameTemplate::parse("{bank}-{fpr8}-{date}").unwrap();
+        assert_eq!(template.render(&fields, "public"), "Alpha_Bank-89ABCDEF-20240102_public");
+
+        let template = NameTemplate::parse("{kind}.{email}.{time}.{fpr}").unwrap();
+        assert_eq!(template.render(&fields, "private"), format!("private.ops@alpha.example.030405.{}", FINGERPRINT));
+
+        assert_eq!(NameTemplate::default().render(&fields, "revocation"), "Alpha_Bank_revocation_20240102_030405");
+        assert!(NameTemplate::parse("{bank}-{unknown}").is_err());
+        assert!(NameTemplate::parse("out/{bank}").is_err());
+    }
+
+    #[test]
+    fn field_values_cannot_escape_or_inject_placeholders() {
+        let now = timestamp();
+        let template = NameTemplate::parse("{bank}_{fpr8}").unwrap();
+
+        let fields = NameFields { bank: "../../etc/passwd", email: None, fingerprint: FINGERPRINT, timestamp: &now };
+        let name = template.render(&fields, "public");
+        assert!(!name.contains('/') && !name.contains(".."), "{}", name);
+
+        let fields = NameFields { bank: "a\\b", email: None, fingerprint: FINGERPRINT, timestamp: &now };
+        assert_eq!(template.render(&fields, "public"), "a_b_89ABCDEF_public");
+
+        // 银行名称中的占位符文本原样保留，不会被后续替换展开
+        let fields = NameFields { bank: "{fpr}", email: None, fingerprint: FINGERPRINT, timestamp: &now };
+        assert_eq!(template.render(&fields, "public"), "{fpr}_89ABCDEF_public");
+    }
+}
@@ -0,0 +1,168 @@
+//! 新密码的复杂度规则（`password_policy`）：最小长度、必须包含的字符类别与可选的正则表达式
+//!
+//! 只约束新设置的密码（私钥、备份包、导出口令、批量模式）；解密时输入的已有密码不受限制。
+
+use anyhow::{anyhow, Result};
+
+/// 密码中必须出现的字符类别
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CharClass {
+    Lowercase,
+    Uppercase,
+    Digit,
+    /// 字母、数字与空白以外的字符
+    Symbol,
+}
+
+impl CharClass {
+    /// 从配置中的名称解析（lower / upper / digit / symbol，不区分大小写）
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "lower" => Some(CharClass::Lowercase),
+            "upper" => Some(CharClass::Uppercase),
+            "digit" => Some(CharClass::Digit),
+            "symbol" => Some(CharClass::Symbol),
+            _ => None,
+        }
+    }
+
+    /// 提示中显示的名称
+    fn label(self) -> &'static str {
+        match self {
+            CharClass::Lowercase => "小写字母",
+            CharClass::Uppercase => "大写字母",
+            CharClass::Digit => "数字",
+            CharClass::Symbol => "符号",
+        }
+    }
+
+    fn matches(self, c: char) -> bool {
+        match self {
+            CharClass::Lowercase => c.is_lowercase(),
+            CharClass::Uppercase => c.is_uppercase(),
+            CharClass::Digit => c.is_numeric(),
+            CharClass::Symbol => !c.is_alphanumeric() && !c.is_whitespace(),
+        }
+    }
+}
+
+/// 新密码必须满足的全部规则
+#[derive(Clone, Debug)]
+pub struct PasswordPolicy {
+    /// 最小字符数
+    pub min_length: usize,
+    /// 必须各至少出现一次的字符类别
+    pub required_classes: Vec<CharClass>,
+    /// 密码必须匹配的正则表达式（不自动加锚点）
+    pub pattern: Option<regex::Regex>,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            min_length: crate::config::DEFAULT_MIN_PASSWORD_LENGTH,
+            required_classes: Vec::new(),
+            pattern: None,
+        }
+    }
+}
+
+impl PasswordPolicy {
+    /// 逐条检查规则，返回第一条未满足规则的说明
+    pub fn check(&self, password: &str) -> std::result::Result<(), String> {
+        if password.chars().count() < self.min_length {
+            return Err(format!("密码至少需要 {} 个字符", self.min_length));
+        }
+        for class in &self.required_classes {
+            if !password.chars().any(|c| class.matches(c)) {
+                return Err(format!("密码必须包含至少一个{}", class.label()));
+            }
+        }
+        if let Some(pattern) = &self.pattern
+            && !pattern.is_match(password)
+        {
+            return Err(format!("密码不符合机构规则（须匹配 {}）", pattern.as_str()));
+        }
+        Ok(())
+    }
+
+    /// 规则的简短说明，如“至少 12 位，包含大写字母、数字”
+    pub fn describe(&self) -> String {
+        let mut text = format!("至少 {} 位", self.min_length);
+        if !self.required_classes.is_empty() {
+            let labels: Vec<&str> = self.required_classes.iter().map(|class| class.label()).collect();
+            text.push_str(&format!("，包含{}", labels.join("、")));
+        }
+        if self.pattern.is_some() {
+            text.push_str("，并符合机构规则");
+        }
+        text
+    }
+}
+
+/// 解析字符类别列表
+pub fn parse_classes(names: &[String]) -> Result<Vec<CharClass>> {
+    names
+        .iter()
+        .filter(|name| !name.trim().is_empty())
+        .map(|name| {
+            CharClass::from_name(name)
+                .ok_or_else(|| anyhow!("无效的字符类别: {}（可选 lower / upper / digit / symbol）", name))
+        })
+        .collect()
+}
+
+/// 编译密码规则正则表达式
+pub fn parse_pattern(pattern: &str) -> Result<regex::Regex> {
+    regex::Regex::new(pattern).map_err(|e| anyhow!("无效的密码规则正则表达式 {}: {}", pattern, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strict_policy() -> PasswordPolicy {
+        PasswordPolicy {
+            min_length: 12,
+            required_classes: parse_classes(&["lower", "upper", "digit", "symbol"].map(str::to_string)).unwrap(),
+            pattern: Some(parse_pattern("^[^ ]+$").unwrap()),
+        }
+    }
+
+    #[test]
+    fn compliant_password_passes() {
+        assert_eq!(strict_policy().check("Correct-Horse-Battery-42"), Ok(()));
+    }
+
+    #[test]
+    fn each_rule_fails_on_its_own() {
+        let policy = strict_policy();
+        let cases = [
+            ("Short-Pw-42", "至少需要 12 个字符"),
+            ("CORRECT-HORSE-BATTERY-42", "小写字母"),
+            ("correct-horse-battery-42", "大写字母"),
+            ("Correct-Horse-Battery-XX", "数字"),
+            ("CorrectHorseBattery42", "符号"),
+            ("Correct Horse-Battery-42", "须匹配 ^[^ ]+$"),
+        ];
+        for (password, expected) in cases {
+            let error = policy.check(password).unwrap_err();
+            assert!(error.contains(expected), "{}: {}", password, error);
+        }
+    }
+
+    #[test]
+    fn length_counts_characters_not_bytes() {
+        let policy = PasswordPolicy { min_length: 4, ..PasswordPolicy::default() };
+        assert!(policy.check("密码密码").is_ok());
+        assert!(policy.check("密码密").is_err());
+    }
+
+    #[test]
+    fn invalid_rule_configuration_is_rejected() {
+        assert!(parse_classes(&["lower".to_string(), "emoji".to_string()]).is_err());
+        assert_eq!(parse_classes(&[" Upper ".to_string(), String::new()]).unwrap(), [CharClass::Uppercase]);
+        assert!(parse_pattern("([").is_err());
+        assert_eq!(strict_policy().describe(), "至少 12 位，包含小写字母、大写字母、数字、符号，并符合机构规则");
+    }
+}
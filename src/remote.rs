@@ -0,0 +1,134 @@
+//! 远程密钥生成（可选功能 `remote-keygen`）
+//!
+//! 企业部署中，私钥在远程 HSM 签名服务中生成并永不离开 HSM，本工具只作为客户端：
+//! 以 JSON over HTTPS 提交生成请求，取回并校验公钥证书。
+//!
+//! 协议：`POST <remote_keygen_url>/v1/keys`，请求体为 [`KeyRequest`]，成功时返回 200 与 [`KeyResponse`]；
+//! 失败时返回非 2xx 状态，响应体可为 `{"error": "..."}`。设置了 `ABU_REMOTE_KEYGEN_TOKEN` 时以
+//! `Authorization: Bearer <token>` 认证（令牌只从环境变量读取，不写入配置文件）。
+
+use crate::pgp;
+use anyhow::{anyhow, Result};
+use sequoia_openpgp::Cert;
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+
+/// 当前协议版本
+pub const PROTOCOL_VERSION: u32 = 1;
+/// 认证令牌的环境变量
+pub const TOKEN_ENV: &str = "ABU_REMOTE_KEYGEN_TOKEN";
+/// 远程服务响应的最大字节数
+const MAX_RESPONSE_SIZE: u64 = 1024 * 1024;
+
+/// 生成请求
+#[derive(Serialize)]
+pub struct KeyRequest {
+    pub version: u32,
+    pub bank_name: String,
+    /// 完整的 User ID（`名称 <邮箱>`），第一个为主 User ID
+    pub user_ids: Vec<String>,
+    /// 曲线标识（`p256` / `p384` / `p521` / `cv25519`）
+    pub curve: String,
+    /// 有效期天数；None 表示永不过期
+    pub expiry_days: Option<u32>,
+    pub notations: Vec<Notation>,
+}
+
+/// 写入主 User ID 自签名的注记
+#[derive(Serialize)]
+pub struct Notation {
+    pub name: String,
+    pub value: String,
+}
+
+/// 成功响应
+#[derive(Deserialize)]
+pub struct KeyResponse {
+    /// 服务端的密钥句柄，后续签名请求以此引用 HSM 中的私钥
+    pub key_id: String,
+    /// 十六进制主密钥指纹
+    pub fingerprint: String,
+    /// ASCII 装甲的公钥证书
+    pub public_cert: String,
+}
+
+/// 失败响应
+#[derive(Deserialize)]
+struct ErrorResponse {
+    error: String,
+}
+
+/// 已校验的远程密钥
+pub struct RemoteKey {
+    pub key_id: String,
+    pub cert: Cert,
+    /// 服务端返回的原始公钥证书文本
+    pub public_cert: String,
+}
+
+/// 请求远程服务生成密钥，并校验返回的证书
+pub fn generate(url: &str, request: &KeyRequest) -> Result<RemoteKey> {
+    let endpoint = format!("{}/v1/keys", url.trim_end_matches('/'));
+    let token = std::env::var(TOKEN_ENV).ok();
+    let body = post_json(&endpoint, token.as_deref(), &serde_json::to_string(request)?)?;
+    let response: KeyResponse =
+        serde_json::from_str(&body).map_err(|e| anyhow!("远程服务的响应格式无效: {}", e))?;
+    check_response(request, response)
+}
+
+/// 校验响应：证书可解析、不含私钥、指纹与声明一致，且包含全部请求的 User ID
+fn check_response(request: &KeyRequest, response: KeyResponse) -> Result<RemoteKey> {
+    let cert = pgp::parse_cert(response.public_cert.as_bytes())?;
+    if cert.is_tsk() {
+        return Err(anyhow!("远程服务返回的证书包含私钥材料，已拒绝"));
+    }
+    let declared = pgp::parse_fingerprint(&response.fingerprint)?;
+    if cert.fingerprint() != declared {
+        return Err(anyhow!(
+            "远程服务声明的指纹 {} 与证书指纹 {} 不一致",
+            declared.to_spaced_hex(),
+            cert.fingerprint().to_spaced_hex(),
+        ));
+    }
+    let user_ids: Vec<String> = cert
+        .userids()
+        .map(|ua| String::from_utf8_lossy(ua.userid().value()).into_owned())
+        .collect();
+    if let Some(missing) = request.user_ids.iter().find(|wanted| !user_ids.contains(wanted)) {
+        return Err(anyhow!("远程服务返回的证书缺少请求的 User ID: {}", missing));
+    }
+    Ok(RemoteKey { key_id: response.key_id, cert, public_cert: response.public_cert })
+}
+
+/// 提交 JSON 请求并返回 2xx 响应体；其他状态码转换为错误
+fn post_json(endpoint: &str, token: Option<&str>, body: &str) -> Result<String> {
+    let mut request = ureq::post(endpoint)
+        .set("Content-Type", "application/json")
+        .timeout(std::time::Duration::from_secs(120));
+    if let Some(token) = token {
+        request = request.set("Authorization", &format!("Bearer {}", token));
+    }
+    let response = match request.send_string(body) {
+        Ok(response) => response,
+        Err(ureq::Error::Status(code, response)) => {
+            let detail = read_body(response)
+                .ok()
+                .and_then(|text| serde_json::from_str::<ErrorResponse>(&text).ok())
+                .map(|e| e.error)
+                .unwrap_or_default();
+            return Err(anyhow!("远程服务拒绝了请求（HTTP {}）: {}", code, detail));
+        }
+        Err(e) => return Err(anyhow!("无法连接远程服务 {}: {}", endpoint, e)),
+    };
+    read_body(response)
+}
+
+fn read_body(response: ureq::Response) -> Result<String> {
+    let mut body = String::new();
+    response
+        .into_reader()
+        .take(MAX_RESPONSE_SIZE)
+        .read_to_string(&mut body)
+        .map_err(|e| anyhow!("读取远程服务响应失败: {}", e))?;
+    Ok(body)
+}
@@ -0,0 +1,130 @@
+//! 吊销证书的分片二维码纸质备份
+//!
+//! 二进制吊销证书按 [`PART_PAYLOAD_CHARS`] 个 Base64 字符分片，每片内容为
+//! `ABUREV1:<序号>/<总数>:<校验>:<Base64 分片>`，校验为整个证书 SHA-256 的前 16 位十六进制，
+//! 用于发现混入了其他证书的分片。扫描器输出的文本（每行一片，顺序任意）可由 [`join`] 重组。
+
+use anyhow::{anyhow, Result};
+use base64::Engine;
+use sha2::{Digest, Sha256};
+
+/// 分片内容的前缀（含格式版本）
+pub const PART_PREFIX: &str = "ABUREV1";
+/// 每片携带的 Base64 字符数；较小的二维码更易于打印和扫描
+#[cfg(feature = "card")]
+pub const PART_PAYLOAD_CHARS: usize = 300;
+/// 校验字段的十六进制位数
+const CHECKSUM_HEX_LEN: usize = 16;
+
+/// 证书内容的校验字段
+fn checksum(data: &[u8]) -> String {
+    crate::commands::to_hex(&Sha256::digest(data))[..CHECKSUM_HEX_LEN].to_string()
+}
+
+/// 将二进制吊销证书拆分为各二维码的文本内容
+#[cfg(feature = "card")]
+pub fn split(revocation: &[u8]) -> Vec<String> {
+    let encoded = base64::engine::general_purpose::STANDARD.encode(revocation);
+    let chunks: Vec<String> = encoded
+        .chars()
+        .collect::<Vec<_>>()
+        .chunks(PART_PAYLOAD_CHARS)
+        .map(|chunk| chunk.iter().collect())
+        .collect();
+    let sum = checksum(revocation);
+    chunks
+        .iter()
+        .enumerate()
+        .map(|(index, chunk)| format!("{}:{}/{}:{}:{}", PART_PREFIX, index + 1, chunks.len(), sum, chunk))
+        .collect()
+}
+
+/// 从扫描得到的文本重组吊销证书；忽略不以 [`PART_PREFIX`] 开头的行，重复的相同分片只计一次
+pub fn join(scanned: &str) -> Result<Vec<u8>> {
+    let mut total = None;
+    let mut expected_sum = None;
+    let mut parts: Vec<Option<String>> = Vec::new();
+    for line in scanned.lines().map(str::trim).filter(|line| line.starts_with(PART_PREFIX)) {
+        let mut fields = line.splitn(4, ':').skip(1);
+        let (Some(position), Some(sum), Some(payload)) = (fields.next(), fields.next(), fields.next()) else {
+            return Err(anyhow!("分片格式无效: {}", line));
+        };
+        let (index, count) = position
+            .split_once('/')
+            .and_then(|(index, count)| Some((index.parse::<usize>().ok()?, count.parse::<usize>().ok()?)))
+            .filter(|(index, count)| (1..=*count).contains(index))
+            .ok_or_else(|| anyhow!("分片序号无效: {}", position))?;
+        if *total.get_or_insert(count) != count || expected_sum.get_or_insert_with(|| sum.to_string()).as_str() != sum {
+            return Err(anyhow!("分片 {} 不属于同一份吊销证书", position));
+        }
+        parts.resize(count, None);
+        match &parts[index - 1] {
+            Some(existing) if existing != payload => return Err(anyhow!("分片 {} 出现了内容不同的两份", position)),
+            _ => parts[index - 1] = Some(payload.to_string()),
+        }
+    }
+    if parts.is_empty() {
+        return Err(anyhow!("未找到任何 {} 分片", PART_PREFIX));
+    }
+    let missing: Vec<String> = parts
+        .iter()
+        .enumerate()
+        .filter(|(_, part)| part.is_none())
+        .map(|(index, _)| (index + 1).to_string())
+        .collect();
+    if !missing.is_empty() {
+        return Err(anyhow!("缺少分片: {}（共 {} 片）", missing.join(", "), parts.len()));
+    }
+
+    let encoded: String = parts.into_iter().flatten().collect();
+    let revocation = base64::engine::general_purpose::STANDARD
+        .decode(encoded)
+        .map_err(|e| anyhow!("分片内容不是有效的 Base64: {}", e))?;
+    if Some(checksum(&revocation)) != expected_sum {
+        return Err(anyhow!("重组后的校验不一致，分片可能扫描有误"));
+    }
+    Ok(revocation)
+}
+
+#[cfg(all(test, feature = "card"))]
+mod tests {
+    use super::*;
+    use crate::security::{KeyCurve, KeyOptions, SecureKey};
+
+    /// RSA 吊销证书超过一片的容量
+    fn revocation_cert() -> Vec<u8> {
+        let options = KeyOptions { curve: KeyCurve::Rsa3072, minimal: true, ..KeyOptions::default() };
+        let key = SecureKey::generate(&["TestBank <bank@example.com>".to_string()], &options).unwrap();
+        key.revocation_cert(false).unwrap()
+    }
+
+    #[test]
+    fn multi_part_split_round_trips_in_any_order() {
+        let revocation = revocation_cert();
+        let parts = split(&revocation);
+        assert!(parts.len() > 1, "{} part(s)", parts.len());
+        for part in &parts {
+            assert!(part.starts_with(PART_PREFIX));
+            assert!(qrcode::QrCode::new(part.as_bytes()).is_ok());
+        }
+
+        // 扫描器的输出：顺序颠倒、某片扫描两次、夹杂无关行
+        let mut scanned: Vec<&str> = parts.iter().rev().map(String::as_str).collect();
+        scanned.push(&parts[0]);
+        scanned.insert(1, "https://example.com/not-a-part");
+        assert_eq!(join(&scanned.join("\n")).unwrap(), revocation);
+    }
+
+    #[test]
+    fn missing_or_foreign_parts_are_rejected() {
+        let revocation = revocation_cert();
+        let parts = split(&revocation);
+
+        let error = join(&parts[1..].join("\n")).unwrap_err();
+        assert!(error.to_string().contains("缺少分片: 1"), "{}", error);
+
+        let other = split(&[revocation.as_slice(), b"x"].concat());
+        let mixed = [parts[0].as_str(), other[1].as_str()].join("\n");
+        assert!(join(&mixed).is_err());
+    }
+}
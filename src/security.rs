@@ -1,92 +1,547 @@
 use sequoia_openpgp as openpgp;
 use openpgp::cert::prelude::*;
+use openpgp::cert::CipherSuite;
+use openpgp::packet::signature::SignatureBuilder;
+use openpgp::types::{CompressionAlgorithm, HashAlgorithm, KeyFlags, SignatureType, SymmetricAlgorithm};
 use openpgp::serialize::SerializeInto;
 use zeroize::{Zeroize, ZeroizeOnDrop};
 
+/// 当前 sequoia 加密后端的信息
+pub struct BackendInfo {
+    /// 后端名称（由 sequoia 报告，例如 "CNG" / "Nettle"）
+    pub name: String,
+}
+
+/// 在程序启动时检查 sequoia 加密后端是否可用
+///
+/// 通过生成一个临时 P-256 密钥并进行一次签名来探测后端，
+/// 以便在真正生成密钥之前就发现后端初始化失败的问题。
+pub fn check_backend() -> Result<BackendInfo, anyhow::Error> {
+    use openpgp::crypto::Signer;
+    use openpgp::packet::key::{Key4, PrimaryRole, SecretParts};
+    use openpgp::packet::Key;
+    use openpgp::types::Curve;
+
+    use crate::error::AbuError;
+
+    let name = openpgp::crypto::backend();
+
+    let key: Key<SecretParts, PrimaryRole> = Key4::generate_ecc(true, Curve::NistP256)
+        .map_err(|e| AbuError::Crypto(format!("backend {} failed to generate P-256 key: {}", name, e)))?
+        .into();
+    let mut pair = key
+        .into_keypair()
+        .map_err(|e| AbuError::Crypto(format!("backend {} failed to create keypair: {}", name, e)))?;
+    pair.sign(HashAlgorithm::SHA256, &[0u8; 32])
+        .map_err(|e| AbuError::Crypto(format!("backend {} failed to sign: {}", name, e)))?;
+
+    Ok(BackendInfo { name })
+}
+
+/// 熵池可用位数低于该值时视为严重不足
+pub const LOW_ENTROPY_THRESHOLD: u32 = 128;
+
+/// Linux 内核报告熵池可用位数的位置
+#[cfg(target_os = "linux")]
+const ENTROPY_AVAIL_PATH: &str = "/proc/sys/kernel/random/entropy_avail";
+
+/// 解析 `entropy_avail` 的内容（十进制整数，允许首尾空白）
+pub fn parse_entropy_avail(text: &str) -> Option<u32> {
+    text.trim().parse().ok()
+}
+
+/// 判断熵池可用位数是否严重不足
+pub fn is_entropy_low(bits: u32) -> bool {
+    bits < LOW_ENTROPY_THRESHOLD
+}
+
+/// 尽力检查系统熵池；熵严重不足时返回当前可用位数
+///
+/// 仅在 Linux 上读取 `/proc`，其他平台或读取失败时返回 None（不视为错误）。
+pub fn low_entropy() -> Option<u32> {
+    #[cfg(target_os = "linux")]
+    {
+        let text = std::fs::read_to_string(ENTROPY_AVAIL_PATH).ok()?;
+        parse_entropy_avail(&text).filter(|bits| is_entropy_low(*bits))
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        None
+    }
+}
+
+/// 可选的密钥曲线（对应 sequoia 的 CipherSuite）
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyCurve {
+    P256,
+    P384,
+    P521,
+    Cv25519,
+    /// RSA 3072，仅用于最小证书（--minimal-cert），不在曲线菜单中出现
+    Rsa3072,
+}
+
+impl KeyCurve {
+    /// 全部曲线，按菜单显示顺序
+    pub const ALL: [KeyCurve; 4] = [KeyCurve::P256, KeyCurve::P384, KeyCurve::P521, KeyCurve::Cv25519];
+
+    /// 配置与命令行中使用的标识
+    pub fn id(self) -> &'static str {
+        match self {
+            KeyCurve::P256 => "p256",
+            KeyCurve::P384 => "p384",
+            KeyCurve::P521 => "p521",
+            KeyCurve::Cv25519 => "cv25519",
+            KeyCurve::Rsa3072 => "rsa3072",
+        }
+    }
+
+    /// 从标识解析（不区分大小写）
+    pub fn from_id(id: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|curve| curve.id().eq_ignore_ascii_case(id))
+    }
+
+    /// 解析主密钥算法的标识：除各曲线外还可选 rsa3072（RSA 主密钥配 ECC 子密钥，兼容旧系统的认证）
+    pub fn from_primary_id(id: &str) -> Option<Self> {
+        Self::from_id(id).or_else(|| KeyCurve::Rsa3072.id().eq_ignore_ascii_case(id).then_some(KeyCurve::Rsa3072))
+    }
+
+    /// 可读名称（用于摘要与元数据）
+    pub fn name(self) -> &'static str {
+        match self {
+            KeyCurve::P256 => "ECC P-256",
+            KeyCurve::P384 => "ECC P-384",
+            KeyCurve::P521 => "ECC P-521",
+            KeyCurve::Cv25519 => "ECC Ed25519/Cv25519",
+            KeyCurve::Rsa3072 => "RSA 3072",
+        }
+    }
+
+    /// 密钥长度（位）
+    pub fn key_size(self) -> u32 {
+        match self {
+            KeyCurve::P256 | KeyCurve::Cv25519 => 256,
+            KeyCurve::P384 => 384,
+            KeyCurve::P521 => 521,
+            KeyCurve::Rsa3072 => 3072,
+        }
+    }
+
+    fn cipher_suite(self) -> CipherSuite {
+        match self {
+            KeyCurve::P256 => CipherSuite::P256,
+            KeyCurve::P384 => CipherSuite::P384,
+            KeyCurve::P521 => CipherSuite::P521,
+            KeyCurve::Cv25519 => CipherSuite::Cv25519,
+            KeyCurve::Rsa3072 => CipherSuite::RSA3k,
+        }
+    }
+}
+
+/// 互操作预设：写入 User ID 自签名的算法偏好（对称加密 / 哈希 / 压缩）
+///
+/// 各预设参照目标生态常用实现的默认偏好，使对方加密或签名时优先选用双方都支持的算法。
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Profile {
+    /// 现代 GnuPG（2.2 及以上）
+    Gnupg,
+    /// ProtonMail（OpenPGP.js）
+    Protonmail,
+    /// RNP（Thunderbird）
+    Rnp,
+}
+
+impl Profile {
+    pub const ALL: [Profile; 3] = [Profile::Gnupg, Profile::Protonmail, Profile::Rnp];
+
+    /// 配置、命令行与元数据中使用的标识
+    pub fn id(self) -> &'static str {
+        match self {
+            Profile::Gnupg => "gnupg",
+            Profile::Protonmail => "protonmail",
+            Profile::Rnp => "rnp",
+        }
+    }
+
+    /// 从标识解析（不区分大小写）
+    pub fn from_id(id: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|profile| profile.id().eq_ignore_ascii_case(id))
+    }
+
+    /// 偏好的对称加密算法，按优先级排列
+    pub fn symmetric_algorithms(self) -> Vec<SymmetricAlgorithm> {
+        match self {
+            Profile::Gnupg | Profile::Rnp => {
+                vec![SymmetricAlgorithm::AES256, SymmetricAlgorithm::AES192, SymmetricAlgorithm::AES128]
+            }
+            Profile::Protonmail => vec![SymmetricAlgorithm::AES256, SymmetricAlgorithm::AES128],
+        }
+    }
+
+    /// 偏好的哈希算法，按优先级排列
+    pub fn hash_algorithms(self) -> Vec<HashAlgorithm> {
+        match self {
+            Profile::Gnupg => vec![HashAlgorithm::SHA512, HashAlgorithm::SHA384, HashAlgorithm::SHA256, HashAlgorithm::SHA224],
+            Profile::Protonmail => vec![HashAlgorithm::SHA256, HashAlgorithm::SHA512],
+            Profile::Rnp => vec![HashAlgorithm::SHA256, HashAlgorithm::SHA384, HashAlgorithm::SHA512, HashAlgorithm::SHA224],
+        }
+    }
+
+    /// 偏好的压缩算法，按优先级排列
+    pub fn compression_algorithms(self) -> Vec<CompressionAlgorithm> {
+        match self {
+            Profile::Gnupg | Profile::Rnp => vec![
+                CompressionAlgorithm::Zlib,
+                CompressionAlgorithm::BZip2,
+                CompressionAlgorithm::Zip,
+                CompressionAlgorithm::Uncompressed,
+            ],
+            Profile::Protonmail => vec![
+                CompressionAlgorithm::Uncompressed,
+                CompressionAlgorithm::Zlib,
+                CompressionAlgorithm::Zip,
+            ],
+        }
+    }
+
+    /// 以该预设的算法偏好重新签发每个 User ID 的自签名，其余子包（注记等）保持不变
+    ///
+    /// CertBuilder 生成时会用自己的默认偏好覆盖模板中的偏好，因此只能在生成之后重新签名；
+    /// 新签名的创建时间晚于原签名，各实现都以最新的自签名为准。
+    fn apply(self, cert: Cert) -> Result<Cert, anyhow::Error> {
+        use openpgp::policy::StandardPolicy;
+
+        let policy = StandardPolicy::new();
+        let mut signer = cert.primary_key().key().clone().parts_into_secret()?.into_keypair()?;
+        let mut signatures = Vec::new();
+        for userid in cert.with_policy(&policy, None)?.userids() {
+            let template = SignatureBuilder::from(userid.binding_signature().clone())
+                .set_preferred_symmetric_algorithms(self.symmetric_algorithms())?
+                .set_preferred_hash_algorithms(self.hash_algorithms())?
+                .set_preferred_compression_algorithms(self.compression_algorithms())?;
+            signatures.push(userid.userid().bind(&mut signer, &cert, template)?);
+        }
+        Ok(cert.insert_packets(signatures)?.0)
+    }
+}
+
+/// 生成密钥的参数
+#[derive(Clone, Debug)]
+pub struct KeyOptions {
+    /// 子密钥的算法；未单独指定主密钥算法时也用于主密钥
+    pub curve: KeyCurve,
+    /// 主密钥的算法；None 表示与子密钥相同
+    pub primary_curve: Option<KeyCurve>,
+    /// 有效期；None 表示永不过期
+    pub validity: Option<std::time::Duration>,
+    /// 附加在主 User ID 自签名上的注记（`name@domain`, value）
+    pub notations: Vec<(String, String)>,
+    /// 互操作预设；None 时使用 sequoia 的默认算法偏好
+    pub profile: Option<Profile>,
+    /// 最小证书：不生成子密钥，认证、签名与加密都由主密钥承担
+    pub minimal: bool,
+    /// 是否生成签名子密钥（最小证书时忽略）
+    pub signing_subkey: bool,
+    /// 是否生成加密子密钥（最小证书时忽略）
+    pub encryption_subkey: bool,
+    /// 签名子密钥的有效期；None 表示与主密钥相同
+    pub signing_validity: Option<std::time::Duration>,
+    /// 加密子密钥的有效期；None 表示与主密钥相同
+    pub encryption_validity: Option<std::time::Duration>,
+    /// 指定吊销人（designated revoker）：可代为吊销本证书的其他证书
+    pub revocation_keys: Vec<openpgp::types::RevocationKey>,
+}
+
+impl Default for KeyOptions {
+    /// P-256 主密钥与独立的签名、加密子密钥，永不过期
+    fn default() -> Self {
+        Self {
+            curve: KeyCurve::P256,
+            primary_curve: None,
+            validity: None,
+            notations: Vec::new(),
+            profile: None,
+            minimal: false,
+            signing_subkey: true,
+            encryption_subkey: true,
+            signing_validity: None,
+            encryption_validity: None,
+            revocation_keys: Vec::new(),
+        }
+    }
+}
+
+/// 元数据与摘要中的密钥类型：主密钥与子密钥算法不同时两者都列出
+pub fn key_type_name(primary: KeyCurve, subkeys: KeyCurve) -> String {
+    if primary == subkeys {
+        primary.name().to_string()
+    } else {
+        format!("{}（子密钥 {}）", primary.name(), subkeys.name())
+    }
+}
+
+/// 将有效期天数转换为时长
+pub fn validity_days(days: Option<u32>) -> Option<std::time::Duration> {
+    days.map(|days| std::time::Duration::from_secs(u64::from(days) * 24 * 60 * 60))
+}
+
+/// 检查当前后端能否生成并序列化指定曲线的密钥
+///
+/// 部分后端（如 Windows CNG）对某些曲线的生成或序列化存在问题，
+/// 因此通过实际生成并序列化一个临时证书来判断，而不是依赖静态列表。
+pub fn curve_supported(curve: KeyCurve) -> bool {
+    let probe = CertBuilder::new()
+        .set_cipher_suite(curve.cipher_suite())
+        .add_signing_subkey()
+        .add_transport_encryption_subkey()
+        .generate();
+
+    match probe {
+        Ok((cert, _)) => cert.as_tsk().to_vec().is_ok(),
+        Err(_) => false,
+    }
+}
+
+/// 曲线支持情况的缓存，避免每次查询都生成探测证书
+///
+/// `probe` 在启动时探测菜单中的全部曲线；其他曲线（如 RSA 主密钥）在首次查询时探测并记住。
+#[derive(Debug, Default)]
+pub struct CurveSupport(std::cell::RefCell<Vec<(KeyCurve, bool)>>);
+
+impl CurveSupport {
+    /// 探测 `KeyCurve::ALL` 中的全部曲线
+    pub fn probe() -> Self {
+        Self(std::cell::RefCell::new(
+            KeyCurve::ALL.into_iter().map(|curve| (curve, curve_supported(curve))).collect(),
+        ))
+    }
+
+    /// 当前后端是否支持该曲线（结果来自缓存）
+    pub fn supported(&self, curve: KeyCurve) -> bool {
+        if let Some((_, supported)) = self.0.borrow().iter().find(|(probed, _)| *probed == curve) {
+            return *supported;
+        }
+        let supported = curve_supported(curve);
+        self.0.borrow_mut().push((curve, supported));
+        supported
+    }
+}
+
+/// 将注记以人类可读、非关键的方式加入签名模板
+pub fn with_notations(
+    mut template: SignatureBuilder,
+    notations: &[(String, String)],
+) -> Result<SignatureBuilder, anyhow::Error> {
+    use openpgp::packet::signature::subpacket::NotationDataFlags;
+
+    for (name, value) in notations {
+        template = template.add_notation(
+            name,
+            value.as_bytes(),
+            NotationDataFlags::empty().set_human_readable(),
+            false,
+        )?;
+    }
+    Ok(template)
+}
+
 /// 安全密钥容器 - 封装由 sequoia 生成的 Cert，并保存可序列化的 secret/public 表示
 #[derive(Zeroize, ZeroizeOnDrop)]
 pub struct SecureKey {
     /// 序列化的可传输秘密密钥（TSK）字节
     secret_bytes: Vec<u8>,
-    /// 序列化的公开证书字节（TPK）
-    public_bytes: Vec<u8>,
+    /// ASCII 装甲的公开证书（TPK），不含秘密信息
+    #[zeroize(skip)]
+    public_armor: crate::pgp::ArmoredString,
     /// 在内存中也保留 Cert 以便操作（不会序列化到磁盘）
     #[zeroize(skip)]
     cert: Cert,
+    /// 生成时一并创建的吊销签名（用于导出吊销证书）
+    #[zeroize(skip)]
+    revocation: openpgp::packet::Signature,
 }
 
 impl SecureKey {
-    /// 使用 sequoia 生成一个包含 user_id 的密钥对（OpenPGP Cert），并保存序列化表示
-    pub fn generate(user_id: &str) -> Result<Self, anyhow::Error> {
+    /// 使用 sequoia 生成一个包含 user_ids 的密钥对（OpenPGP Cert），并保存序列化表示
+    ///
+    /// 第一个 User ID 为主 User ID。
+    pub fn generate(user_ids: &[String], options: &KeyOptions) -> Result<Self, anyhow::Error> {
+        // 只记录数量与算法，不记录 User ID 内容与任何密钥材料
+        let _span = tracing::debug_span!(
+            "generate",
+            curve = %options.curve.id(),
+            primary = %options.primary_curve.unwrap_or(options.curve).id(),
+            user_ids = user_ids.len(),
+            minimal = options.minimal,
+        )
+        .entered();
         let mut builder = CertBuilder::new();
-        builder = builder.add_userid(user_id);
-        // 强制使用 NIST P-256 (secp256r1) 作为主密钥算法，以避免在 Windows CNG 后端上
-        // 对某些默认算法（如 Ed25519）出现序列化问题。
-        builder = builder.set_cipher_suite(openpgp::cert::CipherSuite::P256);
+        for (index, user_id) in user_ids.iter().enumerate() {
+            if index == 0 && !options.notations.is_empty() {
+                // 结构化元数据以签名注记形式写入主 User ID 的自签名
+                let template = with_notations(
+                    SignatureBuilder::new(SignatureType::PositiveCertification),
+                    &options.notations,
+                )?;
+                builder = builder.add_userid_with(user_id.as_str(), template)?;
+            } else {
+                builder = builder.add_userid(user_id.as_str());
+            }
+        }
+        // 默认使用 NIST P-256 (secp256r1)，以避免在 Windows CNG 后端上
+        // 对某些默认算法（如 Ed25519）出现序列化问题；其他曲线需先经 curve_supported 检查。
+        // 主密钥使用 primary_curve（未指定时与子密钥相同），子密钥在 add_subkey 时单独指定算法
+        builder = builder.set_cipher_suite(options.primary_curve.unwrap_or(options.curve).cipher_suite());
+        builder = builder.set_validity_period(options.validity);
+        if !options.revocation_keys.is_empty() {
+            builder = builder.set_revocation_keys(options.revocation_keys.clone());
+        }
+        if options.minimal {
+            if options.primary_curve.is_some_and(|primary| primary != options.curve) {
+                return Err(anyhow::anyhow!("最小证书没有子密钥，不能单独指定主密钥算法"));
+            }
+            // 最小证书：椭圆曲线密钥不能同时签名与加密，只有 RSA 主密钥能承担全部用途
+            if options.curve != KeyCurve::Rsa3072 {
+                return Err(anyhow::anyhow!("最小证书需要 RSA 主密钥，{} 不能同时用于签名与加密", options.curve.name()));
+            }
+            builder = builder.set_primary_key_flags(
+                KeyFlags::empty()
+                    .set_certification()
+                    .set_signing()
+                    .set_transport_encryption()
+                    .set_storage_encryption(),
+            );
+        } else {
+            // 主密钥仅用于认证，签名与加密分别使用独立子密钥，可各自设置更短的有效期
+            for validity in [options.signing_validity, options.encryption_validity].into_iter().flatten() {
+                if options.validity.is_some_and(|primary| validity > primary) {
+                    return Err(anyhow::anyhow!("子密钥的有效期不能长于主密钥"));
+                }
+            }
+            if options.signing_subkey {
+                builder = builder.add_subkey(
+                    KeyFlags::empty().set_signing(),
+                    options.signing_validity,
+                    options.curve.cipher_suite(),
+                );
+            }
+            if options.encryption_subkey {
+                builder = builder.add_subkey(
+                    KeyFlags::empty().set_transport_encryption(),
+                    options.encryption_validity,
+                    options.curve.cipher_suite(),
+                );
+            }
+        }
 
         // generate() 返回 (Cert, KeyPair)；Cert 包含公开信息和秘密密钥包
-        let gen_res = builder.generate();
-        let (cert, _key) = match gen_res {
+        let gen_res = crate::timing::timed("generate", || builder.generate());
+        let (cert, revocation) = match gen_res {
             Ok(pair) => pair,
             Err(e) => {
-                eprintln!("CertBuilder::generate() error: {:?}", e);
-                let mut src = e.source();
-                while let Some(s) = src {
-                    eprintln!("Caused by: {:?}", s);
-                    src = s.source();
-                }
-                return Err(anyhow::anyhow!("CertBuilder::generate() failed: {:?}", e));
+                tracing::error!(error = ?e, "CertBuilder::generate() failed");
+                return Err(crate::error::AbuError::Crypto(format!("CertBuilder::generate() failed: {:?}", e)).into());
             }
         };
+        // 算法偏好写入每个 User ID 的自签名，各实现都以此为准
+        let cert = match options.profile {
+            Some(profile) => profile.apply(cert)?,
+            None => cert,
+        };
 
-        // 输出证书包含的密钥计数（仅在 debug 构建时打印）
-        if cfg!(debug_assertions) {
-            let key_count = cert.keys().count();
-            eprintln!("cert.keys() count: {}", key_count);
+        Self::from_cert(cert, revocation)
+    }
 
-            // 打印完整 Cert 的调试信息，便于诊断序列化问题
-            eprintln!("cert debug: {:#?}", cert);
-        }
+    /// 由 BIP39 助记词与口令确定性地生成密钥（Ed25519/Cv25519，永不过期）
+    ///
+    /// 同一助记词与口令总是得到相同指纹的证书。高级功能：助记词泄露即等同私钥泄露。
+    pub fn from_mnemonic(
+        user_ids: &[String],
+        mnemonic: &str,
+        passphrase: &str,
+        notations: &[(String, String)],
+    ) -> Result<Self, anyhow::Error> {
+        let seed = crate::timing::timed("derive", || crate::seed::mnemonic_seed(mnemonic, passphrase))?;
+        let (cert, revocation) = crate::timing::timed("generate", || crate::seed::cert_from_seed(user_ids, &seed, notations))
+            .map_err(|e| crate::error::AbuError::Crypto(format!("deterministic generation failed: {:?}", e)))?;
+        Self::from_cert(cert, revocation)
+    }
+
+    /// 序列化已生成的证书并构建容器
+    fn from_cert(cert: Cert, revocation: openpgp::packet::Signature) -> Result<Self, anyhow::Error> {
+        // 证书的 Debug 输出包含秘密密钥包，只记录指纹与密钥数量
+        tracing::debug!(fingerprint = %cert.fingerprint(), keys = cert.keys().count(), "certificate generated");
 
         // 使用 ASCII 装甲导出（保证 GnuPG 可导入）
         // 公钥（TPK）装甲
-        let public_out = match cert.armored().to_vec() {
-            Ok(v) => v,
+        let public_out = match crate::timing::timed("serialize (public)", || cert.armored().to_vec()) {
+            Ok(v) => crate::pgp::ArmoredString::from_armored_bytes(v)?,
             Err(e) => {
-                if cfg!(debug_assertions) {
-                    eprintln!("armored public cert failed: {:?}", e);
-                    let mut src = e.source();
-                    while let Some(s) = src {
-                        eprintln!("Caused by: {:?}", s);
-                        src = s.source();
-                    }
-                }
+                tracing::error!(error = ?e, "armored public cert failed");
                 return Err(anyhow::anyhow!("armored public cert failed: {:?}", e));
             }
         };
 
         // 私钥（TSK）装甲（包含秘密密钥包）
-        let secret_out = match cert.as_tsk().armored().to_vec() {
+        let secret_out = match crate::timing::timed("serialize (secret)", || cert.as_tsk().armored().to_vec()) {
             Ok(v) => v,
             Err(e) => {
-                if cfg!(debug_assertions) {
-                    eprintln!("armored secret tsk failed: {:?}", e);
-                    let mut src = e.source();
-                    while let Some(s) = src {
-                        eprintln!("Caused by: {:?}", s);
-                        src = s.source();
-                    }
-                }
+                tracing::error!(error = ?e, "armored secret tsk failed");
                 return Err(anyhow::anyhow!("armored secret tsk failed: {:?}", e));
             }
         };
 
-        Ok(Self { secret_bytes: secret_out, public_bytes: public_out, cert })
+        Ok(Self { secret_bytes: secret_out, public_armor: public_out, cert, revocation })
+    }
+
+    /// 获取 ASCII 装甲的公开证书（标准 OpenPGP 公钥证书）
+    pub fn public_cert_armored(&self) -> &crate::pgp::ArmoredString {
+        &self.public_armor
     }
 
-    /// 获取公开证书的序列化字节（可用于生成标准 OpenPGP 公钥证书）
-    pub fn public_cert_bytes(&self) -> Vec<u8> {
-        self.public_bytes.clone()
+    /// 主密钥指纹
+    pub fn fingerprint(&self) -> openpgp::Fingerprint {
+        self.cert.fingerprint()
+    }
+
+    /// 主密钥与各子密钥允许的用途
+    pub fn key_usage(&self) -> Vec<crate::pgp::KeyUsage> {
+        crate::pgp::key_usage(&self.cert)
+    }
+
+    /// 证书中声明的指定吊销人指纹
+    pub fn designated_revokers(&self) -> Vec<openpgp::Fingerprint> {
+        crate::pgp::designated_revokers(&self.cert)
+    }
+
+    /// 获取公开证书的二进制（未装甲）序列化字节
+    pub fn public_cert_binary(&self) -> Result<Vec<u8>, anyhow::Error> {
+        self.cert
+            .to_vec()
+            .map_err(|e| anyhow::anyhow!("serialize public cert failed: {:?}", e))
+    }
+
+    /// 获取吊销证书（GnuPG 可直接导入）；`armor` 为 true 时为 ASCII 装甲
+    pub fn revocation_cert(&self, armor: bool) -> Result<Vec<u8>, anyhow::Error> {
+        let packet = openpgp::Packet::from(self.revocation.clone());
+        let bytes = packet
+            .to_vec()
+            .map_err(|e| anyhow::anyhow!("serialize revocation cert failed: {:?}", e))?;
+        if armor {
+            Ok(crate::pgp::add_ascii_armor(&bytes, openpgp::armor::Kind::PublicKey)?.into_bytes())
+        } else {
+            Ok(bytes)
+        }
+    }
+
+    /// 用新生成的签名密钥对数据生成分离签名（`armor` 为真时 ASCII 装甲）
+    pub fn sign_detached(
+        &self,
+        data: &[u8],
+        armor: bool,
+        hash: Option<crate::pgp::SignatureHash>,
+    ) -> Result<Vec<u8>, anyhow::Error> {
+        crate::pgp::sign_detached(&self.cert, data, None, armor, hash, false)
     }
 
     /// 获取秘密密钥的序列化字节（未加密）
@@ -94,20 +549,410 @@ impl SecureKey {
         self.secret_bytes.clone()
     }
 
-    /// 从私钥和密码派生加密密钥（PBKDF2-SHA256）
-    pub fn derive_encryption_key(password: &str, salt: &[u8]) -> Result<[u8; 32], anyhow::Error> {
+    /// 从密码派生加密密钥（PBKDF2，HMAC 哈希与迭代次数由 params 指定）
+    pub fn derive_encryption_key(
+        password: &str,
+        salt: &[u8],
+        params: &KdfParams,
+    ) -> Result<[u8; 32], anyhow::Error> {
         use hmac::Hmac;
         use pbkdf2::pbkdf2;
-        use sha2::Sha256;
+        use sha2::{Sha256, Sha512};
 
         let mut key = [0u8; 32];
-        let _ = pbkdf2::<Hmac<Sha256>>(
-            password.as_bytes(),
-            salt,
-            100_000, // 迭代次数
-            &mut key,
-        );
+        crate::timing::timed("derive", || match params.hash {
+            KdfHash::Sha256 => {
+                let _ = pbkdf2::<Hmac<Sha256>>(password.as_bytes(), salt, params.iterations, &mut key);
+            }
+            KdfHash::Sha512 => {
+                let _ = pbkdf2::<Hmac<Sha512>>(password.as_bytes(), salt, params.iterations, &mut key);
+            }
+        });
 
         Ok(key)
     }
-}
\ No newline at end of file
+}
+
+/// PBKDF2 使用的 HMAC 哈希算法
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KdfHash {
+    Sha256,
+    Sha512,
+}
+
+impl KdfHash {
+    /// 写入加密文件头的算法标识
+    pub fn id(self) -> u8 {
+        match self {
+            KdfHash::Sha256 => 1,
+            KdfHash::Sha512 => 2,
+        }
+    }
+
+    /// 从文件头中的算法标识解析
+    pub fn from_id(id: u8) -> Result<Self, anyhow::Error> {
+        match id {
+            1 => Ok(KdfHash::Sha256),
+            2 => Ok(KdfHash::Sha512),
+            other => Err(anyhow::anyhow!("unknown KDF id: {}", other)),
+        }
+    }
+
+    /// 从配置中的名称解析（sha256 / sha512，不区分大小写）
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "sha256" => Some(KdfHash::Sha256),
+            "sha512" => Some(KdfHash::Sha512),
+            _ => None,
+        }
+    }
+
+    /// 可读名称
+    pub fn name(self) -> &'static str {
+        match self {
+            KdfHash::Sha256 => "PBKDF2-HMAC-SHA256",
+            KdfHash::Sha512 => "PBKDF2-HMAC-SHA512",
+        }
+    }
+}
+
+/// 密钥派生参数
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KdfParams {
+    pub hash: KdfHash,
+    pub iterations: u32,
+}
+
+impl Default for KdfParams {
+    /// 默认 PBKDF2-HMAC-SHA256，100,000 次迭代（与旧版无文件头格式一致）
+    fn default() -> Self {
+        Self { hash: KdfHash::Sha256, iterations: 100_000 }
+    }
+}
+
+/// 私钥加密强度预设，各对应一组密钥派生参数
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KdfLevel {
+    /// 日常使用，解锁快
+    Interactive,
+    /// 长期保管的重要密钥
+    Sensitive,
+    /// 离线冷存储，解锁需要数秒
+    Paranoid,
+}
+
+/// 测量 PBKDF2 速度时执行的迭代次数
+const CALIBRATION_ITERATIONS: u32 = 20_000;
+
+impl KdfLevel {
+    pub const ALL: [KdfLevel; 3] = [KdfLevel::Interactive, KdfLevel::Sensitive, KdfLevel::Paranoid];
+
+    /// 写入元数据的名称
+    pub fn name(self) -> &'static str {
+        match self {
+            KdfLevel::Interactive => "interactive",
+            KdfLevel::Sensitive => "sensitive",
+            KdfLevel::Paranoid => "paranoid",
+        }
+    }
+
+    /// 该级别的密钥派生参数
+    pub fn params(self) -> KdfParams {
+        match self {
+            KdfLevel::Interactive => KdfParams { hash: KdfHash::Sha256, iterations: 100_000 },
+            KdfLevel::Sensitive => KdfParams { hash: KdfHash::Sha512, iterations: 600_000 },
+            KdfLevel::Paranoid => KdfParams { hash: KdfHash::Sha512, iterations: 2_000_000 },
+        }
+    }
+}
+
+/// 在本机实测派生一次密钥所需的时间，按迭代次数线性估算
+pub fn estimate_kdf_time(params: &KdfParams) -> Result<std::time::Duration, anyhow::Error> {
+    let sample = KdfParams { hash: params.hash, iterations: CALIBRATION_ITERATIONS };
+    let start = std::time::Instant::now();
+    SecureKey::derive_encryption_key("calibration", &[0u8; 16], &sample)?;
+    Ok(start.elapsed().mul_f64(f64::from(params.iterations) / f64::from(CALIBRATION_ITERATIONS)))
+}
+
+/// 按各级别的估计耗时，推荐不超过 `target` 的最高级别；都超过时退回 [`KdfLevel::Interactive`]
+pub fn recommend_kdf_level(estimates: &[(KdfLevel, std::time::Duration)], target: std::time::Duration) -> KdfLevel {
+    estimates
+        .iter()
+        .filter(|(_, time)| *time <= target)
+        .map(|(level, _)| *level)
+        .max_by_key(|level| KdfLevel::ALL.iter().position(|l| l == level))
+        .unwrap_or(KdfLevel::Interactive)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_backend_reports_active_backend() {
+        let info = check_backend().unwrap();
+        assert!(!info.name.is_empty());
+        assert_eq!(info.name, openpgp::crypto::backend());
+    }
+
+    #[test]
+    fn curve_support_probes_each_curve_once() {
+        let support = CurveSupport::probe();
+        assert_eq!(support.0.borrow().len(), KeyCurve::ALL.len());
+        assert!(support.supported(KeyCurve::P256));
+        for curve in KeyCurve::ALL {
+            assert_eq!(support.supported(curve), curve_supported(curve));
+        }
+        assert_eq!(support.0.borrow().len(), KeyCurve::ALL.len());
+    }
+
+    #[test]
+    fn curve_support_caches_lazily_probed_curves() {
+        let support = CurveSupport::default();
+        assert!(support.supported(KeyCurve::P256));
+        assert!(support.supported(KeyCurve::P256));
+        assert_eq!(support.0.borrow().len(), 1);
+    }
+
+    #[test]
+    fn entropy_avail_parsing() {
+        assert_eq!(parse_entropy_avail("256\n"), Some(256));
+        assert_eq!(parse_entropy_avail("  3754 "), Some(3754));
+        assert_eq!(parse_entropy_avail(""), None);
+        assert_eq!(parse_entropy_avail("many"), None);
+        assert_eq!(parse_entropy_avail("-1"), None);
+    }
+
+    #[test]
+    fn entropy_threshold() {
+        assert!(is_entropy_low(0));
+        assert!(is_entropy_low(LOW_ENTROPY_THRESHOLD - 1));
+        assert!(!is_entropy_low(LOW_ENTROPY_THRESHOLD));
+        assert!(!is_entropy_low(256));
+    }
+
+    #[test]
+    fn public_armor_is_single_utf8_block() {
+        let key = SecureKey::generate(&["TestBank <bank@example.com>".to_string()], &KeyOptions::default()).unwrap();
+        let armored = key.public_cert_armored().as_str();
+        assert!(armored.starts_with("-----BEGIN PGP PUBLIC KEY BLOCK-----"));
+        assert_eq!(armored.matches("-----BEGIN ").count(), 1);
+        assert!(armored.trim_end().ends_with("-----END PGP PUBLIC KEY BLOCK-----"));
+
+        // 解除一层装甲即得到二进制证书，说明没有被重复装甲
+        let cert = crate::pgp::parse_cert(armored.as_bytes()).unwrap();
+        assert_eq!(cert.fingerprint(), key.fingerprint());
+        assert!(!cert.is_tsk());
+        assert_eq!(crate::pgp::parse_cert(&key.public_cert_binary().unwrap()).unwrap(), cert);
+    }
+
+    #[test]
+    fn minimal_cert_has_single_key_with_all_capabilities() {
+        let options = KeyOptions { curve: KeyCurve::Rsa3072, minimal: true, ..KeyOptions::default() };
+        let key = SecureKey::generate(&["TestBank <bank@example.com>".to_string()], &options).unwrap();
+        let cert = crate::pgp::parse_cert(&key.public_cert_binary().unwrap()).unwrap();
+        assert_eq!(cert.keys().count(), 1);
+
+        let usage = crate::pgp::key_usage(&cert);
+        assert_eq!(usage.len(), 1);
+        assert!(usage[0].primary);
+        assert_eq!(usage[0].flags, ["Certify", "Sign", "Encrypt"]);
+
+        // 椭圆曲线密钥不能同时签名与加密
+        let options = KeyOptions { minimal: true, ..KeyOptions::default() };
+        assert!(SecureKey::generate(&["TestBank <bank@example.com>".to_string()], &options).is_err());
+    }
+
+    #[test]
+    fn subkeys_get_independent_expiry_periods() {
+        use openpgp::policy::StandardPolicy;
+
+        let options = KeyOptions {
+            validity: validity_days(Some(730)),
+            signing_validity: validity_days(Some(365)),
+            encryption_validity: validity_days(Some(90)),
+            ..KeyOptions::default()
+        };
+        let key = SecureKey::generate(&["TestBank <bank@example.com>".to_string()], &options).unwrap();
+        let cert = crate::pgp::parse_cert(&key.public_cert_binary().unwrap()).unwrap();
+        let policy = StandardPolicy::new();
+        let valid = cert.with_policy(&policy, None).unwrap();
+        let days = |days: u64| Some(std::time::Duration::from_secs(days * 86400));
+
+        assert_eq!(valid.primary_key().key_validity_period(), days(730));
+        let signing = valid.keys().subkeys().for_signing().next().unwrap();
+        let encryption = valid.keys().subkeys().for_transport_encryption().next().unwrap();
+        assert_eq!(signing.key_validity_period(), days(365));
+        assert_eq!(encryption.key_validity_period(), days(90));
+        assert!(encryption.key_expiration_time() < signing.key_expiration_time());
+
+        // 元数据中的用途列表按子密钥记录各自的过期时间
+        let usage = crate::pgp::key_usage(&cert);
+        let expires = |flag: &str| usage.iter().find(|key| key.flags == [flag]).unwrap().expires.clone();
+        assert!(expires("Sign").is_some());
+        assert!(expires("Encrypt").is_some());
+        assert_ne!(expires("Sign"), expires("Encrypt"));
+
+        // 子密钥的有效期不能长于主密钥
+        let options = KeyOptions { validity: validity_days(Some(30)), ..options };
+        assert!(SecureKey::generate(&["TestBank <bank@example.com>".to_string()], &options).is_err());
+    }
+
+    #[test]
+    fn kdf_levels_map_to_preset_parameters() {
+        let expected = [
+            (KdfLevel::Interactive, "interactive", KdfHash::Sha256, 100_000),
+            (KdfLevel::Sensitive, "sensitive", KdfHash::Sha512, 600_000),
+            (KdfLevel::Paranoid, "paranoid", KdfHash::Sha512, 2_000_000),
+        ];
+        for (level, name, hash, iterations) in expected {
+            assert_eq!(level.name(), name);
+            assert_eq!(level.params(), KdfParams { hash, iterations });
+            assert!(crate::config::parse_iterations(iterations).is_ok());
+        }
+    }
+
+    #[test]
+    fn recommendation_is_highest_level_within_target() {
+        let seconds = std::time::Duration::from_secs_f64;
+        let estimates = [
+            (KdfLevel::Interactive, seconds(0.2)),
+            (KdfLevel::Sensitive, seconds(0.9)),
+            (KdfLevel::Paranoid, seconds(3.0)),
+        ];
+        assert_eq!(recommend_kdf_level(&estimates, seconds(1.0)), KdfLevel::Sensitive);
+        assert_eq!(recommend_kdf_level(&estimates, seconds(5.0)), KdfLevel::Paranoid);
+        assert_eq!(recommend_kdf_level(&estimates, seconds(0.1)), KdfLevel::Interactive);
+    }
+
+    /// 捕获日志输出的写入端
+    #[derive(Clone, Default)]
+    struct Capture(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for Capture {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn generation_logs_span_and_fingerprint_at_debug_level_without_secrets() {
+        let capture = Capture::default();
+        let writer = capture.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::DEBUG)
+            .with_writer(move || writer.clone())
+            .finish();
+
+        let user_ids = ["TestBank <bank@example.com>".to_string()];
+        let key = tracing::subscriber::with_default(subscriber, || {
+            SecureKey::generate(&user_ids, &KeyOptions::default()).unwrap()
+        });
+
+        let log = String::from_utf8(capture.0.lock().unwrap().clone()).unwrap();
+        assert!(log.contains("DEBUG"), "{}", log);
+        assert!(log.contains("generate{"), "{}", log);
+        assert!(log.contains("curve=p256") && log.contains("user_ids=1"), "{}", log);
+        assert!(log.contains("certificate generated"), "{}", log);
+        assert!(log.contains(&key.fingerprint().to_hex()), "{}", log);
+        assert!(!log.contains("bank@example.com"), "{}", log);
+        assert!(!log.contains("PRIVATE KEY") && !log.contains("secret:"), "{}", log);
+    }
+
+    #[test]
+    fn ed25519_primary_with_cv25519_encryption_subkey() {
+        use openpgp::crypto::mpi::PublicKey;
+        use openpgp::policy::StandardPolicy;
+        use openpgp::types::Curve;
+
+        if !curve_supported(KeyCurve::Cv25519) {
+            return;
+        }
+        let options =
+            KeyOptions { curve: KeyCurve::Cv25519, primary_curve: Some(KeyCurve::Cv25519), ..KeyOptions::default() };
+        let key = SecureKey::generate(&["Modern <modern@example.com>".to_string()], &options).unwrap();
+        let cert = crate::pgp::parse_cert(&key.secret_key_bytes()).unwrap();
+        let policy = StandardPolicy::new();
+        let valid = cert.with_policy(&policy, None).unwrap();
+
+        assert!(matches!(cert.primary_key().key().mpis(), PublicKey::EdDSA { curve: Curve::Ed25519, .. }));
+        let encryption: Vec<_> = valid.keys().subkeys().for_transport_encryption().collect();
+        assert_eq!(encryption.len(), 1);
+        assert!(matches!(encryption[0].key().mpis(), PublicKey::ECDH { curve: Curve::Cv25519, .. }));
+        let signing: Vec<_> = valid.keys().subkeys().for_signing().collect();
+        assert_eq!(signing.len(), 1);
+        assert!(matches!(signing[0].key().mpis(), PublicKey::EdDSA { curve: Curve::Ed25519, .. }));
+    }
+
+    #[test]
+    fn rsa_primary_with_ecc_subkeys() {
+        use openpgp::crypto::mpi::PublicKey;
+        use openpgp::types::Curve;
+
+        let options = KeyOptions { primary_curve: Some(KeyCurve::Rsa3072), ..KeyOptions::default() };
+        let key = SecureKey::generate(&["Legacy <legacy@example.com>".to_string()], &options).unwrap();
+        let cert = crate::pgp::parse_cert(&key.secret_key_bytes()).unwrap();
+
+        assert!(matches!(cert.primary_key().key().mpis(), PublicKey::RSA { .. }));
+        assert_eq!(cert.keys().subkeys().count(), 2);
+        for subkey in cert.keys().subkeys() {
+            assert!(matches!(
+                subkey.key().mpis(),
+                PublicKey::ECDSA { curve: Curve::NistP256, .. } | PublicKey::ECDH { curve: Curve::NistP256, .. }
+            ));
+        }
+        assert_eq!(key_type_name(KeyCurve::Rsa3072, KeyCurve::P256), "RSA 3072（子密钥 ECC P-256）");
+        assert_eq!(key_type_name(KeyCurve::P256, KeyCurve::P256), "ECC P-256");
+    }
+
+    #[test]
+    fn minimal_cert_rejects_a_separate_primary_algorithm() {
+        let options = KeyOptions {
+            curve: KeyCurve::Rsa3072,
+            primary_curve: Some(KeyCurve::P256),
+            minimal: true,
+            ..KeyOptions::default()
+        };
+        assert!(SecureKey::generate(&["Minimal <minimal@example.com>".to_string()], &options).is_err());
+    }
+
+    #[test]
+    fn gnupg_profile_writes_its_preferences_into_every_user_id() {
+        use openpgp::policy::StandardPolicy;
+
+        let options = KeyOptions {
+            profile: Some(Profile::Gnupg),
+            notations: vec![("security.abu@abu.mc".to_string(), "tier-1".to_string())],
+            ..KeyOptions::default()
+        };
+        let user_ids = ["TestBank <bank@example.com>".to_string(), "TestBank Ops <ops@example.com>".to_string()];
+        let key = SecureKey::generate(&user_ids, &options).unwrap();
+        let cert = crate::pgp::parse_cert(&key.secret_key_bytes()).unwrap();
+        let policy = StandardPolicy::new();
+        let valid = cert.with_policy(&policy, None).unwrap();
+
+        assert_eq!(valid.userids().count(), 2);
+        for userid in valid.userids() {
+            let binding = userid.binding_signature();
+            assert_eq!(
+                binding.preferred_symmetric_algorithms(),
+                Some(&[SymmetricAlgorithm::AES256, SymmetricAlgorithm::AES192, SymmetricAlgorithm::AES128][..])
+            );
+            assert_eq!(
+                binding.preferred_hash_algorithms(),
+                Some(&[HashAlgorithm::SHA512, HashAlgorithm::SHA384, HashAlgorithm::SHA256, HashAlgorithm::SHA224][..])
+            );
+            assert_eq!(binding.preferred_compression_algorithms(), Some(&Profile::Gnupg.compression_algorithms()[..]));
+        }
+        // 预设与注记共存：注记仍只写在主 User ID 上
+        let notations: Vec<usize> =
+            valid.userids().map(|ua| ua.binding_signature().notation("security.abu@abu.mc").count()).collect();
+        assert_eq!(notations.iter().sum::<usize>(), 1);
+        assert_eq!(Profile::from_id("GnuPG"), Some(Profile::Gnupg));
+    }
+}
@@ -0,0 +1,155 @@
+use sequoia_openpgp as openpgp;
+use openpgp::cert::CertRevocationBuilder;
+use openpgp::packet::key::{Key4, PrimaryRole, SecretParts, SubordinateRole};
+use openpgp::packet::signature::SignatureBuilder;
+use openpgp::packet::{Key, Signature, UserID};
+use openpgp::types::{Features, HashAlgorithm, KeyFlags, ReasonForRevocation, SignatureType, SymmetricAlgorithm};
+use openpgp::{Cert, Packet};
+use anyhow::{anyhow, Result};
+use hmac::{Hmac, Mac};
+use sha2::Sha512;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use zeroize::Zeroizing;
+
+use crate::error::AbuError;
+
+/// 确定性密钥的固定创建时间（2020-01-01T00:00:00Z）
+///
+/// 指纹依赖于密钥创建时间，因此必须固定，才能由同一助记词得到同一证书。
+const DETERMINISTIC_CREATION_TIME: u64 = 1_577_836_800;
+
+/// 各密钥派生时使用的域分隔标签
+const PRIMARY_LABEL: &[u8] = b"abu-keygentool/primary";
+const SIGNING_LABEL: &[u8] = b"abu-keygentool/signing";
+const ENCRYPTION_LABEL: &[u8] = b"abu-keygentool/encryption";
+
+/// 将 BIP39 助记词与可选口令转换为 64 字节种子（BIP39 标准 PBKDF2-HMAC-SHA512）
+///
+/// 助记词的单词与校验和会被验证，输入错误时返回 `BadFormat`。
+pub fn mnemonic_seed(mnemonic: &str, passphrase: &str) -> Result<Zeroizing<[u8; 64]>> {
+    let mnemonic = bip39::Mnemonic::parse(mnemonic.trim())
+        .map_err(|e| AbuError::BadFormat(format!("invalid BIP39 mnemonic: {}", e)))?;
+    Ok(Zeroizing::new(mnemonic.to_seed(passphrase)))
+}
+
+/// 从种子派生单个密钥的 32 字节秘密（HMAC-SHA512，取前 32 字节）
+fn derive_secret(seed: &[u8; 64], label: &[u8]) -> Result<Zeroizing<[u8; 32]>> {
+    let mut mac = Hmac::<Sha512>::new_from_slice(seed)
+        .map_err(|e| anyhow!("Failed to initialise HMAC: {}", e))?;
+    mac.update(label);
+    let digest = Zeroizing::new(mac.finalize().into_bytes());
+    let mut secret = Zeroizing::new([0u8; 32]);
+    secret.copy_from_slice(&digest[..32]);
+    Ok(secret)
+}
+
+/// 由种子确定性地构建证书（Ed25519 主密钥与签名子密钥，Cv25519 加密子密钥）
+///
+/// 返回证书及其吊销签名。第一个 User ID 为主 User ID，`notations` 写入其自签名。
+/// 密钥材料与创建时间固定，因此同一种子总是得到相同指纹；签名本身的时间戳为当前时间。
+pub fn cert_from_seed(
+    user_ids: &[String],
+    seed: &[u8; 64],
+    notations: &[(String, String)],
+) -> Result<(Cert, Signature)> {
+    let ctime = UNIX_EPOCH + Duration::from_secs(DETERMINISTIC_CREATION_TIME);
+    let now = SystemTime::now();
+
+    let primary_secret = derive_secret(seed, PRIMARY_LABEL)?;
+    let primary: Key<SecretParts, PrimaryRole> =
+        Key4::import_secret_ed25519(&primary_secret[..], ctime)?.into();
+    let mut primary_signer = primary.clone().into_keypair()?;
+    let mut cert = Cert::from_packets(vec![Packet::from(primary.clone())].into_iter())?;
+
+    let mut packets: Vec<Packet> = Vec::new();
+    for (index, user_id) in user_ids.iter().enumerate() {
+        let uid = UserID::from(user_id.as_str());
+        let template = SignatureBuilder::new(SignatureType::PositiveCertification)
+            .set_signature_creation_time(now)?
+            .set_key_flags(KeyFlags::empty().set_certification())?
+            .set_features(Features::sequoia())?
+            .set_preferred_hash_algorithms(vec![HashAlgorithm::SHA512, HashAlgorithm::SHA256])?
+            .set_preferred_symmetric_algorithms(vec![SymmetricAlgorithm::AES256, SymmetricAlgorithm::AES128])?
+            .set_primary_userid(index == 0)?;
+        let template = if index == 0 {
+            crate::security::with_notations(template, notations)?
+        } else {
+            template
+        };
+        let binding = uid.bind(&mut primary_signer, &cert, template)?;
+        packets.push(uid.into());
+        packets.push(binding.into());
+    }
+
+    // 签名子密钥需要反向签名（primary key binding）
+    let signing_secret = derive_secret(seed, SIGNING_LABEL)?;
+    let signing: Key<SecretParts, SubordinateRole> =
+        Key4::import_secret_ed25519(&signing_secret[..], ctime)?.into();
+    let mut signing_signer = signing.clone().into_keypair()?;
+    let backsig = SignatureBuilder::new(SignatureType::PrimaryKeyBinding)
+        .set_signature_creation_time(now)?
+        .sign_primary_key_binding(&mut signing_signer, primary.parts_as_public(), signing.parts_as_public())?;
+    let template = SignatureBuilder::new(SignatureType::SubkeyBinding)
+        .set_signature_creation_time(now)?
+        .set_key_flags(KeyFlags::empty().set_signing())?
+        .set_embedded_signature(backsig)?;
+    let binding = signing.bind(&mut primary_signer, &cert, template)?;
+    packets.push(signing.into());
+    packets.push(binding.into());
+
+    let encryption_secret = derive_secret(seed, ENCRYPTION_LABEL)?;
+    let encryption: Key<SecretParts, SubordinateRole> =
+        Key4::import_secret_cv25519(&encryption_secret[..], None, None, ctime)?.into();
+    let template = SignatureBuilder::new(SignatureType::SubkeyBinding)
+        .set_signature_creation_time(now)?
+        .set_key_flags(KeyFlags::empty().set_transport_encryption())?;
+    let binding = encryption.bind(&mut primary_signer, &cert, template)?;
+    packets.push(encryption.into());
+    packets.push(binding.into());
+
+    (cert, _) = cert.insert_packets(packets)?;
+
+    let revocation = CertRevocationBuilder::new()
+        .set_reason_for_revocation(ReasonForRevocation::Unspecified, b"Unspecified")?
+        .build(&mut primary_signer, &cert, None)?;
+
+    Ok((cert, revocation))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MNEMONIC_A: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+    const MNEMONIC_B: &str = "legal winner thank year wave sausage worth useful legal winner thank yellow";
+
+    fn cert_for(mnemonic: &str, passphrase: &str) -> Cert {
+        let seed = mnemonic_seed(mnemonic, passphrase).unwrap();
+        cert_from_seed(&["Bank <bank@example.com>".to_string()], &seed, &[]).unwrap().0
+    }
+
+    fn key_fingerprints(cert: &Cert) -> Vec<openpgp::Fingerprint> {
+        cert.keys().map(|key| key.key().fingerprint()).collect()
+    }
+
+    #[test]
+    fn same_mnemonic_gives_same_fingerprints() {
+        let first = cert_for(MNEMONIC_A, "");
+        let second = cert_for(MNEMONIC_A, "");
+        assert_eq!(first.fingerprint(), second.fingerprint());
+        assert_eq!(key_fingerprints(&first), key_fingerprints(&second));
+    }
+
+    #[test]
+    fn different_mnemonic_or_passphrase_gives_different_fingerprint() {
+        let base = cert_for(MNEMONIC_A, "");
+        assert_ne!(base.fingerprint(), cert_for(MNEMONIC_B, "").fingerprint());
+        assert_ne!(base.fingerprint(), cert_for(MNEMONIC_A, "extra words").fingerprint());
+    }
+
+    #[test]
+    fn invalid_mnemonic_is_bad_format() {
+        let error = mnemonic_seed("abandon abandon abandon", "").err().unwrap();
+        assert!(matches!(error.downcast_ref::<AbuError>(), Some(AbuError::BadFormat(_))));
+    }
+}
@@ -0,0 +1,166 @@
+//! 离线恢复单（可选功能 `recovery-sheet`）
+//!
+//! 生成单页 A4 PDF：银行名称、指纹、创建日期、公钥二维码，以及手写密码提示的空白框，供与离线备份一起存放。
+//! 恢复单上绝不写入密码本身。PDF 内置字体只支持 ASCII，字库外的字符（如中文）显示为 `?`。
+
+use anyhow::{anyhow, Result};
+use printpdf::path::PaintMode;
+use printpdf::{BuiltinFont, IndirectFontRef, Mm, PdfDocument, PdfLayerReference, Rect};
+
+/// 页面尺寸（A4，毫米）
+const PAGE_WIDTH: f32 = 210.0;
+const PAGE_HEIGHT: f32 = 297.0;
+const MARGIN: f32 = 20.0;
+/// 二维码边长（毫米）
+const QR_SIZE: f32 = 90.0;
+/// 密码提示框高度（毫米）
+const HINT_BOX_HEIGHT: f32 = 45.0;
+/// 指纹每 4 位一组，每行 5 组
+const FINGERPRINT_GROUPS_PER_LINE: usize = 5;
+
+/// 恢复单上显示的内容
+pub struct SheetFields<'a> {
+    pub bank_name: &'a str,
+    /// 十六进制指纹
+    pub fingerprint: &'a str,
+    /// 创建日期（如 `2026-10-15 UTC`）
+    pub created: &'a str,
+    /// ASCII 装甲的公钥，编码进二维码
+    pub public_key: &'a str,
+}
+
+/// 绘制恢复单并返回 PDF 字节
+pub fn render_pdf(fields: &SheetFields) -> Result<Vec<u8>> {
+    let (doc, page, layer) = PdfDocument::new("ABU Key Recovery Sheet", Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "sheet");
+    let layer = doc.get_page(page).get_layer(layer);
+    let regular = doc
+        .add_builtin_font(BuiltinFont::Helvetica)
+        .map_err(|e| anyhow!("无法加载 PDF 字体: {}", e))?;
+    let bold = doc
+        .add_builtin_font(BuiltinFont::HelveticaBold)
+        .map_err(|e| anyhow!("无法加载 PDF 字体: {}", e))?;
+    let mono = doc
+        .add_builtin_font(BuiltinFont::Courier)
+        .map_err(|e| anyhow!("无法加载 PDF 字体: {}", e))?;
+
+    let mut y = PAGE_HEIGHT - MARGIN - 5.0;
+    text(&layer, "ABU KEY RECOVERY SHEET", 20.0, MARGIN, y, &bold);
+    y -= 8.0;
+    text(&layer, "Store offline together with the encrypted private key backup.", 10.0, MARGIN, y, &regular);
+    y -= 14.0;
+
+    let name = if fields.bank_name.trim().is_empty() { "(no User ID)" } else { fields.bank_name.trim() };
+    y = labelled(&layer, "Bank name", &[ascii(name)], MARGIN, y, &regular, &bold);
+    y = labelled(&layer, "Fingerprint", &fingerprint_lines(fields.fingerprint), MARGIN, y, &regular, &mono);
+    y = labelled(&layer, "Created", &[fields.created.to_string()], MARGIN, y, &regular, &bold);
+
+    text(&layer, "Public key (scan to recover the certificate)", 9.0, MARGIN, y, &regular);
+    y -= 3.0 + QR_SIZE;
+    draw_qr(&layer, fields.public_key, MARGIN, y)?;
+    y -= 14.0;
+
+    text(&layer, "Passphrase hint - NEVER write the passphrase itself:", 11.0, MARGIN, y, &bold);
+    y -= 3.0;
+    layer.add_rect(
+        Rect::new(Mm(MARGIN), Mm(y - HINT_BOX_HEIGHT), Mm(PAGE_WIDTH - MARGIN), Mm(y)).with_mode(PaintMode::Stroke),
+    );
+
+    doc.save_to_bytes().map_err(|e| anyhow!("无法生成 PDF: {}", e))
+}
+
+/// 绘制小号标签和其下的若干行值，返回下一项的纵坐标
+fn labelled(
+    layer: &PdfLayerReference,
+    label: &str,
+    lines: &[String],
+    x: f32,
+    mut y: f32,
+    label_font: &IndirectFontRef,
+    value_font: &IndirectFontRef,
+) -> f32 {
+    text(layer, label, 9.0, x, y, label_font);
+    y -= 6.0;
+    for line in lines {
+        text(layer, line, 13.0, x, y, value_font);
+        y -= 6.5;
+    }
+    y - 6.0
+}
+
+fn text(layer: &PdfLayerReference, text: &str, size: f32, x: f32, y: f32, font: &IndirectFontRef) {
+    layer.use_text(text, size, Mm(x), Mm(y), font);
+}
+
+/// 内置字体只支持 ASCII，其他字符显示为 `?`
+fn ascii(text: &str) -> String {
+    text.chars().map(|c| if c.is_ascii() && !c.is_ascii_control() { c } else { '?' }).collect()
+}
+
+/// 指纹按 4 位分组并折行
+fn fingerprint_lines(fingerprint: &str) -> Vec<String> {
+    let groups: Vec<String> = fingerprint
+        .to_uppercase()
+        .chars()
+        .collect::<Vec<_>>()
+        .chunks(4)
+        .map(|group| group.iter().collect())
+        .collect();
+    groups.chunks(FINGERPRINT_GROUPS_PER_LINE).map(|line| line.join(" ")).collect()
+}
+
+/// 以左下角 `(x, y)` 绘制边长 [`QR_SIZE`] 的二维码；公钥使用最低纠错级别以容纳较大的证书
+fn draw_qr(layer: &PdfLayerReference, data: &str, x: f32, y: f32) -> Result<()> {
+    let code = qrcode::QrCode::with_error_correction_level(data.as_bytes(), qrcode::EcLevel::L)
+        .map_err(|e| anyhow!("公钥过大，无法放入二维码: {}", e))?;
+    let width = code.width();
+    let module = QR_SIZE / width as f32;
+    for (index, color) in code.to_colors().into_iter().enumerate() {
+        if color == qrcode::Color::Dark {
+            // PDF 坐标原点在左下角，二维码按行自上而下绘制
+            let left = x + (index % width) as f32 * module;
+            let top = y + QR_SIZE - (index / width) as f32 * module;
+            layer.add_rect(Rect::new(Mm(left), Mm(top - module), Mm(left + module), Mm(top)));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FINGERPRINT: &str = "0123456789abcdef0123456789abcdef01234567";
+
+    fn fields(public_key: &str) -> SheetFields<'_> {
+        SheetFields { bank_name: "Alpha Bank", fingerprint: FINGERPRINT, created: "2026-10-15 UTC", public_key }
+    }
+
+    #[test]
+    fn sheet_is_a_valid_pdf() {
+        let public_key = format!(
+            "-----BEGIN PGP PUBLIC KEY BLOCK-----\n\n{}\n-----END PGP PUBLIC KEY BLOCK-----\n",
+            "A".repeat(600)
+        );
+        let pdf = render_pdf(&fields(&public_key)).unwrap();
+
+        assert!(pdf.starts_with(b"%PDF-"));
+        assert!(pdf.trim_ascii_end().ends_with(b"%%EOF"));
+        assert!(pdf.len() > 1024, "{} bytes", pdf.len());
+    }
+
+    #[test]
+    fn fingerprint_is_grouped_for_the_sheet() {
+        assert_eq!(
+            fingerprint_lines(FINGERPRINT),
+            ["0123 4567 89AB CDEF 0123", "4567 89AB CDEF 0123 4567"].map(str::to_string).to_vec(),
+        );
+        assert_eq!(ascii("Alpha 银行"), "Alpha ??");
+    }
+
+    #[test]
+    fn oversized_public_key_is_rejected() {
+        let public_key = "A".repeat(8000);
+        let error = render_pdf(&fields(&public_key)).err().unwrap();
+        assert!(error.to_string().contains("二维码"), "{}", error);
+    }
+}
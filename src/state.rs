@@ -0,0 +1,101 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// 本地状态文件名（与配置文件放在同一目录）
+pub const STATE_FILE: &str = "abu-keygentool-state.json";
+
+/// 本地保存的非敏感状态：仅记录指纹与是否已确认备份
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct State {
+    #[serde(default)]
+    pub keys: Vec<KeyState>,
+}
+
+/// 单个已生成密钥的备份状态
+#[derive(Debug, Deserialize, Serialize)]
+pub struct KeyState {
+    pub fingerprint: String,
+    pub backed_up: bool,
+}
+
+impl State {
+    /// 读取状态文件；文件不存在时返回空状态
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.is_file() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("无法读取状态文件 {}: {}", path.display(), e))?;
+        serde_json::from_str(&content).map_err(|e| anyhow!("状态文件 {} 格式错误: {}", path.display(), e))
+    }
+
+    /// 写回状态文件
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let content = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, content).map_err(|e| anyhow!("无法写入状态文件 {}: {}", path.display(), e))
+    }
+
+    /// 记录新生成的密钥（已存在时更新其备份状态）
+    pub fn record(&mut self, fingerprint: &str, backed_up: bool) {
+        match self.keys.iter_mut().find(|key| key.fingerprint == fingerprint) {
+            Some(key) => key.backed_up = backed_up,
+            None => self.keys.push(KeyState { fingerprint: fingerprint.to_string(), backed_up }),
+        }
+    }
+
+    /// 尚未确认备份、需要提醒的密钥指纹
+    pub fn pending_backups(&self) -> Vec<String> {
+        self.keys
+            .iter()
+            .filter(|key| !key.backed_up)
+            .map(|key| key.fingerprint.clone())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_file_loads_empty_state() {
+        let dir = crate::testutil::temp_dir("state-missing");
+        let state = State::load(&dir.join(STATE_FILE)).unwrap();
+        assert!(state.keys.is_empty());
+        assert!(state.pending_backups().is_empty());
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let dir = crate::testutil::temp_dir("state-round-trip");
+        let path = dir.join(STATE_FILE);
+        let mut state = State::default();
+        state.record("AAAA", false);
+        state.record("BBBB", true);
+        state.save(&path).unwrap();
+
+        let loaded = State::load(&path).unwrap();
+        assert_eq!(loaded.keys.len(), 2);
+        assert_eq!(loaded.pending_backups(), vec!["AAAA".to_string()]);
+    }
+
+    #[test]
+    fn nag_stops_after_acknowledgement() {
+        let mut state = State::default();
+        state.record("AAAA", false);
+        assert_eq!(state.pending_backups(), vec!["AAAA".to_string()]);
+
+        state.record("AAAA", true);
+        assert_eq!(state.keys.len(), 1);
+        assert!(state.pending_backups().is_empty());
+    }
+
+    #[test]
+    fn corrupt_state_file_is_an_error() {
+        let dir = crate::testutil::temp_dir("state-corrupt");
+        let path = dir.join(STATE_FILE);
+        std::fs::write(&path, "not json").unwrap();
+        assert!(State::load(&path).is_err());
+    }
+}
@@ -0,0 +1,27 @@
+//! 单元测试共用的辅助函数
+
+use std::path::PathBuf;
+
+/// 为测试创建一个空的临时目录（按名称与进程号区分，已存在时先清空）
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("abu-keygentool-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// 生成 `created_days_ago` 天前创建、有效期 `validity_days` 天（None 为永不过期）的 P-256 测试证书（含私钥）
+pub fn cert_created(created_days_ago: u32, validity_days: Option<u32>) -> sequoia_openpgp::Cert {
+    use sequoia_openpgp::cert::{CertBuilder, CipherSuite};
+
+    let day = std::time::Duration::from_secs(24 * 60 * 60);
+    let (cert, _) = CertBuilder::new()
+        .add_userid("Expiry <expiry@example.com>")
+        .set_cipher_suite(CipherSuite::P256)
+        .set_creation_time(std::time::SystemTime::now() - day * created_days_ago)
+        .set_validity_period(validity_days.map(|days| day * days))
+        .add_signing_subkey()
+        .generate()
+        .unwrap();
+    cert
+}
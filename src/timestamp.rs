@@ -0,0 +1,99 @@
+use chrono::{DateTime, Local, SecondsFormat, Utc};
+
+/// 一次操作中统一使用的时间戳（文件名、元数据与摘要共用同一时刻）
+#[derive(Clone, Copy, Debug)]
+pub struct Timestamp {
+    instant: DateTime<Utc>,
+    /// 为 true 时以 UTC 显示，否则使用系统本地时区
+    utc: bool,
+}
+
+impl Timestamp {
+    /// 捕获当前时刻
+    pub fn now(utc: bool) -> Self {
+        Self::at(Utc::now(), utc)
+    }
+
+    /// 使用指定时刻
+    pub fn at(instant: DateTime<Utc>, utc: bool) -> Self {
+        Self { instant, utc }
+    }
+
+    /// 文件名使用的紧凑格式（`%Y%m%d_%H%M%S`）
+    pub fn file_stamp(&self) -> String {
+        self.format("%Y%m%d_%H%M%S")
+    }
+
+    /// 文件名模板中的日期（`%Y%m%d`）
+    pub fn date_stamp(&self) -> String {
+        self.format("%Y%m%d")
+    }
+
+    /// 文件名模板中的时间（`%H%M%S`）
+    pub fn time_stamp(&self) -> String {
+        self.format("%H%M%S")
+    }
+
+    /// 元数据使用的 RFC 3339 格式；UTC 模式以 `Z` 结尾，本地模式带系统时区偏移
+    pub fn rfc3339(&self) -> String {
+        if self.utc {
+            self.instant.to_rfc3339_opts(SecondsFormat::Secs, true)
+        } else {
+            self.instant.with_timezone(&Local).to_rfc3339_opts(SecondsFormat::Secs, false)
+        }
+    }
+
+    /// 与显示时区无关的 RFC 3339 格式（UTC、秒精度、以 `Z` 结尾），用于可复现的规范元数据
+    pub fn canonical_rfc3339(&self) -> String {
+        self.instant.to_rfc3339_opts(SecondsFormat::Secs, true)
+    }
+
+    /// 摘要中显示的可读格式，附带时区
+    pub fn display(&self) -> String {
+        if self.utc {
+            self.format("%Y-%m-%d %H:%M:%S UTC")
+        } else {
+            self.format("%Y-%m-%d %H:%M:%S (UTC%:z)")
+        }
+    }
+
+    fn format(&self, pattern: &str) -> String {
+        if self.utc {
+            self.instant.format(pattern).to_string()
+        } else {
+            self.instant.with_timezone(&Local).format(pattern).to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn instant() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 3, 5, 7, 8, 9).unwrap()
+    }
+
+    #[test]
+    fn utc_mode_uses_z_suffix() {
+        let stamp = Timestamp::at(instant(), true);
+        assert_eq!(stamp.rfc3339(), "2024-03-05T07:08:09Z");
+        assert_eq!(stamp.display(), "2024-03-05 07:08:09 UTC");
+        assert_eq!(stamp.file_stamp(), "20240305_070809");
+    }
+
+    #[test]
+    fn local_mode_uses_system_offset() {
+        let stamp = Timestamp::at(instant(), false);
+        let local = instant().with_timezone(&Local);
+        assert_eq!(stamp.rfc3339(), local.to_rfc3339_opts(SecondsFormat::Secs, false));
+        assert!(stamp.rfc3339().ends_with(&local.format("%:z").to_string()));
+        assert_eq!(stamp.file_stamp(), local.format("%Y%m%d_%H%M%S").to_string());
+    }
+
+    #[test]
+    fn canonical_form_ignores_display_zone() {
+        assert_eq!(Timestamp::at(instant(), false).canonical_rfc3339(), "2024-03-05T07:08:09Z");
+    }
+}
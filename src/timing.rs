@@ -0,0 +1,22 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+
+/// 是否输出各步骤耗时（--verbose）；默认关闭
+static VERBOSE: AtomicBool = AtomicBool::new(false);
+
+/// 开启或关闭耗时输出
+pub fn set_verbose(enabled: bool) {
+    VERBOSE.store(enabled, Ordering::Relaxed);
+}
+
+/// 执行 `f`，verbose 模式下将耗时输出到 stderr
+pub fn timed<T>(step: &str, f: impl FnOnce() -> T) -> T {
+    if !VERBOSE.load(Ordering::Relaxed) {
+        return f();
+    }
+
+    let start = Instant::now();
+    let result = f();
+    eprintln!("[timing] {}: {:.1} ms", step, start.elapsed().as_secs_f64() * 1000.0);
+    result
+}
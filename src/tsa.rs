@@ -0,0 +1,330 @@
+//! RFC 3161 可信时间戳（可选功能 `tsa`）
+//!
+//! 对公钥文件的 SHA-256 摘要向时间戳服务机构（TSA）申请时间戳令牌，证明该公钥不晚于令牌时间已经存在。
+//! 收到的令牌会校验其中的消息摘要与随机数是否与请求一致；TSA 的签名与证书链不在此校验，
+//! 需要时可用 `openssl ts -verify` 配合 TSA 证书验证。
+
+use anyhow::{anyhow, Result};
+use sha2::{Digest, Sha256};
+
+/// 时间戳令牌文件扩展名（DER 编码的 CMS ContentInfo）
+pub const TOKEN_EXTENSION: &str = "tst";
+
+/// TSA 响应的最大字节数
+#[cfg(feature = "tsa")]
+const MAX_RESPONSE_SIZE: u64 = 1024 * 1024;
+
+/// SHA-256 算法 OID（2.16.840.1.101.3.4.2.1）的 DER 内容
+const OID_SHA256: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01];
+/// CMS signedData OID（1.2.840.113549.1.7.2）
+const OID_SIGNED_DATA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x07, 0x02];
+/// id-ct-TSTInfo OID（1.2.840.113549.1.9.16.1.4）
+const OID_TST_INFO: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x10, 0x01, 0x04];
+
+const TAG_BOOLEAN: u8 = 0x01;
+const TAG_INTEGER: u8 = 0x02;
+const TAG_OCTET_STRING: u8 = 0x04;
+const TAG_NULL: u8 = 0x05;
+const TAG_OID: u8 = 0x06;
+const TAG_GENERALIZED_TIME: u8 = 0x18;
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_SET: u8 = 0x31;
+const TAG_CONTEXT_0: u8 = 0xa0;
+
+/// 已校验的时间戳令牌
+pub struct TimestampToken {
+    /// DER 编码的令牌，原样保存
+    pub token: Vec<u8>,
+    /// TSA 给出的时间（GeneralizedTime，例如 `20261015083000Z`）
+    pub gen_time: String,
+}
+
+/// 令牌保存在公钥旁：`<公钥文件名>.tst`
+pub fn token_path(public_path: &std::path::Path) -> std::path::PathBuf {
+    let mut path = public_path.as_os_str().to_owned();
+    path.push(format!(".{}", TOKEN_EXTENSION));
+    path.into()
+}
+
+/// 向 TSA 申请 `data` 的时间戳，并校验返回的令牌覆盖所提交的摘要
+pub fn timestamp(url: &str, data: &[u8]) -> Result<TimestampToken> {
+    let digest: [u8; 32] = Sha256::digest(data).into();
+    let nonce: u64 = rand::random();
+    let response = post(url, &build_request(&digest, nonce))?;
+    parse_response(&response, &digest, nonce)
+}
+
+/// 以 `application/timestamp-query` 提交请求并读取响应
+#[cfg(feature = "tsa")]
+fn post(url: &str, request: &[u8]) -> Result<Vec<u8>> {
+    use std::io::Read;
+
+    let response = ureq::post(url)
+        .set("Content-Type", "application/timestamp-query")
+        .timeout(std::time::Duration::from_secs(30))
+        .send_bytes(request)
+        .map_err(|e| anyhow!("无法连接时间戳服务 {}: {}", url, e))?;
+    let mut body = Vec::new();
+    response
+        .into_reader()
+        .take(MAX_RESPONSE_SIZE)
+        .read_to_end(&mut body)
+        .map_err(|e| anyhow!("读取时间戳响应失败: {}", e))?;
+    Ok(body)
+}
+
+/// 提交时间戳请求（未启用 `tsa` 的构建）
+#[cfg(not(feature = "tsa"))]
+fn post(_url: &str, _request: &[u8]) -> Result<Vec<u8>> {
+    Err(anyhow!("此构建未启用时间戳支持（tsa）"))
+}
+
+/// 构建 TimeStampReq：version 1、SHA-256 消息摘要、随机数，并要求返回 TSA 证书
+fn build_request(digest: &[u8; 32], nonce: u64) -> Vec<u8> {
+    let algorithm = der(TAG_SEQUENCE, &[der(TAG_OID, OID_SHA256), der(TAG_NULL, &[])].concat());
+    let imprint = der(TAG_SEQUENCE, &[algorithm, der(TAG_OCTET_STRING, digest)].concat());
+    let body = [
+        der(TAG_INTEGER, &[1]),
+        imprint,
+        der(TAG_INTEGER, &integer_bytes(nonce)),
+        der(TAG_BOOLEAN, &[0xff]),
+    ]
+    .concat();
+    der(TAG_SEQUENCE, &body)
+}
+
+/// 解析 TimeStampResp：状态必须为 granted / grantedWithMods，且令牌覆盖所提交的摘要与随机数
+fn parse_response(response: &[u8], digest: &[u8; 32], nonce: u64) -> Result<TimestampToken> {
+    let mut outer = DerReader::new(response).expect(TAG_SEQUENCE)?;
+    let mut status_info = outer.expect(TAG_SEQUENCE)?;
+    let status = status_info.expect(TAG_INTEGER)?.data;
+    // 0 = granted，1 = grantedWithMods
+    if !matches!(status, [0] | [1]) {
+        return Err(anyhow!("TSA 拒绝了时间戳请求（状态码 {:?}）", status));
+    }
+    let (tag, _, token) = outer.read()?;
+    if tag != TAG_SEQUENCE {
+        return Err(anyhow!("TSA 响应中缺少时间戳令牌"));
+    }
+    let gen_time = check_token(token, digest, nonce)?;
+    Ok(TimestampToken { token: token.to_vec(), gen_time })
+}
+
+/// 从令牌（ContentInfo → SignedData → TSTInfo）中取出消息摘要与随机数并与请求比对，返回 genTime
+fn check_token(token: &[u8], digest: &[u8; 32], nonce: u64) -> Result<String> {
+    let mut content_info = DerReader::new(token).expect(TAG_SEQUENCE)?;
+    if content_info.expect(TAG_OID)?.data != OID_SIGNED_DATA {
+        return Err(anyhow!("时间戳令牌不是 CMS SignedData"));
+    }
+    let mut signed_data = content_info.expect(TAG_CONTEXT_0)?.expect(TAG_SEQUENCE)?;
+    signed_data.expect(TAG_INTEGER)?;
+    signed_data.expect(TAG_SET)?;
+    let mut encap = signed_data.expect(TAG_SEQUENCE)?;
+    if encap.expect(TAG_OID)?.data != OID_TST_INFO {
+        return Err(anyhow!("时间戳令牌的内容类型不是 TSTInfo"));
+    }
+    let tst_info = encap.expect(TAG_CONTEXT_0)?.expect(TAG_OCTET_STRING)?;
+
+    let mut tst_info = DerReader::new(tst_info.data).expect(TAG_SEQUENCE)?;
+    tst_info.expect(TAG_INTEGER)?;
+    tst_info.expect(TAG_OID)?;
+    let mut imprint = tst_info.expect(TAG_SEQUENCE)?;
+    let mut algorithm = imprint.expect(TAG_SEQUENCE)?;
+    if algorithm.expect(TAG_OID)?.data != OID_SHA256 {
+        return Err(anyhow!("时间戳令牌使用的摘要算法不是 SHA-256"));
+    }
+    if imprint.expect(TAG_OCTET_STRING)?.data != digest {
+        return Err(anyhow!("时间戳令牌中的摘要与所提交的公钥摘要不一致"));
+    }
+    tst_info.expect(TAG_INTEGER)?;
+    let gen_time = tst_info.expect(TAG_GENERALIZED_TIME)?.data;
+    let gen_time = String::from_utf8(gen_time.to_vec()).map_err(|_| anyhow!("时间戳令牌中的时间格式无效"))?;
+
+    // accuracy、ordering 均可省略，随机数紧随其后
+    let mut returned = None;
+    while !tst_info.is_empty() {
+        let (tag, field, _) = tst_info.read()?;
+        if tag == TAG_INTEGER {
+            returned = Some(field.data);
+            break;
+        }
+    }
+    if returned != Some(integer_bytes(nonce).as_slice()) {
+        return Err(anyhow!("时间戳令牌中的随机数与请求不一致"));
+    }
+    Ok(gen_time)
+}
+
+/// 编码一个 DER 字段
+fn der(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut out = vec![tag];
+    let len = content.len();
+    if len < 0x80 {
+        out.push(len as u8);
+    } else {
+        let bytes = len.to_be_bytes();
+        let start = bytes.iter().position(|&b| b != 0).unwrap_or(bytes.len() - 1);
+        out.push(0x80 | (bytes.len() - start) as u8);
+        out.extend_from_slice(&bytes[start..]);
+    }
+    out.extend_from_slice(content);
+    out
+}
+
+/// 非负整数的 DER INTEGER 内容（最短大端表示，最高位为 1 时补 0）
+fn integer_bytes(value: u64) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    let start = bytes.iter().position(|&b| b != 0).unwrap_or(bytes.len() - 1);
+    let mut out = Vec::with_capacity(9);
+    if bytes[start] & 0x80 != 0 {
+        out.push(0);
+    }
+    out.extend_from_slice(&bytes[start..]);
+    out
+}
+
+/// 只读的最小 DER 解析器，仅支持本模块用到的定长编码
+struct DerReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> DerReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// 读取下一个字段，返回标签、内容读取器与完整编码（含标签和长度）
+    fn read(&mut self) -> Result<(u8, DerReader<'a>, &'a [u8])> {
+        let truncated = || anyhow!("时间戳数据格式无效：DER 字段被截断");
+        let (&tag, rest) = self.data.split_first().ok_or_else(truncated)?;
+        let (&first, rest) = rest.split_first().ok_or_else(truncated)?;
+        let (len, rest) = if first < 0x80 {
+            (first as usize, rest)
+        } else {
+            let count = (first & 0x7f) as usize;
+            if count == 0 || count > 4 || rest.len() < count {
+                return Err(anyhow!("时间戳数据格式无效：不支持的 DER 长度编码"));
+            }
+            let len = rest[..count].iter().fold(0usize, |acc, &b| (acc << 8) | b as usize);
+            (len, &rest[count..])
+        };
+        if rest.len() < len {
+            return Err(truncated());
+        }
+        let header = self.data.len() - rest.len();
+        let whole = &self.data[..header + len];
+        let content = &rest[..len];
+        self.data = &rest[len..];
+        Ok((tag, DerReader::new(content), whole))
+    }
+
+    /// 读取下一个字段并要求其标签为 `tag`
+    fn expect(&mut self, tag: u8) -> Result<DerReader<'a>> {
+        let (found, content, _) = self.read()?;
+        if found != tag {
+            return Err(anyhow!("时间戳数据格式无效：期望标签 0x{:02x}，实际为 0x{:02x}", tag, found));
+        }
+        Ok(content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GEN_TIME: &[u8] = b"20261015083000Z";
+
+    /// 模拟 TSA 的响应：令牌原样带回请求中的摘要与随机数（不含签名与证书）
+    fn mock_response(request: &[u8]) -> Vec<u8> {
+        let mut request = DerReader::new(request).expect(TAG_SEQUENCE).unwrap();
+        request.expect(TAG_INTEGER).unwrap();
+        let (_, _, imprint) = request.read().unwrap();
+        let nonce = request.expect(TAG_INTEGER).unwrap().data;
+
+        let tst_info = [
+            der(TAG_INTEGER, &[1]),
+            der(TAG_OID, &[0x2a, 0x03, 0x04]),
+            imprint.to_vec(),
+            der(TAG_INTEGER, &[7]),
+            der(TAG_GENERALIZED_TIME, GEN_TIME),
+            der(TAG_INTEGER, nonce),
+        ]
+        .concat();
+        let encap = [
+            der(TAG_OID, OID_TST_INFO),
+            der(TAG_CONTEXT_0, &der(TAG_OCTET_STRING, &der(TAG_SEQUENCE, &tst_info))),
+        ]
+        .concat();
+        let signed_data = [der(TAG_INTEGER, &[3]), der(TAG_SET, &[]), der(TAG_SEQUENCE, &encap)].concat();
+        let token = [der(TAG_OID, OID_SIGNED_DATA), der(TAG_CONTEXT_0, &der(TAG_SEQUENCE, &signed_data))].concat();
+        let status = der(TAG_SEQUENCE, &der(TAG_INTEGER, &[0]));
+        der(TAG_SEQUENCE, &[status, der(TAG_SEQUENCE, &token)].concat())
+    }
+
+    #[test]
+    fn token_must_cover_submitted_digest_and_nonce() {
+        let digest: [u8; 32] = Sha256::digest(b"public key").into();
+        let response = mock_response(&build_request(&digest, 0x8000_0001));
+
+        let token = parse_response(&response, &digest, 0x8000_0001).unwrap();
+        assert_eq!(token.gen_time.as_bytes(), GEN_TIME);
+
+        let other: [u8; 32] = Sha256::digest(b"other key").into();
+        assert!(parse_response(&response, &other, 0x8000_0001).is_err());
+        assert!(parse_response(&response, &digest, 2).is_err());
+    }
+
+    #[test]
+    fn token_is_saved_beside_public_key() {
+        assert_eq!(
+            token_path(std::path::Path::new("keys/bank_public.asc")),
+            std::path::Path::new("keys/bank_public.asc.tst"),
+        );
+    }
+
+    #[cfg(feature = "tsa")]
+    #[test]
+    fn mocked_endpoint_token_is_saved_and_matches_hash() {
+        use std::io::{BufRead, Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/tsa", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = std::io::BufReader::new(stream.try_clone().unwrap());
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line == "\r\n" {
+                    break;
+                }
+                if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                    length = value.trim().parse().unwrap();
+                }
+            }
+            let mut request = vec![0; length];
+            reader.read_exact(&mut request).unwrap();
+            let body = mock_response(&request);
+            write!(stream, "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", body.len()).unwrap();
+            stream.write_all(&body).unwrap();
+        });
+
+        let dir = crate::testutil::temp_dir("tsa-mock");
+        let public_path = dir.join("bank_public.asc");
+        std::fs::write(&public_path, b"-----BEGIN PGP PUBLIC KEY BLOCK-----").unwrap();
+        let public_key = std::fs::read(&public_path).unwrap();
+        let token = timestamp(&url, &public_key).unwrap();
+        std::fs::write(token_path(&public_path), &token.token).unwrap();
+        server.join().unwrap();
+
+        let saved = std::fs::read(dir.join("bank_public.asc.tst")).unwrap();
+        let digest: [u8; 32] = Sha256::digest(&public_key).into();
+        assert_eq!(saved, token.token);
+        assert!(saved.windows(digest.len()).any(|window| window == digest));
+    }
+}
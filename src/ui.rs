@@ -3,20 +3,398 @@ use dialoguer::{
     Select, Password, Confirm, Input
 };
 pub use console::style;
+#[cfg(feature = "gui")]
 use native_dialog::FileDialog;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 use anyhow::Result;
+use crate::error::AbuError;
+
+/// 危险操作确认前的倒计时秒数
+const DANGEROUS_COUNTDOWN_SECS: u64 = 5;
+
+/// 指定脚本应答文件的环境变量（仅 `test-ui` 构建）
+#[cfg(feature = "test-ui")]
+pub const SCRIPT_ENV: &str = "ABU_UI_SCRIPT";
+
+/// 敏感操作后清除终端的方式
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ClearMode {
+    /// 不清除（默认）
+    #[default]
+    Off,
+    /// 清除当前屏幕
+    Screen,
+    /// 清除屏幕与滚动缓冲（需要终端支持 `ESC[3J`，如 xterm、Windows Terminal、iTerm2）
+    Scrollback,
+}
+
+impl ClearMode {
+    /// 从配置中的名称解析（off / screen / scrollback，不区分大小写）
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "off" => Some(ClearMode::Off),
+            "screen" => Some(ClearMode::Screen),
+            "scrollback" => Some(ClearMode::Scrollback),
+            _ => None,
+        }
+    }
+}
+
+/// 界面语言（目前只影响欢迎信息，其余提示均为中文）
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Language {
+    /// 中文（默认）
+    #[default]
+    Zh,
+    /// 英文
+    En,
+}
+
+impl Language {
+    /// 全部语言，按设置菜单显示顺序
+    pub const ALL: [Language; 2] = [Language::Zh, Language::En];
+
+    /// 从配置中的名称解析（zh / en，不区分大小写，也接受 zh-CN / en-US 形式）
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "zh" | "zh-cn" => Some(Language::Zh),
+            "en" | "en-us" => Some(Language::En),
+            _ => None,
+        }
+    }
+
+    /// 配置中使用的标识
+    pub fn id(self) -> &'static str {
+        match self {
+            Language::Zh => "zh",
+            Language::En => "en",
+        }
+    }
+
+    /// 显示名称
+    pub fn name(self) -> &'static str {
+        match self {
+            Language::Zh => "中文",
+            Language::En => "English",
+        }
+    }
+}
+
+/// 清除终端所需的 ANSI 序列：光标归位并清屏，`Scrollback` 时再清除滚动缓冲；`Off` 时为空
+pub fn clear_sequence(mode: ClearMode) -> &'static str {
+    match mode {
+        ClearMode::Off => "",
+        ClearMode::Screen => "\x1b[H\x1b[2J",
+        ClearMode::Scrollback => "\x1b[H\x1b[2J\x1b[3J",
+    }
+}
+
+/// 读取脚本应答文件：每行一个应答，空行表示接受默认值（仅 `test-ui` 构建）
+#[cfg(feature = "test-ui")]
+pub fn read_script(path: &std::path::Path) -> Result<Vec<String>> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("无法读取脚本应答文件 {}: {}", path.display(), e))?;
+    Ok(text.lines().map(str::to_string).collect())
+}
+
+/// 将提示框的 Ctrl-C 中断映射为取消，其他错误原样返回
+fn interrupt_as_cancel(error: dialoguer::Error, what: &str) -> anyhow::Error {
+    match error {
+        dialoguer::Error::IO(e) if e.kind() == std::io::ErrorKind::Interrupted => {
+            AbuError::Cancelled(what.to_string()).into()
+        }
+        other => other.into(),
+    }
+}
+
+/// 是否正在等待密码输入；此时 Ctrl-C 只取消当前操作
+static IN_PASSWORD_PROMPT: AtomicBool = AtomicBool::new(false);
+/// 密码输入期间是否收到过 Ctrl-C
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// 安装 Ctrl-C（SIGINT）处理器
+///
+/// 选择框等提示在原始模式下读取按键，Ctrl-C 由 dialoguer 报告为中断；密码提示则在
+/// 行模式下读取，终端会发出 SIGINT，默认会直接结束进程。安装后密码输入期间的 Ctrl-C
+/// 只做标记，回车后该提示返回取消；其他时候仍以“用户取消”的退出码结束程序。
+pub fn install_interrupt_handler() -> Result<()> {
+    ctrlc::set_handler(|| {
+        if IN_PASSWORD_PROMPT.load(Ordering::SeqCst) {
+            INTERRUPTED.store(true, Ordering::SeqCst);
+            eprintln!();
+            eprintln!("已中断，按回车返回主菜单");
+        } else {
+            std::process::exit(crate::error::exit_code::CANCELLED);
+        }
+    })
+    .map_err(|e| anyhow::anyhow!("无法安装 Ctrl-C 处理器: {}", e))
+}
+
+/// 整理一次密码提示的结果：期间收到过 Ctrl-C 时视为取消，否则按 [`interrupt_as_cancel`] 映射错误
+fn finish_password_prompt(result: dialoguer::Result<String>, interrupted: bool, what: &str) -> Result<String> {
+    if interrupted {
+        return Err(AbuError::Cancelled(what.to_string()).into());
+    }
+    result.map_err(|e| interrupt_as_cancel(e, what))
+}
+
+/// 打开文件时按用途限定的扩展名
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileFilter {
+    /// 加密私钥（解密、修改密码等）
+    EncryptedKey,
+    /// 公钥或私钥证书（验证、比较、导出等）
+    Cert,
+    /// 分离签名
+    Signature,
+}
+
+impl FileFilter {
+    /// 对话框中显示的过滤器名称
+    fn description(self) -> &'static str {
+        match self {
+            FileFilter::EncryptedKey => "加密私钥 (.bin / .asc)",
+            FileFilter::Cert => "OpenPGP 证书 (.asc / .gpg / .bin)",
+            FileFilter::Signature => "分离签名 (.sig / .asc)",
+        }
+    }
+
+    /// 允许的扩展名（不含点）
+    pub fn extensions(self) -> &'static [&'static str] {
+        match self {
+            FileFilter::EncryptedKey => &["bin", "asc"],
+            FileFilter::Cert => &["asc", "gpg", "bin"],
+            FileFilter::Signature => &["sig", "asc"],
+        }
+    }
+}
+
+/// 判断路径的扩展名是否在允许列表中（不区分大小写）
+pub fn has_expected_extension(path: &std::path::Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| extensions.iter().any(|expected| ext.eq_ignore_ascii_case(expected)))
+}
+
+/// 从 `dir` 向上查找含有任一版本库标记（如 `.git`）的目录，返回该工作区根目录
+pub fn find_vcs_root(dir: &std::path::Path, markers: &[String]) -> Option<PathBuf> {
+    // 相对路径按当前目录解析；目录尚不存在时仍检查其已存在的上级
+    let dir = std::path::absolute(dir).ok()?;
+    dir.ancestors()
+        .find(|ancestor| markers.iter().any(|marker| ancestor.join(marker).exists()))
+        .map(std::path::Path::to_path_buf)
+}
+
+/// 判断用户输入是否与确认短语一致（忽略首尾空白，区分大小写）
+pub fn phrase_matches(input: &str, phrase: &str) -> bool {
+    input.trim() == phrase
+}
 
 pub struct UserInterface {
     theme: ColorfulTheme,
+    /// 保存文件时默认使用的目录
+    output_dir: Option<PathBuf>,
+    /// 设置新密码时须满足的复杂度规则
+    password_policy: crate::password::PasswordPolicy,
+    /// 保存位置位于这些版本库标记所在的工作区内时警告
+    vcs_markers: Vec<String>,
+    /// 敏感操作后清除终端的方式
+    clear_mode: ClearMode,
+    /// 界面语言
+    language: Language,
+    /// 脚本应答队列；设置后所有提示与文件对话框按顺序取用应答，不再读取终端
+    #[cfg(feature = "test-ui")]
+    script: Option<std::cell::RefCell<std::collections::VecDeque<String>>>,
 }
 
 impl UserInterface {
     pub fn new() -> Self {
         Self {
             theme: ColorfulTheme::default(),
+            output_dir: None,
+            password_policy: crate::password::PasswordPolicy { min_length: 0, ..Default::default() },
+            vcs_markers: Vec::new(),
+            clear_mode: ClearMode::Off,
+            language: Language::Zh,
+            #[cfg(feature = "test-ui")]
+            script: None,
         }
     }
+
+    /// 以脚本应答代替终端交互，用于无人值守的端到端测试
+    ///
+    /// 每个提示按顺序取用一个应答：选择项为下标或选项文字，确认为 y/n，空应答表示接受默认值。
+    /// 应答用完或不符合提示要求时返回错误。
+    #[cfg(feature = "test-ui")]
+    pub fn with_script<I, S>(responses: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            script: Some(std::cell::RefCell::new(responses.into_iter().map(Into::into).collect())),
+            ..Self::new()
+        }
+    }
+
+    /// 设置新密码的复杂度规则；输入已有密码（解密）时不受限制
+    pub fn with_password_policy(mut self, policy: crate::password::PasswordPolicy) -> Self {
+        self.password_policy = policy;
+        self
+    }
+
+    /// 设置保存位置检查的版本库标记；为空时不检查
+    pub fn with_vcs_markers(mut self, markers: Vec<String>) -> Self {
+        self.vcs_markers = markers;
+        self
+    }
+
+    /// 设置敏感操作后清除终端的方式
+    pub fn with_clear_mode(mut self, mode: ClearMode) -> Self {
+        self.clear_mode = mode;
+        self
+    }
+
+    /// 设置界面语言
+    pub fn with_language(mut self, language: Language) -> Self {
+        self.language = language;
+        self
+    }
+
+    /// 设置保存文件时的默认目录
+    pub fn with_output_dir(mut self, output_dir: Option<PathBuf>) -> Self {
+        self.output_dir = output_dir;
+        self
+    }
+
+    /// 取出下一个脚本应答并回显；未设置脚本时返回 None
+    #[cfg(feature = "test-ui")]
+    fn scripted(&self, prompt: &str, secret: bool) -> Result<Option<String>> {
+        let Some(script) = &self.script else {
+            return Ok(None);
+        };
+        let answer = script
+            .borrow_mut()
+            .pop_front()
+            .ok_or_else(|| anyhow::anyhow!("脚本应答已用完（提示: {}）", prompt))?;
+        println!("{} {}", prompt, if secret { "********" } else { answer.as_str() });
+        Ok(Some(answer))
+    }
+
+    /// 未启用 `test-ui` 的构建没有脚本应答
+    #[cfg(not(feature = "test-ui"))]
+    fn scripted(&self, _prompt: &str, _secret: bool) -> Result<Option<String>> {
+        Ok(None)
+    }
+
+    /// 选择提示；脚本应答可以是下标或选项文字
+    fn ask_select<T: ToString>(&self, prompt: &str, items: &[T], default: usize) -> Result<usize> {
+        if let Some(answer) = self.scripted(prompt, false)? {
+            if answer.is_empty() {
+                return Ok(default);
+            }
+            return answer
+                .parse::<usize>()
+                .ok()
+                .filter(|index| *index < items.len())
+                .or_else(|| items.iter().position(|item| item.to_string() == answer))
+                .ok_or_else(|| anyhow::anyhow!("脚本应答 {:?} 不是有效选项（提示: {}）", answer, prompt));
+        }
+        Ok(Select::with_theme(&self.theme)
+            .with_prompt(prompt)
+            .items(items)
+            .default(default)
+            .interact()?)
+    }
+
+    /// 文本输入提示；`validate` 不通过时要求重新输入（脚本应答直接报错）
+    fn ask_input<V>(&self, prompt: &str, default: Option<&str>, allow_empty: bool, validate: V) -> Result<String>
+    where
+        V: Fn(&str) -> std::result::Result<(), String>,
+    {
+        if let Some(answer) = self.scripted(prompt, false)? {
+            let answer = match default {
+                Some(default) if answer.is_empty() => default.to_string(),
+                _ => answer,
+            };
+            if answer.is_empty() && !allow_empty {
+                return Err(anyhow::anyhow!("脚本应答不能为空（提示: {}）", prompt));
+            }
+            validate(&answer).map_err(|e| anyhow::anyhow!("脚本应答 {:?} 无效（提示: {}）: {}", answer, prompt, e))?;
+            return Ok(answer);
+        }
+        let mut input = Input::<String>::with_theme(&self.theme)
+            .with_prompt(prompt)
+            .allow_empty(allow_empty)
+            .validate_with(|input: &String| validate(input));
+        if let Some(default) = default {
+            input = input.default(default.to_string());
+        }
+        Ok(input.interact_text()?)
+    }
+
+    /// 不可见的密码输入提示；Ctrl-C 视为取消
+    fn ask_password<V>(&self, prompt: &str, allow_empty: bool, validate: V) -> Result<String>
+    where
+        V: Fn(&str) -> std::result::Result<(), String>,
+    {
+        if let Some(answer) = self.scripted(prompt, true)? {
+            if answer.is_empty() && !allow_empty {
+                return Err(anyhow::anyhow!("脚本应答不能为空（提示: {}）", prompt));
+            }
+            validate(&answer).map_err(|e| anyhow::anyhow!("脚本应答无效（提示: {}）: {}", prompt, e))?;
+            return Ok(answer);
+        }
+        // 空输入由这里判断：Ctrl-C 清空当前行后，用户回车得到的是空输入，需要先检查中断标记
+        IN_PASSWORD_PROMPT.store(true, Ordering::SeqCst);
+        INTERRUPTED.store(false, Ordering::SeqCst);
+        let result = loop {
+            let result = Password::with_theme(&self.theme)
+                .with_prompt(prompt)
+                .allow_empty_password(true)
+                .validate_with(|input: &String| if input.is_empty() { Ok(()) } else { validate(input) })
+                .interact();
+            let interrupted = INTERRUPTED.swap(false, Ordering::SeqCst);
+            match finish_password_prompt(result, interrupted, "密码输入") {
+                Ok(answer) if answer.is_empty() && !allow_empty => continue,
+                other => break other,
+            }
+        };
+        IN_PASSWORD_PROMPT.store(false, Ordering::SeqCst);
+        result
+    }
+
+    /// 是/否确认提示；脚本应答为 y/yes/是 或 n/no/否
+    fn ask_confirm(&self, prompt: &str, default: bool) -> Result<bool> {
+        if let Some(answer) = self.scripted(prompt, false)? {
+            return match answer.trim().to_ascii_lowercase().as_str() {
+                "" => Ok(default),
+                "y" | "yes" | "是" => Ok(true),
+                "n" | "no" | "否" => Ok(false),
+                _ => Err(anyhow::anyhow!("脚本应答 {:?} 不是 y/n（提示: {}）", answer, prompt)),
+            };
+        }
+        Ok(Confirm::with_theme(&self.theme)
+            .with_prompt(prompt)
+            .default(default)
+            .interact()?)
+    }
+
+    /// 从列表中选择一项，返回其下标
+    pub fn select_index(&self, prompt: &str, items: &[String]) -> Result<usize> {
+        self.ask_select(prompt, items, 0)
+    }
+
+    /// 输入一个值并用 `validate` 校验，校验失败时提示并要求重新输入
+    pub fn input_validated<F>(&self, prompt: &str, default: &str, validate: F) -> Result<String>
+    where
+        F: Fn(&str) -> Result<()>,
+    {
+        let value = self.ask_input(prompt, Some(default), false, |input| validate(input).map_err(|e| e.to_string()))?;
+
+        Ok(value.trim().to_string())
+    }
     
     /// 显示欢迎界面
     pub fn show_welcome(&self) {
@@ -27,43 +405,81 @@ impl UserInterface {
         println!("{}", style("║   ©2026 Max Shin - All Rights Reserved.  ║").cyan());
         println!("{}", style("╚══════════════════════════════════════════╝").cyan());
         println!();
-        println!("欢迎使用 Alpha Bank Union 通用密钥生成器");
-        println!("此工具将为您生成安全的ECC密钥对");
+        match self.language {
+            Language::Zh => {
+                println!("欢迎使用 Alpha Bank Union 通用密钥生成器");
+                println!("此工具将为您生成安全的ECC密钥对");
+            }
+            Language::En => {
+                println!("Welcome to the Alpha Bank Union key generator");
+                println!("This tool generates secure ECC key pairs for you");
+            }
+        }
         println!();
     }
     
     /// 选择主操作
     pub fn select_operation(&self) -> Result<Operation> {
-        let items = vec![
-            "生成新的密钥对",
-            "解密/导出私钥（需密码）",
-            "退出程序",
-        ];
-        
-        let selection = Select::with_theme(&self.theme)
-            .with_prompt("请选择要执行的操作")
-            .items(&items)
-            .default(0)
-            .interact()?;
-        
-        match selection {
-            0 => Ok(Operation::Generate),
-            1 => Ok(Operation::Decrypt),
-            2 => Ok(Operation::Exit),
-            _ => Err(anyhow::anyhow!("无效的选择"))
-        }
+        let mut items = vec![("生成新的密钥对", Operation::Generate)];
+        #[cfg(feature = "remote-keygen")]
+        items.push(("在远程签名服务（HSM）上生成密钥", Operation::RemoteGenerate));
+        items.extend([
+            ("解密/导出私钥（需密码）", Operation::Decrypt),
+            ("比较两个密钥文件（指纹）", Operation::Compare),
+            ("验证公钥与加密私钥是否配对（需密码）", Operation::VerifyPair),
+            ("验证分离签名（在公钥环中查找签名者）", Operation::VerifySignature),
+            ("验证生成文件清单（签名与各文件哈希）", Operation::VerifyManifest),
+            ("从私钥文件导出仅含公钥的证书", Operation::StripSecret),
+            ("解出加密备份包", Operation::ExtractBackup),
+            ("用密码加密任意文件", Operation::EncryptFile),
+            ("解密用密码加密的文件", Operation::DecryptFile),
+            ("密码恢复（从候选列表尝试，仅限本人）", Operation::RecoverPassword),
+            ("更换主 User ID（需密码）", Operation::ChangePrimaryUserId),
+            ("修改私钥密码（重新加密并更换 salt）", Operation::ChangePassword),
+            ("使用托管恢复密钥解密私钥", Operation::RecoveryDecrypt),
+            ("导出证书指纹 (.fpr)", Operation::ExportFingerprint),
+            ("查看密钥 ID（长/短 Key ID 与 keygrip）", Operation::ShowKeyIds),
+            ("查看密钥元数据（JSON，校验字段）", Operation::ViewMetadata),
+            ("导出到 Web Key Directory (WKD)", Operation::ExportWkd),
+            ("导出公钥为 SPKI PEM（供非 OpenPGP 系统使用）", Operation::ExportSpki),
+            ("导出证书 JSON（供网页工具使用）", Operation::ExportJson),
+        ]);
+        #[cfg(feature = "card")]
+        items.push(("导出公钥卡片 (PNG，含指纹二维码)", Operation::ExportCard));
+        #[cfg(feature = "recovery-sheet")]
+        items.push(("导出离线恢复单 (PDF，含公钥二维码)", Operation::ExportRecoverySheet));
+        #[cfg(feature = "card")]
+        items.push(("导出吊销证书为分片二维码 (PNG)", Operation::ExportRevocationQr));
+        items.extend([
+            ("从扫描的二维码重组吊销证书", Operation::ImportRevocationQr),
+            ("认证他人的公钥（User ID）", Operation::CertifyKey),
+            ("生成密钥迁移声明（新旧密钥共同签名）", Operation::TransitionStatement),
+            ("导入 GnuPG 私钥并转换为 ABU 格式", Operation::ImportGnupg),
+            ("诊断截断/损坏的密钥文件", Operation::Diagnose),
+            ("紧急销毁目录中的私钥（不可恢复）", Operation::PanicWipe),
+            ("设置", Operation::Settings),
+            ("退出程序", Operation::Exit),
+        ]);
+        let labels: Vec<&str> = items.iter().map(|(label, _)| *label).collect();
+
+        let selection = self.ask_select("请选择要执行的操作", &labels, 0)?;
+
+        items
+            .get(selection)
+            .map(|(_, op)| *op)
+            .ok_or_else(|| anyhow::anyhow!("无效的选择"))
     }
     
-    /// 输入密码
+    /// 输入密码；`confirmation` 为 true 时表示设置新密码，需满足密码规则并再次确认
+    ///
+    /// 不满足规则时提示具体是哪一条并要求重新输入。
     pub fn input_password(&self, prompt: &str, confirmation: bool) -> Result<String> {
-        let password = Password::with_theme(&self.theme)
-            .with_prompt(prompt)
-            .interact()?;
+        let password = self.ask_password(prompt, false, |input| {
+            if confirmation { self.password_policy.check(input) } else { Ok(()) }
+        })?;
         
         if confirmation {
-            let confirm = Password::with_theme(&self.theme)
-                .with_prompt("请再次确认密码")
-                .interact()?;
+            let confirm = self.ask_password("请再次确认密码", false, |_| Ok(()))?;
             
             if password != confirm {
                 return Err(anyhow::anyhow!("两次输入的密码不一致"));
@@ -73,55 +489,273 @@ impl UserInterface {
         Ok(password)
     }
     
-    /// 输入银行/城镇名称
-    pub fn input_bank_name(&self) -> Result<String> {
-        let name: String = Input::with_theme(&self.theme)
-            .with_prompt("请输入您的银行/玩家名称")
-            .default("Example".to_string())
-            .interact()?;
+    /// 输入 BIP39 助记词与可选口令（输入时不可见）
+    pub fn input_mnemonic(&self) -> Result<(String, String)> {
+        let mnemonic = self.ask_password("请输入 BIP39 助记词（单词以空格分隔）", false, |_| Ok(()))?;
+        let passphrase = self.ask_password("请输入助记词口令（可留空）", true, |_| Ok(()))?;
+
+        Ok((mnemonic, passphrase))
+    }
+
+    /// 确定性生成模式的风险提示
+    pub fn show_mnemonic_warning(&self) {
+        println!();
+        println!("{}", style("高级模式：由助记词确定性生成密钥").red().bold());
+        println!("{}", style("1. 任何知道助记词与口令的人都能重新生成您的私钥").red());
+        println!("{}", style("2. 助记词的强度即私钥的强度，切勿使用自行编造的短语").red());
+        println!("{}", style("3. 密钥固定为 Ed25519/Cv25519、永不过期，创建时间固定为 2020-01-01").red());
+        println!("{}", style("4. 仍会照常保存加密私钥文件；请勿把助记词与这些文件存放在一起").red());
+    }
+
+    /// 输入银行/城镇名称；`default` 为上次未完成生成时输入的值
+    pub fn input_bank_name(&self, default: Option<&str>) -> Result<String> {
+        let name = self.ask_input("请输入您的银行/玩家名称", Some(default.unwrap_or("Example")), false, |_| Ok(()))?;
         
         Ok(name)
     }
 
-    /// 输入邮箱地址（用于 OpenPGP User ID）
-    pub fn input_email(&self) -> Result<String> {
-        let email: String = Input::with_theme(&self.theme)
-            .with_prompt("请输入您的电子邮箱 (用于 User ID)")
-            .validate_with(|input: &String| {
-                if input.contains('@') && input.contains('.') {
-                    Ok(())
+    /// 输入邮箱地址（用于 OpenPGP User ID）；`default` 为上次未完成生成时输入的值
+    pub fn input_email(&self, default: Option<&str>) -> Result<String> {
+        let email = self.ask_input("请输入您的电子邮箱 (用于 User ID)", default, false, |input| {
+            crate::userid::validate_email(input).map_err(|e| e.to_string())
+        })?;
+
+        Ok(email.trim().to_string())
+    }
+
+    /// 选择密钥曲线；当前后端不支持的曲线会被标注且不可选
+    pub fn select_curve(
+        &self,
+        curves: &[(crate::security::KeyCurve, bool)],
+        default: crate::security::KeyCurve,
+    ) -> Result<crate::security::KeyCurve> {
+        let labels: Vec<String> = curves
+            .iter()
+            .map(|(curve, supported)| {
+                if *supported {
+                    curve.name().to_string()
                 } else {
-                    Err("请输入有效的邮箱地址")
+                    format!("{}（当前后端不支持）", curve.name())
                 }
             })
-            .interact()?;
+            .collect();
+        let default_index = curves.iter().position(|(curve, _)| *curve == default).unwrap_or(0);
+
+        loop {
+            let selection = self.ask_select("请选择密钥曲线", &labels, default_index)?;
+
+            match curves.get(selection) {
+                Some((curve, true)) => return Ok(*curve),
+                Some((curve, false)) => {
+                    self.show_error(&format!("当前加密后端无法生成 {}，请选择其他曲线", curve.name()));
+                }
+                None => return Err(anyhow::anyhow!("无效的选择")),
+            }
+        }
+    }
+
+    /// 输入附加 User ID 的名称
+    pub fn input_user_id_name(&self, default: &str) -> Result<String> {
+        let name = self.ask_input("请输入 User ID 的名称", Some(default), false, |_| Ok(()))?;
 
-        Ok(email)
+        Ok(name)
+    }
+
+    /// 危险操作确认：倒计时后要求用户完整输入指定短语
+    pub fn confirm_dangerous(&self, phrase: &str) -> Result<bool> {
+        println!("{}", style("这是一个危险操作，请仔细阅读上面的警告。").red().bold());
+        for remaining in (1..=DANGEROUS_COUNTDOWN_SECS).rev() {
+            print!("\r{} 秒后可继续...", remaining);
+            std::io::Write::flush(&mut std::io::stdout())?;
+            std::thread::sleep(std::time::Duration::from_secs(1));
+        }
+        println!();
+
+        let input = self.ask_input(&format!("如确认继续，请输入 \"{}\"", phrase), None, true, |_| Ok(()))?;
+
+        Ok(phrase_matches(&input, phrase))
+    }
+
+    /// 确认提示
+    pub fn confirm(&self, prompt: &str, default: bool) -> Result<bool> {
+        self.ask_confirm(prompt, default)
     }
     
-    /// 选择文件保存位置
+    /// 选择文件保存位置；位于版本库工作区内时警告并要求确认，防止私钥被误提交
     pub fn select_save_location(&self, default_name: &str) -> Result<PathBuf> {
-        let path = FileDialog::new()
+        loop {
+            let path = self.pick_save_location(default_name)?;
+            let dir = path.parent().unwrap_or(std::path::Path::new("."));
+            let Some(root) = find_vcs_root(dir, &self.vcs_markers) else {
+                return Ok(path);
+            };
+            println!(
+                "{} {}",
+                style("⚠").yellow().bold(),
+                style(format!("{} 位于版本库工作区 {} 内，密钥文件可能被误提交！", path.display(), root.display()))
+                    .yellow()
+                    .bold(),
+            );
+            if self.confirm("仍要保存到该位置吗？", false)? {
+                return Ok(path);
+            }
+        }
+    }
+
+    /// 文件保存对话框
+    #[cfg(feature = "gui")]
+    fn pick_save_location(&self, default_name: &str) -> Result<PathBuf> {
+        if let Some(answer) = self.scripted("选择密钥保存位置", false)? {
+            return Ok(PathBuf::from(answer));
+        }
+        let mut dialog = FileDialog::new()
             .set_title("选择密钥保存位置")
-            .set_filename(default_name)
+            .set_filename(default_name);
+        if let Some(dir) = &self.output_dir {
+            dialog = dialog.set_location(dir);
+        }
+        let path = dialog
             .show_save_single_file()
             .map_err(|e| anyhow::anyhow!("文件对话框错误: {:?}", e))?
-            .ok_or_else(|| anyhow::anyhow!("用户取消了文件选择"))?;
+            .ok_or_else(|| AbuError::Cancelled("文件选择".to_string()))?;
         
         Ok(path)
     }
 
+    /// 输入文件保存位置（无 GUI 构建）
+    #[cfg(not(feature = "gui"))]
+    fn pick_save_location(&self, default_name: &str) -> Result<PathBuf> {
+        let default_path = match &self.output_dir {
+            Some(dir) => dir.join(default_name).display().to_string(),
+            None => default_name.to_string(),
+        };
+        let path = self.ask_input("请输入保存路径", Some(default_path.as_str()), false, |_| Ok(()))?;
+
+        Ok(PathBuf::from(path.trim()))
+    }
+
     /// 选择要打开的私钥文件（解密用）
     pub fn select_open_location(&self) -> Result<PathBuf> {
+        self.select_key_file("选择要解密的私钥文件", FileFilter::EncryptedKey)
+    }
+
+    /// 选择密钥相关文件；扩展名不符合用途时警告并要求确认，防止误选
+    pub fn select_key_file(&self, title: &str, filter: FileFilter) -> Result<PathBuf> {
+        loop {
+            let path = self.pick_key_file(title, filter)?;
+            if has_expected_extension(&path, filter.extensions()) {
+                return Ok(path);
+            }
+            println!(
+                "{} {}",
+                style("⚠").yellow().bold(),
+                style(format!("{} 的扩展名不是预期的 {}", path.display(), filter.description())).yellow(),
+            );
+            if self.confirm("仍要使用该文件吗？", false)? {
+                return Ok(path);
+            }
+        }
+    }
+
+    /// 文件对话框只列出该用途的扩展名（仍可切换为显示全部文件）
+    #[cfg(feature = "gui")]
+    fn pick_key_file(&self, title: &str, filter: FileFilter) -> Result<PathBuf> {
+        if let Some(answer) = self.scripted(title, false)? {
+            return Ok(PathBuf::from(answer));
+        }
+        let path = FileDialog::new()
+            .set_title(title)
+            .add_filter(filter.description(), filter.extensions())
+            .add_filter("所有文件", &["*"])
+            .show_open_single_file()
+            .map_err(|e| anyhow::anyhow!("文件对话框错误: {:?}", e))?
+            .ok_or_else(|| AbuError::Cancelled("文件选择".to_string()))?;
+
+        Ok(path)
+    }
+
+    /// 输入路径（无 GUI 构建），扩展名由调用方检查
+    #[cfg(not(feature = "gui"))]
+    fn pick_key_file(&self, title: &str, _filter: FileFilter) -> Result<PathBuf> {
+        self.select_open_file(title)
+    }
+
+    /// 以指定标题选择要打开的文件
+    #[cfg(feature = "gui")]
+    pub fn select_open_file(&self, title: &str) -> Result<PathBuf> {
+        if let Some(answer) = self.scripted(title, false)? {
+            return Ok(PathBuf::from(answer));
+        }
         let path = FileDialog::new()
-            .set_title("选择要解密的私钥文件")
+            .set_title(title)
             .show_open_single_file()
             .map_err(|e| anyhow::anyhow!("文件对话框错误: {:?}", e))?
-            .ok_or_else(|| anyhow::anyhow!("用户取消了文件选择"))?;
+            .ok_or_else(|| AbuError::Cancelled("文件选择".to_string()))?;
 
         Ok(path)
     }
+
+    /// 以指定提示输入要打开的文件路径（无 GUI 构建）
+    #[cfg(not(feature = "gui"))]
+    pub fn select_open_file(&self, title: &str) -> Result<PathBuf> {
+        let path = self.ask_input(title, None, false, |input| {
+            if std::path::Path::new(input.trim()).is_file() {
+                Ok(())
+            } else {
+                Err("文件不存在".to_string())
+            }
+        })?;
+
+        Ok(PathBuf::from(path.trim()))
+    }
+    
+    /// 选择目录
+    #[cfg(feature = "gui")]
+    pub fn select_directory(&self, title: &str) -> Result<PathBuf> {
+        if let Some(answer) = self.scripted(title, false)? {
+            return Ok(PathBuf::from(answer));
+        }
+        let path = FileDialog::new()
+            .set_title(title)
+            .show_open_single_dir()
+            .map_err(|e| anyhow::anyhow!("文件对话框错误: {:?}", e))?
+            .ok_or_else(|| AbuError::Cancelled("目录选择".to_string()))?;
+
+        Ok(path)
+    }
+
+    /// 输入目录路径（无 GUI 构建）
+    #[cfg(not(feature = "gui"))]
+    pub fn select_directory(&self, title: &str) -> Result<PathBuf> {
+        let path = self.ask_input(title, None, false, |input| {
+            if std::path::Path::new(input.trim()).is_dir() {
+                Ok(())
+            } else {
+                Err("目录不存在".to_string())
+            }
+        })?;
+
+        Ok(PathBuf::from(path.trim()))
+    }
     
+    /// 敏感操作结束后，经确认清除终端，避免私钥、元数据等残留在屏幕与滚动缓冲中
+    ///
+    /// 未启用或输出不是终端时不做任何事。
+    pub fn clear_sensitive(&self) -> Result<()> {
+        let term = console::Term::stdout();
+        if self.clear_mode == ClearMode::Off || !term.is_term() {
+            return Ok(());
+        }
+        let prompt = match self.clear_mode {
+            ClearMode::Scrollback => "是否清除屏幕与滚动缓冲，避免敏感信息残留？",
+            _ => "是否清除屏幕，避免敏感信息残留？",
+        };
+        if self.confirm(prompt, true)? {
+            term.write_str(clear_sequence(self.clear_mode))?;
+        }
+        Ok(())
+    }
+
     /// 显示成功消息
     pub fn show_success(&self, message: &str) {
         println!();
@@ -136,25 +770,197 @@ impl UserInterface {
         println!();
     }
     
+    /// 熵池不足时的提示（不阻止继续生成）
+    pub fn show_low_entropy_warning(&self, bits: u32) {
+        println!();
+        println!(
+            "{} {}",
+            style("⚠").yellow().bold(),
+            style(format!("系统熵池可用位数较低（{} 位），生成的密钥可能不够随机", bits)).yellow().bold()
+        );
+        println!("{}", style("建议稍等片刻，或通过键盘/鼠标操作、启用 rng-tools/haveged 等方式补充熵后再生成。").yellow());
+    }
+
+    /// 最小证书的安全取舍说明
+    pub fn show_minimal_cert_warning(&self) {
+        println!();
+        println!("{} {}", style("⚠").yellow().bold(), style("最小证书：不生成子密钥，主密钥同时用于认证、签名与加密").yellow().bold());
+        println!("{}", style("1. 椭圆曲线密钥不能同时签名与加密，因此改用 RSA 3072 主密钥").yellow());
+        println!("{}", style("2. 无法单独轮换或吊销签名/加密密钥，主密钥泄露即全部失效").yellow());
+        println!("{}", style("3. 同一密钥既签名又解密，增加了误用的风险；仅在对方系统只支持单个密钥时使用").yellow());
+    }
+
+    /// 系统时钟偏差过大时的提示；`skew` 为本机相对时间源的秒数
+    pub fn show_clock_skew_warning(&self, skew: i64) {
+        let direction = if skew > 0 { "快" } else { "慢" };
+        println!();
+        println!(
+            "{} {}",
+            style("⚠").yellow().bold(),
+            style(format!("系统时钟比时间源{}了约 {} 秒", direction, skew.unsigned_abs())).yellow().bold()
+        );
+        println!("{}", style("证书的创建时间与有效期将以错误的时钟为准，其他系统可能认为密钥尚未生效或已过期。请先校准系统时间。").yellow());
+    }
+
     /// 显示重要警告
     pub fn show_warning(&self) {
         println!();
         println!("{}", style("重要安全警告:").yellow().bold());
         println!("{}", style("1. 请务必备份您的私钥文件").yellow());
         println!("{}", style("2. 不要将私钥分享给任何人").yellow());
-        println!("{}", style("3. 设置强密码（建议12位以上）").yellow());
+        println!("{}", style(format!("3. 设置强密码（{}）", self.password_policy.describe())).yellow());
         println!("{}", style("4. 私钥丢失将导致您使用此密钥加密的资产永久无法访问").yellow());
         
-        Confirm::with_theme(&self.theme)
-            .with_prompt("我已阅读并理解上述警告")
-            .default(true)
-            .interact()
-            .ok();
+        self.ask_confirm("我已阅读并理解上述警告", true).ok();
     }
 }
 
+/// 显示加密后端初始化失败的提示及建议的补救措施
+pub fn show_backend_error(error: &anyhow::Error) {
+    eprintln!();
+    eprintln!("{} {}", style("✗").red().bold(), style("加密后端初始化失败").red().bold());
+    eprintln!("  {}", style(error).red());
+    eprintln!();
+    eprintln!("{}", style("建议:").yellow().bold());
+    eprintln!("{}", style("1. Windows: 确认系统已安装最新更新，CNG 服务 (KeyIso) 正在运行").yellow());
+    eprintln!("{}", style("2. 其他平台: 确认已安装 Nettle 等后端所需的系统库").yellow());
+    eprintln!("{}", style("3. 以管理员身份重新运行本程序，或更换为其他后端重新编译").yellow());
+    eprintln!();
+    eprintln!("{}", style("密钥生成功能已禁用，解密功能仍可使用。").yellow());
+    eprintln!();
+}
+
+#[derive(Clone, Copy)]
 pub enum Operation {
     Generate,
+    #[cfg(feature = "remote-keygen")]
+    RemoteGenerate,
     Decrypt,
+    Compare,
+    VerifyPair,
+    VerifySignature,
+    VerifyManifest,
+    StripSecret,
+    ExtractBackup,
+    EncryptFile,
+    DecryptFile,
+    RecoverPassword,
+    ChangePrimaryUserId,
+    ChangePassword,
+    RecoveryDecrypt,
+    ExportFingerprint,
+    ShowKeyIds,
+    ViewMetadata,
+    ExportWkd,
+    ExportSpki,
+    ExportJson,
+    #[cfg(feature = "card")]
+    ExportCard,
+    #[cfg(feature = "recovery-sheet")]
+    ExportRecoverySheet,
+    #[cfg(feature = "card")]
+    ExportRevocationQr,
+    ImportRevocationQr,
+    CertifyKey,
+    TransitionStatement,
+    ImportGnupg,
+    Diagnose,
+    PanicWipe,
+    Settings,
     Exit,
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn phrase_must_match_exactly_apart_from_surrounding_whitespace() {
+        assert!(phrase_matches("I UNDERSTAND", "I UNDERSTAND"));
+        assert!(phrase_matches("  I UNDERSTAND\n", "I UNDERSTAND"));
+        assert!(!phrase_matches("i understand", "I UNDERSTAND"));
+        assert!(!phrase_matches("I UNDERSTAN", "I UNDERSTAND"));
+        assert!(!phrase_matches("", "I UNDERSTAND"));
+    }
+
+    #[test]
+    fn interrupted_prompt_maps_to_cancel() {
+        let interrupted = || dialoguer::Error::IO(std::io::Error::new(std::io::ErrorKind::Interrupted, "read interrupted"));
+        assert!(crate::error::is_cancelled(&interrupt_as_cancel(interrupted(), "密码输入")));
+        let other = dialoguer::Error::IO(std::io::Error::new(std::io::ErrorKind::BrokenPipe, "closed"));
+        assert!(!crate::error::is_cancelled(&interrupt_as_cancel(other, "密码输入")));
+
+        // SIGINT 处理器标记的中断优先于提示本身的结果
+        let error = finish_password_prompt(Ok(String::new()), true, "密码输入").unwrap_err();
+        assert!(crate::error::is_cancelled(&error));
+        assert_eq!(crate::error::exit_code_for(&error), crate::error::exit_code::CANCELLED);
+        assert!(crate::error::is_cancelled(&finish_password_prompt(Err(interrupted()), false, "密码输入").unwrap_err()));
+        assert_eq!(finish_password_prompt(Ok("secret".to_string()), false, "密码输入").unwrap(), "secret");
+    }
+
+    #[cfg(feature = "test-ui")]
+    #[test]
+    fn new_password_must_meet_minimum_length() {
+        let policy = crate::password::PasswordPolicy::default();
+        assert_eq!(policy.min_length, crate::config::DEFAULT_MIN_PASSWORD_LENGTH);
+
+        let ui = UserInterface::with_script(["short"]).with_password_policy(policy.clone());
+        let error = ui.input_password("设置密码", true).unwrap_err();
+        assert!(error.to_string().contains("至少需要 12 个字符"), "{}", error);
+
+        let long = "long-enough-pw";
+        let ui = UserInterface::with_script([long, long]).with_password_policy(policy.clone());
+        assert_eq!(ui.input_password("设置密码", true).unwrap(), long);
+
+        // 解密时输入已有密码不受长度限制
+        let ui = UserInterface::with_script(["short"]).with_password_policy(policy);
+        assert_eq!(ui.input_password("解密密码", false).unwrap(), "short");
+    }
+
+    #[test]
+    fn extension_check_is_case_insensitive_and_per_filter() {
+        use std::path::Path;
+
+        let key = FileFilter::EncryptedKey.extensions();
+        assert!(has_expected_extension(Path::new("bank_private.bin"), key));
+        assert!(has_expected_extension(Path::new("dir/bank_private.ASC"), key));
+        assert!(!has_expected_extension(Path::new("bank_public.gpg"), key));
+        assert!(!has_expected_extension(Path::new("bank_private"), key));
+        assert!(!has_expected_extension(Path::new("bank.bin.txt"), key));
+
+        let signature = FileFilter::Signature.extensions();
+        assert!(has_expected_extension(Path::new("report.pdf.sig"), signature));
+        assert!(!has_expected_extension(Path::new("report.pdf"), signature));
+        assert!(has_expected_extension(Path::new("bank_public.gpg"), FileFilter::Cert.extensions()));
+    }
+
+    #[test]
+    fn vcs_root_is_found_from_nested_directory() {
+        let root = crate::testutil::temp_dir("vcs-root");
+        std::fs::create_dir(root.join(".git")).unwrap();
+        let nested = root.join("keys").join("2026").join("alpha");
+        std::fs::create_dir_all(&nested).unwrap();
+        let markers = crate::config::DEFAULT_VCS_MARKERS.map(str::to_string).to_vec();
+
+        assert_eq!(find_vcs_root(&nested, &markers), Some(root.clone()));
+        // 尚未创建的目标目录按其已存在的上级判断
+        assert_eq!(find_vcs_root(&nested.join("not-yet-created"), &markers), Some(root.clone()));
+        let outside = crate::testutil::temp_dir("vcs-root-outside");
+        assert_eq!(find_vcs_root(&outside, &markers), None);
+        // 标记可配置：只检查 .hg 时不再认为位于版本库内
+        assert_eq!(find_vcs_root(&nested, &[".hg".to_string()]), None);
+        assert_eq!(find_vcs_root(&nested, &[]), None);
+    }
+
+    #[test]
+    fn clear_sequence_emits_expected_escape_codes() {
+        assert_eq!(clear_sequence(ClearMode::Off), "");
+        assert_eq!(clear_sequence(ClearMode::Screen), "\x1b[H\x1b[2J");
+        assert_eq!(clear_sequence(ClearMode::Scrollback), "\x1b[H\x1b[2J\x1b[3J");
+        // 只有 Scrollback 才清除滚动缓冲
+        assert!(!clear_sequence(ClearMode::Screen).contains("\x1b[3J"));
+
+        assert_eq!(ClearMode::from_name("Scrollback"), Some(ClearMode::Scrollback));
+        assert_eq!(ClearMode::from_name("OFF"), Some(ClearMode::Off));
+        assert_eq!(ClearMode::from_name("all"), None);
+    }
+}
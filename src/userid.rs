@@ -0,0 +1,146 @@
+use anyhow::{anyhow, Result};
+
+/// 校验邮箱格式（比输入框的 `@` + `.` 检查更严格）
+pub fn validate_email(email: &str) -> std::result::Result<(), &'static str> {
+    let email = email.trim();
+    if email.chars().any(|c| c.is_whitespace() || c == '<' || c == '>') {
+        return Err("邮箱地址不能包含空白或尖括号");
+    }
+
+    let (local, domain) = email.split_once('@').ok_or("邮箱地址缺少 @")?;
+    if local.is_empty() || domain.is_empty() || domain.contains('@') {
+        return Err("邮箱地址格式无效");
+    }
+    if !domain.contains('.') || domain.starts_with('.') || domain.ends_with('.') || domain.contains("..") {
+        return Err("邮箱域名格式无效");
+    }
+
+    Ok(())
+}
+
+/// 规范化邮箱：去除首尾空白并转为小写，用于去重比较
+pub fn normalize_email(email: &str) -> String {
+    email.trim().to_lowercase()
+}
+
+/// 构建 `名称 <邮箱>` 形式的 User ID
+pub fn format_user_id(name: &str, email: &str) -> String {
+    format!("{} <{}>", name.trim(), email.trim())
+}
+
+/// 检查邮箱域名是否在允许列表中（不区分大小写）；列表为空时不限制
+pub fn check_email_domain(email: &str, allowed: &[String]) -> Result<()> {
+    if allowed.is_empty() {
+        return Ok(());
+    }
+    let domain = normalize_email(email).rsplit_once('@').map(|(_, d)| d.to_string()).unwrap_or_default();
+    if allowed.iter().any(|a| a.eq_ignore_ascii_case(&domain)) {
+        Ok(())
+    } else {
+        Err(anyhow!("邮箱域名 {} 不在允许的域名列表中（{}）", domain, allowed.join(", ")))
+    }
+}
+
+/// 生成时收集的 User ID 集合，拒绝重复项
+#[derive(Default)]
+pub struct UserIdSet {
+    /// (User ID, 规范化邮箱)
+    entries: Vec<(String, String)>,
+    /// 允许的邮箱域名；为空时不限制
+    allowed_domains: Vec<String>,
+    /// 附加在每个名称后的 User ID 注释（`名称 (注释) <邮箱>`）
+    comment: Option<String>,
+}
+
+impl UserIdSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 启用域名策略：只接受允许列表中的邮箱域名
+    pub fn with_allowed_domains(mut self, domains: &[String]) -> Self {
+        self.allowed_domains = domains.to_vec();
+        self
+    }
+
+    /// 为之后添加的每个 User ID 附加注释；None 时不附加
+    pub fn with_comment(mut self, comment: Option<&str>) -> Self {
+        self.comment = comment.map(str::to_string);
+        self
+    }
+
+    /// 添加一个 User ID；完全相同或邮箱仅大小写不同的重复项会被拒绝
+    pub fn add(&mut self, name: &str, email: &str) -> Result<()> {
+        validate_email(email).map_err(|e| anyhow!("{}: {}", e, email))?;
+        check_email_domain(email, &self.allowed_domains)?;
+
+        let user_id = match &self.comment {
+            Some(comment) => format_user_id(&format!("{} ({})", name.trim(), comment), email),
+            None => format_user_id(name, email),
+        };
+        let normalized = normalize_email(email);
+        if self.entries.iter().any(|(uid, _)| *uid == user_id) {
+            return Err(anyhow!("User ID 已存在: {}", user_id));
+        }
+        if self.entries.iter().any(|(_, existing)| *existing == normalized) {
+            return Err(anyhow!("邮箱已被其他 User ID 使用（不区分大小写）: {}", email));
+        }
+
+        self.entries.push((user_id, normalized));
+        Ok(())
+    }
+
+    /// 按添加顺序返回全部 User ID（第一个为主 User ID）
+    pub fn user_ids(&self) -> Vec<String> {
+        self.entries.iter().map(|(uid, _)| uid.clone()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exact_duplicate_is_rejected() {
+        let mut set = UserIdSet::new();
+        set.add("TestBank", "bank@example.com").unwrap();
+
+        assert!(set.add("TestBank", "bank@example.com").is_err());
+        assert_eq!(set.user_ids().len(), 1);
+    }
+
+    #[test]
+    fn email_duplicate_differing_in_case_is_rejected() {
+        let mut set = UserIdSet::new();
+        set.add("TestBank", "bank@example.com").unwrap();
+
+        assert!(set.add("TestBank Treasury", "Bank@Example.COM").is_err());
+        assert_eq!(set.user_ids().len(), 1);
+    }
+
+    #[test]
+    fn distinct_second_user_id_is_accepted() {
+        let mut set = UserIdSet::new();
+        set.add("TestBank", "bank@example.com").unwrap();
+        set.add("TestBank Treasury", "treasury@example.com").unwrap();
+
+        assert_eq!(
+            set.user_ids(),
+            ["TestBank <bank@example.com>", "TestBank Treasury <treasury@example.com>"],
+        );
+    }
+
+    #[test]
+    fn domain_policy_accepts_only_allowed_domains() {
+        let allowed = ["alphabank.example".to_string()];
+        let mut set = UserIdSet::new().with_allowed_domains(&allowed);
+
+        set.add("Alpha Bank", "ops@AlphaBank.example").unwrap();
+        let error = set.add("Alpha Bank Treasury", "treasury@gmail.example").unwrap_err();
+        assert!(error.to_string().contains("gmail.example"), "{}", error);
+        assert_eq!(set.user_ids(), ["Alpha Bank <ops@AlphaBank.example>"]);
+
+        // 未配置域名列表时不限制
+        UserIdSet::new().add("Anyone", "someone@gmail.example").unwrap();
+    }
+}
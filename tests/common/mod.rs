@@ -0,0 +1,142 @@
+//! 集成测试共用的辅助函数：在临时目录中运行编译出的程序并检查其输出
+//!
+//! 每个集成测试文件只用到其中一部分函数。
+#![allow(dead_code)]
+
+use sequoia_openpgp as openpgp;
+use openpgp::parse::stream::{DetachedVerifierBuilder, MessageLayer, MessageStructure, VerificationHelper};
+use openpgp::parse::Parse;
+use openpgp::policy::StandardPolicy;
+use openpgp::{Cert, KeyHandle};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// 测试密钥的私钥密码（满足默认密码规则）
+pub const PASSWORD: &str = "Correct-Horse-Battery-42";
+
+/// 作业文件生成的测试密钥
+pub struct TestKey {
+    pub public: PathBuf,
+    pub private: PathBuf,
+    pub password_file: PathBuf,
+}
+
+/// 为测试创建一个空的临时目录（按名称与进程号区分，已存在时先清空）
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("abu-keygentool-it-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// 以 `dir` 为当前目录运行程序；不继承 ABU_* 环境变量，目录中没有配置文件时使用默认配置
+pub fn command(dir: &Path) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_abu-keygentool"));
+    command.current_dir(dir);
+    for (key, _) in std::env::vars_os() {
+        if key.to_string_lossy().starts_with("ABU_") {
+            command.env_remove(key);
+        }
+    }
+    command
+}
+
+/// 目录中文件名包含 `marker` 的唯一文件
+pub fn find_file(dir: &Path, marker: &str) -> PathBuf {
+    let matches: Vec<PathBuf> = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.file_name().unwrap().to_string_lossy().contains(marker))
+        .collect();
+    assert_eq!(matches.len(), 1, "{} 中应有且只有一个包含 {} 的文件: {:?}", dir.display(), marker, matches);
+    matches.into_iter().next().unwrap()
+}
+
+/// 用作业文件在 `dir` 中非交互生成一个 P-256 测试密钥
+pub fn generate_key(dir: &Path) -> TestKey {
+    let password_file = dir.join("password.txt");
+    std::fs::write(&password_file, PASSWORD).unwrap();
+    let job = dir.join("job.toml");
+    std::fs::write(&job, "bank_name = \"TestBank\"\nemails = [\"bank@example.com\"]\n").unwrap();
+
+    let output = command(dir)
+        .args(["job", "--input", "job.toml", "--password-file", "password.txt", "--iterations", "10000"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "生成测试密钥失败: {}", String::from_utf8_lossy(&output.stderr));
+
+    TestKey {
+        public: find_file(dir, "_public_"),
+        private: find_file(dir, "_private_"),
+        password_file,
+    }
+}
+
+/// 在 `dir` 中写入测试配置：降低迭代次数以加快测试，不写备份提醒状态，不检查版本库
+pub fn write_test_config(dir: &Path) {
+    std::fs::write(
+        dir.join("abu-keygentool.toml"),
+        "encryption_iterations = 10000\nbackup_reminder = false\nvcs_markers = []\n",
+    )
+    .unwrap();
+}
+
+/// 一次完整生成的脚本应答（从主菜单开始）：接受警告、P-256、不添加其他 User ID、确认写入、不导出指纹与备份
+pub fn generation_script(public_path: &Path) -> Vec<String> {
+    [
+        "生成新的密钥对",
+        "y",
+        "TestBank",
+        PASSWORD,
+        PASSWORD,
+        "0",
+        "bank@example.com",
+        "n",
+        "",
+        &public_path.display().to_string(),
+        "y",
+        "n",
+        "n",
+    ]
+    .map(str::to_string)
+    .to_vec()
+}
+
+/// 以脚本应答驱动一次交互会话（需要 `test-ui` 构建），返回进程输出
+pub fn run_script(dir: &Path, args: &[&str], answers: &[String]) -> std::process::Output {
+    let script = dir.join("script.txt");
+    std::fs::write(&script, answers.join("\n") + "\n").unwrap();
+    command(dir).args(args).env("ABU_UI_SCRIPT", &script).output().unwrap()
+}
+
+/// 用公钥文件验证分离签名
+pub fn verify_detached(public: &Path, signature: &[u8], data: &[u8]) -> bool {
+    let cert = Cert::from_file(public).unwrap();
+    let policy = StandardPolicy::new();
+    let verified = DetachedVerifierBuilder::from_bytes(signature)
+        .and_then(|builder| builder.with_policy(&policy, None, Helper { cert }))
+        .and_then(|mut verifier| verifier.verify_bytes(data));
+    verified.is_ok()
+}
+
+/// 只提供一个证书，要求至少有一个有效签名
+struct Helper {
+    cert: Cert,
+}
+
+impl VerificationHelper for Helper {
+    fn get_certs(&mut self, _ids: &[KeyHandle]) -> openpgp::Result<Vec<Cert>> {
+        Ok(vec![self.cert.clone()])
+    }
+
+    fn check(&mut self, structure: MessageStructure) -> openpgp::Result<()> {
+        for layer in structure.into_iter() {
+            if let MessageLayer::SignatureGroup { results } = layer
+                && results.iter().any(Result::is_ok)
+            {
+                return Ok(());
+            }
+        }
+        Err(anyhow::anyhow!("no valid signature"))
+    }
+}
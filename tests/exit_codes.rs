@@ -0,0 +1,37 @@
+//! 退出码约定：脚本按退出码区分失败原因
+
+mod common;
+
+#[test]
+fn wrong_password_exits_with_code_3() {
+    let dir = common::temp_dir("exit-wrong-password");
+    let key = common::generate_key(&dir);
+    let data = dir.join("data.txt");
+    std::fs::write(&data, "payload").unwrap();
+    let wrong = dir.join("wrong-password.txt");
+    std::fs::write(&wrong, "Not-The-Right-Password-1").unwrap();
+
+    let output = common::command(&dir)
+        .arg("sign")
+        .arg("--key")
+        .arg(&key.private)
+        .arg("--password-file")
+        .arg(&wrong)
+        .arg("--in")
+        .arg(&data)
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(3), "{}", String::from_utf8_lossy(&output.stderr));
+}
+
+#[test]
+fn bad_format_exits_with_code_4() {
+    let dir = common::temp_dir("exit-bad-format");
+    let garbage = dir.join("garbage.bin");
+    std::fs::write(&garbage, b"ABUK\x09not a real header").unwrap();
+
+    let output = common::command(&dir).arg("--dump-header").arg(&garbage).output().unwrap();
+
+    assert_eq!(output.status.code(), Some(4), "{}", String::from_utf8_lossy(&output.stderr));
+}
@@ -0,0 +1,23 @@
+//! 无 GUI 构建（`--no-default-features`）：文件对话框改为文本输入，仍能完成一次完整生成
+//!
+//! 运行方式：`cargo test --no-default-features --features test-ui --test headless`
+
+#![cfg(all(not(feature = "gui"), feature = "test-ui"))]
+
+mod common;
+
+#[test]
+fn text_prompts_generate_a_key_without_gui() {
+    let dir = common::temp_dir("headless");
+    common::write_test_config(&dir);
+    let public_path = dir.join("TestBank_public.asc");
+    let mut answers = common::generation_script(&public_path);
+    answers.push("n".to_string());
+
+    let output = common::run_script(&dir, &[], &answers);
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(public_path.is_file());
+    common::find_file(&dir, "_private_");
+    common::find_file(&dir, "_revocation_");
+}
@@ -0,0 +1,24 @@
+//! `--once`：执行一个操作后直接退出，不询问是否继续
+//!
+//! 需要 `test-ui` 构建：`cargo test --features test-ui --test once`
+
+#![cfg(feature = "test-ui")]
+
+mod common;
+
+#[test]
+fn once_exits_after_single_operation_without_prompting() {
+    let dir = common::temp_dir("once");
+    common::write_test_config(&dir);
+    let public_path = dir.join("TestBank_public.asc");
+    // 脚本中没有“是否继续”的应答：若仍然询问，应答用完会以错误退出
+    let answers = common::generation_script(&public_path);
+
+    let output = common::run_script(&dir, &["--once"], &answers);
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(!stdout.contains("是否继续执行其他操作"), "{}", stdout);
+    assert!(stdout.contains("感谢使用ABU密钥生成器"), "{}", stdout);
+    assert!(public_path.is_file());
+}
@@ -0,0 +1,46 @@
+//! 以脚本应答驱动完整的交互会话：主菜单 → 生成密钥 → 继续 → 从菜单退出
+//!
+//! 需要 `test-ui` 构建：`cargo test --features test-ui --test session`
+
+#![cfg(feature = "test-ui")]
+
+mod common;
+
+#[test]
+fn scripted_session_generates_then_exits_from_menu() {
+    let dir = common::temp_dir("session");
+    common::write_test_config(&dir);
+    let public_path = dir.join("TestBank_public.asc");
+    let mut answers = common::generation_script(&public_path);
+    answers.extend(["y", "退出程序"].map(str::to_string));
+
+    let output = common::run_script(&dir, &[], &answers);
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(stdout.contains("是否继续执行其他操作"), "{}", stdout);
+    assert!(stdout.contains("感谢使用ABU密钥生成器"), "{}", stdout);
+    assert!(public_path.is_file());
+    common::find_file(&dir, "_private_");
+    common::find_file(&dir, "_revocation_");
+}
+
+#[test]
+fn exhausted_script_fails_instead_of_waiting_for_input() {
+    let dir = common::temp_dir("session-exhausted");
+    common::write_test_config(&dir);
+    let public_path = dir.join("TestBank_public.asc");
+    // 在输入密码之前应答用完：不能退回到终端交互，也不能写出任何密钥文件
+    let answers = common::generation_script(&public_path)[..3].to_vec();
+
+    let output = common::run_script(&dir, &[], &answers);
+
+    assert!(!output.status.success());
+    assert!(!public_path.exists());
+    let leftovers: Vec<_> = std::fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .filter(|name| name.contains("_private_") || name.contains("_revocation_"))
+        .collect();
+    assert!(leftovers.is_empty(), "{:?}", leftovers);
+}
@@ -0,0 +1,64 @@
+//! `sign` 子命令：对 stdin 数据生成分离签名并写到 stdout
+
+mod common;
+
+use std::io::Write;
+use std::process::Stdio;
+
+#[test]
+fn stdin_data_signature_verifies() {
+    let dir = common::temp_dir("sign-stdin");
+    let key = common::generate_key(&dir);
+    let data = b"{\"from\": \"alpha\", \"to\": \"beta\", \"amount\": 100}\n";
+
+    let mut child = common::command(&dir)
+        .arg("sign")
+        .arg("--key")
+        .arg(&key.private)
+        .arg("--password-file")
+        .arg(&key.password_file)
+        .arg("--stdin-data")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(data).unwrap();
+    let output = child.wait_with_output().unwrap();
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(output.stdout.starts_with(b"-----BEGIN PGP SIGNATURE-----"));
+    assert!(common::verify_detached(&key.public, &output.stdout, data));
+    assert!(!common::verify_detached(&key.public, &output.stdout, b"tampered"));
+}
+
+#[test]
+fn repeated_inputs_are_signed_with_one_invocation() {
+    let dir = common::temp_dir("sign-files");
+    let key = common::generate_key(&dir);
+    let inputs: Vec<_> = (1..=3)
+        .map(|index| {
+            let path = dir.join(format!("tx{}.json", index));
+            std::fs::write(&path, format!("{{\"tx\": {}}}\n", index)).unwrap();
+            path
+        })
+        .collect();
+
+    let mut command = common::command(&dir);
+    command.arg("sign").arg("--key").arg(&key.private).arg("--password-file").arg(&key.password_file);
+    for input in &inputs {
+        command.arg("--in").arg(input);
+    }
+    let output = command.output().unwrap();
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{}", stderr);
+    assert!(stderr.contains("成功 3，失败 0"), "{}", stderr);
+    for input in &inputs {
+        let data = std::fs::read(input).unwrap();
+        let signature = std::fs::read(input.with_extension("json.asc")).unwrap();
+        assert!(common::verify_detached(&key.public, &signature, &data));
+    }
+    let first = std::fs::read(inputs[0].with_extension("json.asc")).unwrap();
+    assert!(!common::verify_detached(&key.public, &first, &std::fs::read(&inputs[1]).unwrap()));
+}
@@ -0,0 +1,34 @@
+//! `--verbose`：生成过程中各步骤的耗时输出到 stderr，默认关闭
+//!
+//! 需要 `test-ui` 构建：`cargo test --features test-ui --test verbose`
+
+#![cfg(feature = "test-ui")]
+
+mod common;
+
+/// 以脚本应答完成一次生成后退出，返回 stderr
+fn generate(name: &str, args: &[&str]) -> String {
+    let dir = common::temp_dir(name);
+    common::write_test_config(&dir);
+    let mut answers = common::generation_script(&dir.join("TestBank_public.asc"));
+    answers.push("n".to_string());
+
+    let output = common::run_script(&dir, args, &answers);
+    let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+    assert!(output.status.success(), "{}", stderr);
+    stderr
+}
+
+#[test]
+fn verbose_generation_reports_step_timings() {
+    let stderr = generate("verbose-on", &["--verbose"]);
+    for step in ["generate", "serialize (public)", "serialize (secret)", "derive", "encrypt"] {
+        assert!(stderr.contains(&format!("[timing] {}: ", step)), "缺少 {} 的耗时: {}", step, stderr);
+    }
+}
+
+#[test]
+fn timings_are_off_by_default() {
+    let stderr = generate("verbose-off", &[]);
+    assert!(!stderr.contains("[timing]"), "{}", stderr);
+}